@@ -0,0 +1,144 @@
+// src/api.rs
+// Object-safe client interface for dependency injection
+
+use crate::client::HttpClient;
+use crate::error::Result;
+use futures::future::BoxFuture;
+use reqwest::{Request, Response};
+use serde_json::Value;
+
+/// Object-safe view of an HTTP client
+///
+/// Application code can hold an `Arc<dyn HttpClientApi>` and receive a
+/// real `HttpClient` in production or a fake in unit tests. The JSON
+/// methods work on `serde_json::Value` to stay object-safe; use
+/// `serde_json::from_value` to get a typed result.
+pub trait HttpClientApi: Send + Sync {
+    /// Send a prepared request
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response>>;
+    
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>>;
+    
+    fn post<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>>;
+    
+    fn put<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>>;
+    
+    fn delete<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>>;
+    
+    /// Send a GET request and parse the response as JSON
+    fn get_json<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Value>>;
+    
+    /// Send a POST request with a JSON body and parse the JSON response
+    fn post_json<'a>(&'a self, url: &'a str, body: &'a Value) -> BoxFuture<'a, Result<Value>>;
+    
+    /// Send a PUT request with a JSON body and parse the JSON response
+    fn put_json<'a>(&'a self, url: &'a str, body: &'a Value) -> BoxFuture<'a, Result<Value>>;
+    
+    /// Send a DELETE request and parse the response as JSON
+    fn delete_json<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Value>>;
+}
+
+impl HttpClientApi for HttpClient {
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response>> {
+        Box::pin(HttpClient::execute(self, request))
+    }
+    
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(HttpClient::get(self, url))
+    }
+    
+    fn post<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(HttpClient::post(self, url))
+    }
+    
+    fn put<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(HttpClient::put(self, url))
+    }
+    
+    fn delete<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(HttpClient::delete(self, url))
+    }
+    
+    fn get_json<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(HttpClient::get_json(self, url))
+    }
+    
+    fn post_json<'a>(&'a self, url: &'a str, body: &'a Value) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(HttpClient::post_json(self, url, body))
+    }
+    
+    fn put_json<'a>(&'a self, url: &'a str, body: &'a Value) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(HttpClient::put_json(self, url, body))
+    }
+    
+    fn delete_json<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(HttpClient::delete_json(self, url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HttpError;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+    
+    struct FakeUsers;
+    
+    impl HttpClientApi for FakeUsers {
+        fn execute(&self, _request: Request) -> BoxFuture<'_, Result<Response>> {
+            Box::pin(async { Err(HttpError::Unknown("not faked".to_string())) })
+        }
+        
+        fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+            self.execute(Request::new(reqwest::Method::GET, url.parse().unwrap()))
+        }
+        
+        fn post<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+            self.get(url)
+        }
+        
+        fn put<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+            self.get(url)
+        }
+        
+        fn delete<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+            self.get(url)
+        }
+        
+        fn get_json<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<Value>> {
+            Box::pin(async { Ok(json!([{"name": "fake"}])) })
+        }
+        
+        fn post_json<'a>(&'a self, _url: &'a str, body: &'a Value) -> BoxFuture<'a, Result<Value>> {
+            Box::pin(async move { Ok(body.clone()) })
+        }
+        
+        fn put_json<'a>(&'a self, url: &'a str, body: &'a Value) -> BoxFuture<'a, Result<Value>> {
+            self.post_json(url, body)
+        }
+        
+        fn delete_json<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Value>> {
+            self.get_json(url)
+        }
+    }
+    
+    async fn first_user(api: Arc<dyn HttpClientApi>) -> Result<String> {
+        let users = api.get_json("/users").await?;
+        Ok(users[0]["name"].as_str().unwrap_or_default().to_string())
+    }
+    
+    #[tokio::test]
+    async fn test_client_and_fake_behind_trait_object() {
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::get("/users").respond_with(MockResponse::json(StatusCode::OK, &json!([{"name": "ada"}])).unwrap()),
+        );
+        let client = transport.client();
+        
+        assert_eq!(first_user(Arc::new(client)).await.unwrap(), "ada");
+        assert_eq!(first_user(Arc::new(FakeUsers)).await.unwrap(), "fake");
+    }
+}
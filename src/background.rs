@@ -0,0 +1,238 @@
+// src/background.rs
+// Fire-and-forget request queue
+
+use crate::batch::catch_panic;
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use crate::retry::RetryPolicy;
+use futures::StreamExt;
+use reqwest::{Request, Response, Url};
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
+
+/// Called with each background request's URL and result once it is done
+pub type CompletionCallback = Arc<dyn Fn(&Url, &Result<Response>) + Send + Sync>;
+
+/// Settings for `HttpClient::background`
+#[derive(Clone)]
+pub struct BackgroundQueue {
+    /// Requests that may wait to be sent; more are refused
+    pub capacity: usize,
+    /// Requests sent at once
+    pub concurrency: usize,
+    /// Retry policy for queued requests; the client's when `None`
+    pub retry: Option<RetryPolicy>,
+    pub on_complete: Option<CompletionCallback>,
+}
+
+impl BackgroundQueue {
+    /// Queue up to `capacity` requests and send them one at a time
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            concurrency: 1,
+            retry: None,
+            on_complete: None,
+        }
+    }
+    
+    /// Send up to `concurrency` queued requests at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+    
+    /// Retry queued requests with `retry` instead of the client's policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+    
+    /// Call `callback` when each request finishes, successfully or not
+    pub fn on_complete<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Url, &Result<Response>) + Send + Sync + 'static,
+    {
+        self.on_complete = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl fmt::Debug for BackgroundQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackgroundQueue")
+            .field("capacity", &self.capacity)
+            .field("concurrency", &self.concurrency)
+            .field("retry", &self.retry)
+            .field("on_complete", &self.on_complete.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    count: AtomicUsize,
+    rejected: AtomicU64,
+    idle: Notify,
+}
+
+impl Pending {
+    fn finish(&self) {
+        if self.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+/// Marks a queued request finished when dropped, even if its task panics
+struct Finish<'a>(&'a Pending);
+
+impl Drop for Finish<'_> {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
+
+/// Handle for queueing requests that are sent in the background
+///
+/// `send` never waits: when the queue is full the request is refused, so
+/// a slow or unreachable server can't hold up the caller or grow memory.
+/// Clones share the queue, which is drained and stopped once every clone
+/// has been dropped.
+#[derive(Debug, Clone)]
+pub struct BackgroundSender {
+    queue: mpsc::Sender<Request>,
+    pending: Arc<Pending>,
+}
+
+impl BackgroundSender {
+    pub(crate) fn spawn(client: HttpClient, options: BackgroundQueue) -> Self {
+        let (queue, mut receiver) = mpsc::channel::<Request>(options.capacity);
+        let pending = Arc::new(Pending::default());
+        let retry = options.retry.unwrap_or_else(|| client.config().retry_policy());
+        
+        let worker_pending = pending.clone();
+        tokio::spawn(async move {
+            let requests = futures::stream::poll_fn(|cx| receiver.poll_recv(cx));
+            requests
+                .for_each_concurrent(options.concurrency, |request| {
+                    let (client, retry, on_complete) = (&client, &retry, &options.on_complete);
+                    let pending = &worker_pending;
+                    async move {
+                        let _finish = Finish(pending);
+                        let url = request.url().clone();
+                        let result = catch_panic(client.execute_request_with_retry(request, retry)).await;
+                        if let Err(e) = &result {
+                            log::debug!("Background request to {} failed: {}", url, e);
+                        }
+                        if let Some(on_complete) = on_complete {
+                            let callback = AssertUnwindSafe(|| on_complete(&url, &result));
+                            if std::panic::catch_unwind(callback).is_err() {
+                                log::warn!("Completion callback for {} panicked", url);
+                            }
+                        }
+                    }
+                })
+                .await;
+        });
+        
+        Self { queue, pending }
+    }
+    
+    /// Queue `request`, failing with `QueueFull` if there is no room
+    pub fn send(&self, request: Request) -> Result<()> {
+        let url = request.url().to_string();
+        self.pending.count.fetch_add(1, Ordering::SeqCst);
+        self.queue.try_send(request).map_err(|_| {
+            self.pending.finish();
+            self.pending.rejected.fetch_add(1, Ordering::Relaxed);
+            HttpError::QueueFull(url)
+        })
+    }
+    
+    /// Requests queued or in flight
+    pub fn pending(&self) -> usize {
+        self.pending.count.load(Ordering::SeqCst)
+    }
+    
+    /// Requests refused because the queue was full
+    pub fn rejected(&self) -> u64 {
+        self.pending.rejected.load(Ordering::Relaxed)
+    }
+    
+    /// Wait until every queued request has finished
+    pub async fn flush(&self) {
+        loop {
+            let idle = self.pending.idle.notified();
+            if self.pending() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+impl HttpClient {
+    /// Start a background queue sending requests through this client
+    ///
+    /// Must be called inside a tokio runtime.
+    pub fn background(&self, options: BackgroundQueue) -> BackgroundSender {
+        BackgroundSender::spawn(self.clone(), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockTransport};
+    use reqwest::Method;
+    use std::sync::Mutex;
+    
+    #[tokio::test]
+    async fn test_background_sender() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::post("/beacon"));
+        let client = transport.client();
+        
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let options = BackgroundQueue::new(2).on_complete({
+            let completed = completed.clone();
+            move |url, result| completed.lock().unwrap().push((url.path().to_string(), result.is_ok()))
+        });
+        let sender = client.background(options);
+        let beacon = || client.request(Method::POST, "/beacon").unwrap().build().unwrap();
+        
+        // the worker hasn't run yet, so the third request finds the queue full
+        sender.send(beacon()).unwrap();
+        sender.send(beacon()).unwrap();
+        assert!(matches!(sender.send(beacon()), Err(HttpError::QueueFull(_))));
+        assert_eq!(sender.rejected(), 1);
+        
+        sender.flush().await;
+        assert_eq!(sender.pending(), 0);
+        assert_eq!(transport.requests().len(), 2);
+        assert_eq!(*completed.lock().unwrap(), [("/beacon".to_string(), true), ("/beacon".to_string(), true)]);
+        
+        sender.send(client.request(Method::POST, "/unmocked").unwrap().build().unwrap()).unwrap();
+        sender.flush().await;
+        assert_eq!(completed.lock().unwrap()[2], ("/unmocked".to_string(), false));
+    }
+    
+    #[tokio::test]
+    async fn test_background_sender_survives_panicking_callback() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::post("/beacon"));
+        let client = transport.client();
+        let sender = client.background(BackgroundQueue::new(4).on_complete(|_, _| panic!("callback failed")));
+        
+        for _ in 0..2 {
+            sender.send(client.request(Method::POST, "/beacon").unwrap().build().unwrap()).unwrap();
+        }
+        sender.flush().await;
+        assert_eq!(sender.pending(), 0);
+        assert_eq!(transport.requests().len(), 2);
+    }
+}
@@ -0,0 +1,419 @@
+// src/batch.rs
+// Helpers for sending many requests at once
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use crate::retry::RetryPolicy;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, Stream, StreamExt};
+use reqwest::{Request, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::Any;
+use std::future::Future;
+use std::ops::Range;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+/// Run `future`, turning a panic into an error for this item only
+pub(crate) async fn catch_panic<T, F>(future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    AssertUnwindSafe(future)
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|panic| Err(HttpError::Unknown(format!("Request panicked: {}", panic_message(&panic)))))
+}
+
+fn panic_message(panic: &Box<dyn Any + Send>) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// A request from a scatter-gather batch that got a 2xx response
+#[derive(Debug)]
+pub struct BatchSuccess {
+    /// Position of the request in the batch
+    pub index: usize,
+    pub url: Url,
+    pub duration: Duration,
+    pub response: Response,
+}
+
+/// A request from a scatter-gather batch that failed or got a non-2xx response
+#[derive(Debug)]
+pub struct BatchFailure {
+    /// Position of the request in the batch
+    pub index: usize,
+    pub url: Url,
+    pub duration: Duration,
+    pub error: HttpError,
+}
+
+/// Outcome of `HttpClient::scatter_gather`, both lists in batch order
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub successes: Vec<BatchSuccess>,
+    pub failures: Vec<BatchFailure>,
+}
+
+impl BatchReport {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+    
+    /// Total number of requests in the batch
+    pub fn len(&self) -> usize {
+        self.successes.len() + self.failures.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A chunk of `HttpClient::post_json_chunked` that could not be posted
+#[derive(Debug)]
+pub struct ChunkFailure {
+    /// Position of the chunk among all chunks
+    pub index: usize,
+    /// The items the chunk covered
+    pub items: Range<usize>,
+    pub error: HttpError,
+}
+
+/// Outcome of `HttpClient::post_json_chunked`, both lists in chunk order
+#[derive(Debug)]
+pub struct ChunkedPost<R> {
+    /// The decoded response of every chunk that succeeded, with its index
+    pub responses: Vec<(usize, R)>,
+    pub failures: Vec<ChunkFailure>,
+}
+
+impl<R> ChunkedPost<R> {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// How `HttpClient::pipeline` sends its requests
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    concurrency: usize,
+    retry: Option<RetryPolicy>,
+}
+
+impl Pipeline {
+    /// Keep up to `concurrency` requests in flight (at least one)
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            retry: None,
+        }
+    }
+    
+    /// Retry each item with `retry` instead of the client's policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+    
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+}
+
+impl HttpClient {
+    /// GET every URL and decode the responses as JSON
+    ///
+    /// At most `concurrency` requests are in flight at once (at least one).
+    /// Results come back in the order of `urls`, and a failing or panicking
+    /// request only fails its own entry.
+    pub async fn get_json_many<T, I, S>(&self, urls: I, concurrency: usize) -> Vec<Result<T>>
+    where
+        T: DeserializeOwned,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        futures::stream::iter(urls)
+            .map(|url| async move { catch_panic(self.get_json::<T>(url.as_ref())).await })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+    
+    /// GET the same resource from several URLs and keep the first 2xx
+    ///
+    /// All requests start at once; when one succeeds the others are
+    /// dropped, which cancels them. Non-2xx responses count as failures,
+    /// and if every URL fails the error is `AllFailed` with each URL's
+    /// error in input order.
+    pub async fn first_successful<S: AsRef<str>>(&self, urls: &[S]) -> Result<Response> {
+        if urls.is_empty() {
+            return Err(HttpError::ConfigError("first_successful needs at least one URL".to_string()));
+        }
+        let mut racing: FuturesUnordered<_> = urls
+            .iter()
+            .enumerate()
+            .map(|(index, url)| async move { (index, catch_panic(self.get_successful(url.as_ref())).await) })
+            .collect();
+        
+        let mut failures = Vec::with_capacity(urls.len());
+        while let Some((index, result)) = racing.next().await {
+            match result {
+                Ok(response) => return Ok(response),
+                Err(e) => failures.push((index, e)),
+            }
+        }
+        failures.sort_by_key(|(index, _)| *index);
+        Err(HttpError::AllFailed(
+            failures.into_iter().map(|(index, e)| (urls[index].as_ref().to_string(), e)).collect(),
+        ))
+    }
+    
+    /// Send unrelated requests together and sort the results into a report
+    ///
+    /// At most `concurrency` requests are in flight at once (at least one).
+    /// Every request ends up in exactly one list of the report, along with
+    /// its URL and how long it took; non-2xx responses are failures.
+    pub async fn scatter_gather<I>(&self, requests: I, concurrency: usize) -> BatchReport
+    where
+        I: IntoIterator<Item = Request>,
+    {
+        let clock = self.config().clock.clone();
+        let mut results: Vec<_> = futures::stream::iter(requests.into_iter().enumerate())
+            .map(|(index, request)| {
+                let clock = clock.clone();
+                async move {
+                    let url = request.url().clone();
+                    let started = clock.now();
+                    let result = catch_panic(async { successful(self.execute(request).await?).await }).await;
+                    (index, url, clock.now().saturating_duration_since(started), result)
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        results.sort_by_key(|(index, ..)| *index);
+        
+        let mut report = BatchReport::default();
+        for (index, url, duration, result) in results {
+            match result {
+                Ok(response) => report.successes.push(BatchSuccess { index, url, duration, response }),
+                Err(error) => report.failures.push(BatchFailure { index, url, duration, error }),
+            }
+        }
+        report
+    }
+    
+    /// POST `items` as JSON arrays of at most `chunk_size` items each
+    ///
+    /// Chunks are sent one after another; see `post_json_chunked_concurrent`
+    /// to send several at once. A failed chunk doesn't stop the others.
+    pub async fn post_json_chunked<T, R>(&self, url: &str, items: &[T], chunk_size: usize) -> ChunkedPost<R>
+    where
+        T: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        self.post_json_chunked_concurrent(url, items, chunk_size, 1).await
+    }
+    
+    /// Like `post_json_chunked`, with up to `concurrency` chunks in flight
+    pub async fn post_json_chunked_concurrent<T, R>(
+        &self,
+        url: &str,
+        items: &[T],
+        chunk_size: usize,
+        concurrency: usize,
+    ) -> ChunkedPost<R>
+    where
+        T: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let chunk_size = chunk_size.max(1);
+        let mut results: Vec<_> = futures::stream::iter(items.chunks(chunk_size).enumerate())
+            .map(|(index, chunk)| async move { (index, catch_panic(self.post_json::<_, R>(url, &chunk)).await) })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        results.sort_by_key(|(index, _)| *index);
+        
+        let mut report = ChunkedPost { responses: Vec::new(), failures: Vec::new() };
+        for (index, result) in results {
+            match result {
+                Ok(response) => report.responses.push((index, response)),
+                Err(error) => {
+                    let start = index * chunk_size;
+                    let items = start..(start + chunk_size).min(items.len());
+                    report.failures.push(ChunkFailure { index, items, error });
+                }
+            }
+        }
+        report
+    }
+    
+    async fn get_successful(&self, url: &str) -> Result<Response> {
+        successful(self.get(url).await?).await
+    }
+    
+    /// Send a stream of requests, yielding each result as it completes
+    ///
+    /// Every request is paired with a key of the caller's choosing, such as
+    /// the record it was built from, and the key comes back with its
+    /// result. Requests are pulled from `requests` only as slots free up,
+    /// so arbitrarily long streams run in bounded memory. Results arrive in
+    /// completion order, not input order.
+    pub fn pipeline<'a, K, S>(
+        &'a self,
+        requests: S,
+        pipeline: Pipeline,
+    ) -> impl Stream<Item = (K, Result<Response>)> + 'a
+    where
+        K: 'a,
+        S: Stream<Item = (K, Request)> + 'a,
+    {
+        let retry = pipeline.retry.unwrap_or_else(|| self.config().retry_policy());
+        requests
+            .map(move |(key, request)| {
+                let retry = retry.clone();
+                async move { (key, catch_panic(self.execute_request_with_retry(request, &retry)).await) }
+            })
+            .buffer_unordered(pipeline.concurrency)
+    }
+}
+
+/// Turn a non-2xx response into a `ResponseError`
+async fn successful(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(HttpError::ResponseError { status, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
+    
+    #[tokio::test]
+    async fn test_get_json_many_keeps_order() {
+        let transport = MockTransport::new();
+        let found = MockResponse::json(StatusCode::OK, &json!({"id": "{{id}}"})).unwrap().templated();
+        transport.expect(Mock::get("/items/2").respond_with(MockResponse::new(StatusCode::NOT_FOUND)));
+        transport.expect(Mock::get("/items/{id}").respond_with(found));
+        let client = transport.client();
+        
+        let results = client.get_json_many::<Value, _, _>(["/items/1", "/items/2", "/items/3"], 0).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap()["id"], "1");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap()["id"], "3");
+    }
+    
+    #[tokio::test]
+    async fn test_pipeline_retries_and_pairs_keys() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::get("/flaky").respond_with(MockResponse::new(StatusCode::SERVICE_UNAVAILABLE)));
+        transport.expect(Mock::get("/{path}"));
+        let client = transport.client();
+        
+        let requests = futures::stream::iter(["flaky", "a", "b"])
+            .map(|path| (path, client.request(reqwest::Method::GET, path).unwrap().build().unwrap()));
+        let pipeline = Pipeline::new(2).with_retry(RetryPolicy::new(1, std::time::Duration::ZERO));
+        let mut results: Vec<_> = client
+            .pipeline(requests, pipeline)
+            .map(|(path, result)| (path, result.unwrap().status()))
+            .collect()
+            .await;
+        results.sort();
+        
+        assert_eq!(results, [("a", StatusCode::OK), ("b", StatusCode::OK), ("flaky", StatusCode::SERVICE_UNAVAILABLE)]);
+        // the flaky request was retried once
+        assert_eq!(transport.requests().len(), 4);
+    }
+    
+    #[tokio::test]
+    async fn test_first_successful() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::get("/down").respond_with(MockResponse::new(StatusCode::BAD_GATEWAY)));
+        transport.expect(Mock::get("/up"));
+        let client = transport.client();
+        
+        let response = client.first_successful(&["/down", "/up"]).await.unwrap();
+        assert_eq!(response.url().path(), "/up");
+        
+        match client.first_successful(&["/down", "/missing"]).await {
+            Err(HttpError::AllFailed(failures)) => {
+                assert_eq!(failures[0].0, "/down");
+                assert!(matches!(failures[0].1, HttpError::ResponseError { status: StatusCode::BAD_GATEWAY, .. }));
+                assert_eq!(failures[1].0, "/missing");
+            }
+            other => panic!("expected AllFailed, got {:?}", other),
+        }
+        assert!(client.first_successful::<&str>(&[]).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_scatter_gather_report() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::post("/orders").respond_with(MockResponse::new(StatusCode::CREATED)));
+        transport.expect(Mock::get("/stock").respond_with(MockResponse::new(StatusCode::CONFLICT)));
+        transport.expect(Mock::get("/user"));
+        let client = transport.client();
+        
+        let requests = [
+            (reqwest::Method::POST, "/orders"),
+            (reqwest::Method::GET, "/stock"),
+            (reqwest::Method::GET, "/user"),
+            (reqwest::Method::DELETE, "/unmocked"),
+        ]
+        .map(|(method, path)| client.request(method, path).unwrap().build().unwrap());
+        let report = client.scatter_gather(requests, 2).await;
+        
+        assert_eq!(report.len(), 4);
+        assert!(!report.is_complete());
+        let successes: Vec<_> = report.successes.iter().map(|success| (success.index, success.url.path())).collect();
+        assert_eq!(successes, [(0, "/orders"), (2, "/user")]);
+        assert_eq!(report.failures[0].index, 1);
+        assert!(matches!(report.failures[0].error, HttpError::ResponseError { status: StatusCode::CONFLICT, .. }));
+        assert_eq!(report.failures[1].url.path(), "/unmocked");
+        assert!(matches!(report.failures[1].error, HttpError::ConfigError(_)));
+    }
+    
+    #[tokio::test]
+    async fn test_post_json_chunked() {
+        let transport = MockTransport::new();
+        let rejected = MockResponse::new(StatusCode::PAYLOAD_TOO_LARGE);
+        transport.expect(Mock::post("/bulk").with_json_body(&json!([3, 4])).unwrap().respond_with(rejected));
+        let accepted = MockResponse::json(StatusCode::OK, &json!({"indexed": 2})).unwrap();
+        transport.expect(Mock::post("/bulk").respond_with(accepted));
+        let client = transport.client();
+        
+        let report = client.post_json_chunked_concurrent::<_, Value>("/bulk", &[1, 2, 3, 4, 5], 2, 3).await;
+        assert_eq!(transport.requests().len(), 3);
+        let indexes: Vec<_> = report.responses.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indexes, [0, 2]);
+        assert_eq!(report.responses[0].1["indexed"], 2);
+        assert!(!report.is_complete());
+        assert_eq!(report.failures[0].index, 1);
+        assert_eq!(report.failures[0].items, 2..4);
+        
+        let report = client.post_json_chunked::<_, Value>("/bulk", &[1, 2, 5], 2).await;
+        assert!(report.is_complete());
+        assert_eq!(report.responses.len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_catch_panic() {
+        let result: Result<()> = catch_panic(async { panic!("boom") }).await;
+        assert!(matches!(result, Err(HttpError::Unknown(message)) if message.contains("boom")));
+    }
+}
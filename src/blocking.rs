@@ -4,21 +4,41 @@
 // This module provides a blocking version of the HTTP client for use cases
 // where async/await is not suitable or available.
 
+use crate::client::{
+    normalize_path_prefix, resolve_request_url, ClientConfig, CrossHostPolicy, HttpClient, PathEncoding,
+    RequestUrlConfig, TrailingSlash,
+};
+use crate::clock::{system_clock, Clock};
+use crate::content_type::{guess_content_type, ContentType};
+use crate::cookies::{CookieStore, MemoryCookieStore, ReqwestCookieStore};
 use crate::error::{HttpError, Result};
-use reqwest::blocking::{Client, RequestBuilder, Response};
+use crate::rate_limit::{AdaptiveThrottle, RateLimit, RateLimiter, RoutePattern};
+use crate::retry::{Outcome, RetryPolicy};
+use crate::tls::ClientIdentity;
+use crate::upload::ChunkedFile;
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::blocking::{Client, Request, RequestBuilder, Response};
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue},
-    Method,
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Method, Url,
 };
 
 
 use serde::{de::DeserializeOwned, Serialize};
-use std::{collections::HashMap, fmt, time::Duration};
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
 
 /// Configuration for the blocking HTTP client
 #[derive(Debug, Clone)]
 pub struct BlockingClientConfig {
     pub base_url: Option<String>,
+    pub path_prefix: Option<String>,
+    pub path_encoding: PathEncoding,
+    pub trailing_slash: TrailingSlash,
+    pub collapse_slashes: bool,
     pub timeout: Option<Duration>,
     pub default_headers: HeaderMap,
     pub follow_redirects: bool,
@@ -26,12 +46,23 @@ pub struct BlockingClientConfig {
     pub connect_timeout: Option<Duration>,
     pub pool_idle_timeout: Option<Duration>,
     pub pool_max_idle_per_host: Option<usize>,
+    pub user_agent: String,
+    pub identity: Option<ClientIdentity>,
+    pub cookie_store: Option<Arc<dyn CookieStore>>,
+    pub retry: RetryPolicy,
+    pub rate_limits: Vec<(RoutePattern, RateLimit)>,
+    pub throttle: Option<AdaptiveThrottle>,
+    pub clock: Arc<dyn Clock>,
 }
 
 impl Default for BlockingClientConfig {
     fn default() -> Self {
         Self {
             base_url: None,
+            path_prefix: None,
+            path_encoding: PathEncoding::Auto,
+            trailing_slash: TrailingSlash::Preserve,
+            collapse_slashes: false,
             timeout: Some(Duration::from_secs(30)),
             default_headers: HeaderMap::new(),
             follow_redirects: true,
@@ -39,6 +70,13 @@ impl Default for BlockingClientConfig {
             connect_timeout: Some(Duration::from_secs(10)),
             pool_idle_timeout: Some(Duration::from_secs(90)),
             pool_max_idle_per_host: Some(10),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
+            identity: None,
+            cookie_store: None,
+            retry: RetryPolicy::default(),
+            rate_limits: Vec::new(),
+            throttle: None,
+            clock: system_clock(),
         }
     }
 }
@@ -55,6 +93,30 @@ impl BlockingClientConfig {
         self
     }
     
+    /// Put `prefix` in front of every relative request path
+    pub fn with_path_prefix(mut self, prefix: &str) -> Result<Self> {
+        self.path_prefix = normalize_path_prefix(prefix)?;
+        Ok(self)
+    }
+    
+    /// Choose whether relative paths are percent-encoded automatically
+    pub fn with_path_encoding(mut self, encoding: PathEncoding) -> Self {
+        self.path_encoding = encoding;
+        self
+    }
+    
+    /// Add or remove the trailing slash of joined request paths
+    pub fn with_trailing_slash(mut self, policy: TrailingSlash) -> Self {
+        self.trailing_slash = policy;
+        self
+    }
+    
+    /// Collapse runs of `/` in joined request paths
+    pub fn with_collapse_slashes(mut self, collapse: bool) -> Self {
+        self.collapse_slashes = collapse;
+        self
+    }
+    
     /// Set the request timeout
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
@@ -97,6 +159,197 @@ impl BlockingClientConfig {
         self.connect_timeout = Some(timeout);
         self
     }
+    
+    /// Identify the application in the `User-Agent` header
+    ///
+    /// Produces `{app}/{version} rusty-http-client/{VERSION}`.
+    pub fn with_user_agent(mut self, app: impl fmt::Display, version: impl fmt::Display) -> Self {
+        self.user_agent = format!("{}/{} {}", app, version, crate::DEFAULT_USER_AGENT);
+        self
+    }
+    
+    /// Present a client certificate for mutual TLS
+    pub fn with_identity(mut self, identity: ClientIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+    
+    /// Present a client certificate from a DER-encoded PKCS#12 archive
+    #[cfg(feature = "native-tls")]
+    pub fn with_pkcs12_identity<B: AsRef<[u8]>>(self, der: B, password: &str) -> Result<Self> {
+        Ok(self.with_identity(ClientIdentity::from_pkcs12_der(der.as_ref(), password)?))
+    }
+    
+    /// Present a client certificate from a PEM certificate chain and PKCS#8 private key
+    #[cfg(feature = "native-tls")]
+    pub fn with_pkcs8_identity<C, K>(self, cert_pem: C, key_pem: K) -> Result<Self>
+    where
+        C: AsRef<[u8]>,
+        K: AsRef<[u8]>,
+    {
+        Ok(self.with_identity(ClientIdentity::from_pkcs8_pem(cert_pem.as_ref(), key_pem.as_ref())?))
+    }
+    
+    /// Replace the `User-Agent` header entirely
+    pub fn with_raw_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+    
+    /// Store cookies from responses and send them on later requests
+    pub fn with_cookies(mut self, enabled: bool) -> Self {
+        self.cookie_store = enabled.then(|| Arc::new(MemoryCookieStore::new()) as Arc<dyn CookieStore>);
+        self
+    }
+    
+    /// Keep cookies in a custom store, e.g. a `JsonFileCookieStore`
+    pub fn with_cookie_store(mut self, store: Arc<dyn CookieStore>) -> Self {
+        self.cookie_store = Some(store);
+        self
+    }
+    
+    /// Retry failed requests with the same rules as the async client
+    ///
+    /// See `RetryPolicy` for what is retried; the backoff doubles after each
+    /// attempt.
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.retry.max_retries = max_retries;
+        self.retry.backoff = backoff;
+        self
+    }
+    
+    /// Replace the whole retry policy
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+    
+    /// Limit the rate of requests matching a host or route pattern
+    ///
+    /// Works like `ClientConfig::with_rate_limit`, except that the calling
+    /// thread sleeps while it waits for a token.
+    pub fn with_rate_limit(mut self, pattern: &str, limit: RateLimit) -> Result<Self> {
+        self.rate_limits.push((RoutePattern::parse(pattern)?, limit));
+        Ok(self)
+    }
+    
+    /// Pace requests by the quota headers servers send back
+    ///
+    /// The throttle may be cloned from one used by an async client so both
+    /// share the same budgets.
+    pub fn with_adaptive_throttle(mut self, throttle: AdaptiveThrottle) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+    
+    /// Read time and sleep through `clock` for retry backoff and rate limits
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+/// Carry over the settings both clients support
+///
+/// Settings only the async client has, such as endpoints, middleware, the
+/// TLS backend choice and HTTP/2 tuning, are left out. Fails with
+/// `ConfigError` when a security setting the blocking client can't enforce
+/// is set: custom root certificates, SPKI pins, minimum or maximum TLS
+/// versions, proxies, offline mode, SSRF protection, a cross-host policy
+/// other than `Allow`, or host profiles (which may carry credentials).
+impl TryFrom<&ClientConfig> for BlockingClientConfig {
+    type Error = HttpError;
+    
+    fn try_from(config: &ClientConfig) -> Result<Self> {
+        let unsupported = [
+            (!config.root_certificates.is_empty(), "custom root certificates"),
+            (!config.spki_pins.is_empty(), "SPKI pins"),
+            (config.min_tls_version.is_some(), "a minimum TLS version"),
+            (config.max_tls_version.is_some(), "a maximum TLS version"),
+            (
+                !config.proxies.is_empty() || config.env_proxy.is_some() || !config.proxy_routes.is_empty(),
+                "proxies",
+            ),
+            (config.offline, "offline mode"),
+            (config.block_internal_addresses, "SSRF protection"),
+            (config.cross_host_policy != CrossHostPolicy::Allow, "a cross-host policy"),
+            (!config.host_profiles.is_empty(), "host profiles"),
+        ];
+        if let Some((_, setting)) = unsupported.iter().find(|(set, _)| *set) {
+            return Err(HttpError::ConfigError(format!(
+                "{} can't be carried over to a blocking client",
+                setting
+            )));
+        }
+        
+        Ok(Self {
+            base_url: config.base_url.as_ref().map(|url| url.to_string()),
+            path_prefix: config.path_prefix.clone(),
+            path_encoding: config.path_encoding,
+            trailing_slash: config.trailing_slash,
+            collapse_slashes: config.collapse_slashes,
+            timeout: config.timeout,
+            default_headers: config.default_headers.clone(),
+            follow_redirects: config.follow_redirects,
+            max_redirects: config.max_redirects,
+            connect_timeout: config.connect_timeout,
+            pool_idle_timeout: config.pool_idle_timeout,
+            pool_max_idle_per_host: config.pool_max_idle_per_host,
+            user_agent: config.user_agent.clone(),
+            identity: config.identity.clone(),
+            cookie_store: config.cookie_store.clone(),
+            retry: config.retry_policy(),
+            rate_limits: config.rate_limits.clone(),
+            throttle: None,
+            clock: config.clock.clone(),
+        })
+    }
+}
+
+/// Carry over the blocking settings, validating the base URL
+///
+/// An adaptive throttle is not carried over; add it to the async client
+/// as middleware instead.
+impl TryFrom<&BlockingClientConfig> for ClientConfig {
+    type Error = HttpError;
+    
+    fn try_from(config: &BlockingClientConfig) -> Result<Self> {
+        let mut converted = ClientConfig {
+            timeout: config.timeout,
+            default_headers: config.default_headers.clone(),
+            follow_redirects: config.follow_redirects,
+            max_redirects: config.max_redirects,
+            connect_timeout: config.connect_timeout,
+            pool_idle_timeout: config.pool_idle_timeout,
+            pool_max_idle_per_host: config.pool_max_idle_per_host,
+            user_agent: config.user_agent.clone(),
+            identity: config.identity.clone(),
+            cookie_store: config.cookie_store.clone(),
+            retry: config.retry.clone(),
+            rate_limits: config.rate_limits.clone(),
+            clock: config.clock.clone(),
+            path_prefix: config.path_prefix.clone(),
+            path_encoding: config.path_encoding,
+            trailing_slash: config.trailing_slash,
+            collapse_slashes: config.collapse_slashes,
+            ..ClientConfig::default()
+        };
+        if let Some(base_url) = &config.base_url {
+            converted = converted.with_base_url(base_url)?;
+        }
+        Ok(converted)
+    }
+}
+
+impl HttpClient {
+    /// Build a blocking client with the same settings
+    ///
+    /// See `TryFrom<&ClientConfig> for BlockingClientConfig` for what is
+    /// carried over and which settings make the conversion fail. A shared
+    /// cookie store stays shared.
+    pub fn to_blocking(&self) -> Result<BlockingHttpClient> {
+        BlockingHttpClient::with_config(BlockingClientConfig::try_from(&*self.config())?)
+    }
 }
 
 /// Blocking HTTP client struct
@@ -104,6 +357,7 @@ impl BlockingClientConfig {
 pub struct BlockingHttpClient {
     client: Client,
     config: BlockingClientConfig,
+    route_limiters: Arc<Vec<(RoutePattern, RateLimiter)>>,
 }
 
 impl fmt::Debug for BlockingHttpClient {
@@ -123,17 +377,31 @@ impl Default for BlockingHttpClient {
 impl BlockingHttpClient {
     /// Create a new blocking HTTP client with default settings
     pub fn new() -> Self {
-        let config = BlockingClientConfig::default();
-        let client = Self::build_reqwest_client(&config).unwrap();
-        
-        Self { client, config }
+        Self::with_config(BlockingClientConfig::default()).unwrap()
     }
     
     /// Create a new blocking HTTP client with custom configuration
     pub fn with_config(config: BlockingClientConfig) -> Result<Self> {
         let client = Self::build_reqwest_client(&config)?;
+        let route_limiters = config
+            .rate_limits
+            .iter()
+            .map(|(pattern, limit)| (pattern.clone(), RateLimiter::with_clock(*limit, config.clock.clone())))
+            .collect();
         
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            route_limiters: Arc::new(route_limiters),
+        })
+    }
+    
+    /// Build an async client with the same settings
+    ///
+    /// See `TryFrom<&BlockingClientConfig> for ClientConfig` for what is
+    /// carried over. A shared cookie store stays shared.
+    pub fn to_async(&self) -> Result<HttpClient> {
+        HttpClient::with_config(ClientConfig::try_from(&self.config)?)
     }
     
     /// Create a new blocking HTTP client with a base URL
@@ -163,6 +431,7 @@ impl BlockingHttpClient {
         }
         
         builder = builder
+            .user_agent(config.user_agent.as_str())
             .redirect(if config.follow_redirects {
                 reqwest::redirect::Policy::limited(config.max_redirects as usize)
             } else {
@@ -170,23 +439,40 @@ impl BlockingHttpClient {
             })
             .default_headers(config.default_headers.clone());
         
+        if let Some(identity) = &config.identity {
+            builder = match identity.backend() {
+                #[cfg(feature = "native-tls")]
+                crate::tls::TlsBackend::NativeTls => builder.use_native_tls(),
+                #[cfg(feature = "rustls")]
+                crate::tls::TlsBackend::Rustls => builder.use_rustls_tls(),
+                #[allow(unreachable_patterns)]
+                _ => builder,
+            };
+            builder = builder.identity(identity.as_reqwest().clone());
+        }
+        
+        if let Some(store) = &config.cookie_store {
+            builder = builder.cookie_provider(Arc::new(ReqwestCookieStore(store.clone())));
+        }
+        
         builder.build().map_err(HttpError::from)
     }
     
-    /// Build the complete URL with the base URL
+    /// Build the complete URL with the base URL, joined like the async client's
     fn build_url(&self, url: &str) -> Result<String> {
         match &self.config.base_url {
-            Some(base) if !url.starts_with("http") => {
-                let mut full_url = base.clone();
-                if !base.ends_with('/') && !url.starts_with('/') {
-                    full_url.push('/');
-                } else if base.ends_with('/') && url.starts_with('/') {
-                    full_url.pop();
-                }
-                full_url.push_str(url);
-                Ok(full_url)
+            Some(base) => {
+                let base = Url::parse(base)
+                    .map_err(|e| HttpError::UrlError(format!("Invalid base URL '{}': {}", base, e)))?;
+                let config = RequestUrlConfig {
+                    path_prefix: self.config.path_prefix.as_deref(),
+                    path_encoding: self.config.path_encoding,
+                    trailing_slash: self.config.trailing_slash,
+                    collapse_slashes: self.config.collapse_slashes,
+                };
+                Ok(resolve_request_url(&base, config, url)?.to_string())
             }
-            _ => Ok(url.to_string()),
+            None => Ok(url.to_string()),
         }
     }
     
@@ -197,11 +483,56 @@ impl BlockingHttpClient {
         Ok(builder)
     }
     
+    /// Send a request built with `request`, retrying per the retry policy
+    ///
+    /// Each attempt first waits for the rate limits and adaptive throttle.
+    pub fn execute(&self, mut request: Request) -> Result<Response> {
+        let policy = &self.config.retry;
+        let mut attempt = 0;
+        
+        loop {
+            // bodies that cannot be cloned are only sent once
+            let retry = if attempt < policy.max_retries { request.try_clone() } else { None };
+            let idempotent = policy.is_idempotent(request.method(), request.headers());
+            let url = request.url().clone();
+            self.wait_for_rate_limits(&url);
+            
+            let result = self.client.execute(request).map_err(HttpError::from);
+            if let (Some(throttle), Ok(response), Some(host)) = (&self.config.throttle, &result, url.host_str()) {
+                throttle.observe(host, response.status(), response.headers());
+            }
+            let outcome = match &result {
+                Ok(response) => Outcome::Response(response.status(), response.headers()),
+                Err(e) => Outcome::Error(e),
+            };
+            let (next, delay) = match (retry, policy.delay(attempt, idempotent, outcome)) {
+                (Some(next), Some(delay)) => (next, delay),
+                _ => return result,
+            };
+            
+            attempt += 1;
+            log::debug!("Retrying {} in {:?} (attempt {})", url, delay, attempt + 1);
+            self.config.clock.sleep_blocking(delay);
+            request = next;
+        }
+    }
+    
+    fn wait_for_rate_limits(&self, url: &reqwest::Url) {
+        if let Some((_, limiter)) = self.route_limiters.iter().find(|(pattern, _)| pattern.matches(url)) {
+            limiter.acquire_blocking();
+        }
+        if let (Some(throttle), Some(host)) = (&self.config.throttle, url.host_str()) {
+            throttle.wait_blocking(host);
+        }
+    }
+    
+    fn send(&self, builder: RequestBuilder) -> Result<Response> {
+        self.execute(builder.build()?)
+    }
+    
     /// Send a GET request
     pub fn get(&self, url: &str) -> Result<Response> {
-        self.request(Method::GET, url)?
-            .send()
-            .map_err(HttpError::from)
+        self.send(self.request(Method::GET, url)?)
     }
     
     /// Send a GET request and deserialize the response as JSON
@@ -212,9 +543,7 @@ impl BlockingHttpClient {
     
     /// Send a POST request
     pub fn post(&self, url: &str) -> Result<Response> {
-        self.request(Method::POST, url)?
-            .send()
-            .map_err(HttpError::from)
+        self.send(self.request(Method::POST, url)?)
     }
     
     /// Send a POST request with a JSON body
@@ -223,19 +552,15 @@ impl BlockingHttpClient {
         url: &str,
         body: &T,
     ) -> Result<R> {
-        let response = self.request(Method::POST, url)?
-            .json(body)
-            .send()
-            .map_err(HttpError::from)?;
+        let builder = self.request(Method::POST, url)?.json(body);
+        let response = self.send(builder)?;
         
         self.process_json_response(response)
     }
     
     /// Send a PUT request
     pub fn put(&self, url: &str) -> Result<Response> {
-        self.request(Method::PUT, url)?
-            .send()
-            .map_err(HttpError::from)
+        self.send(self.request(Method::PUT, url)?)
     }
     
     /// Send a PUT request with a JSON body
@@ -244,19 +569,15 @@ impl BlockingHttpClient {
         url: &str,
         body: &T,
     ) -> Result<R> {
-        let response = self.request(Method::PUT, url)?
-            .json(body)
-            .send()
-            .map_err(HttpError::from)?;
+        let builder = self.request(Method::PUT, url)?.json(body);
+        let response = self.send(builder)?;
         
         self.process_json_response(response)
     }
     
     /// Send a DELETE request
     pub fn delete(&self, url: &str) -> Result<Response> {
-        self.request(Method::DELETE, url)?
-            .send()
-            .map_err(HttpError::from)
+        self.send(self.request(Method::DELETE, url)?)
     }
     
     /// Send a DELETE request and deserialize the response as JSON
@@ -267,9 +588,7 @@ impl BlockingHttpClient {
     
     /// Send a PATCH request
     pub fn patch(&self, url: &str) -> Result<Response> {
-        self.request(Method::PATCH, url)?
-            .send()
-            .map_err(HttpError::from)
+        self.send(self.request(Method::PATCH, url)?)
     }
     
     /// Send a PATCH request with a JSON body
@@ -278,19 +597,15 @@ impl BlockingHttpClient {
         url: &str,
         body: &T,
     ) -> Result<R> {
-        let response = self.request(Method::PATCH, url)?
-            .json(body)
-            .send()
-            .map_err(HttpError::from)?;
+        let builder = self.request(Method::PATCH, url)?.json(body);
+        let response = self.send(builder)?;
         
         self.process_json_response(response)
     }
     
     /// Send a HEAD request
     pub fn head(&self, url: &str) -> Result<Response> {
-        self.request(Method::HEAD, url)?
-            .send()
-            .map_err(HttpError::from)
+        self.send(self.request(Method::HEAD, url)?)
     }
     
     /// Helper method to process a JSON response
@@ -298,13 +613,12 @@ impl BlockingHttpClient {
         let status = response.status();
         
         if status.is_success() {
-            response.json::<T>().map_err(|e| {
-                HttpError::SerializationError(format!("Failed to deserialize response: {}", e))
-            })
+            crate::response::parse_json(&response.bytes()?)
         } else {
-            let body = response
-                .text()
-                .unwrap_or_else(|_| "Could not read error body".to_string());
+            let body = match response.bytes() {
+                Ok(body) => String::from_utf8_lossy(&body).into_owned(),
+                Err(_) => "Could not read error body".to_string(),
+            };
             Err(HttpError::ResponseError { status, body })
         }
     }
@@ -328,7 +642,7 @@ impl BlockingHttpClient {
             builder = builder.header(header_name, header_value);
         }
         
-        builder.send().map_err(HttpError::from)
+        self.send(builder)
     }
     
     /// Send a request with query parameters
@@ -338,10 +652,22 @@ impl BlockingHttpClient {
         url: &str,
         params: &T,
     ) -> Result<Response> {
-        self.request(method, url)?
-            .query(params)
-            .send()
-            .map_err(HttpError::from)
+        let builder = self.request(method, url)?.query(params);
+        self.send(builder)
+    }
+    
+    /// Send a request with its own timeout in place of the client's
+    ///
+    /// The timeout covers each attempt, from connecting until the body
+    /// has been read, like the client-wide one.
+    pub fn request_with_timeout(&self, method: Method, url: &str, timeout: Duration) -> Result<Response> {
+        let builder = self.request(method, url)?.timeout(timeout);
+        self.send(builder)
+    }
+    
+    /// Send a GET request with its own timeout
+    pub fn get_with_timeout(&self, url: &str, timeout: Duration) -> Result<Response> {
+        self.request_with_timeout(Method::GET, url, timeout)
     }
     
     /// Get client configuration
@@ -349,34 +675,43 @@ impl BlockingHttpClient {
         &self.config
     }
     
+    /// Get the cookie store, if cookies are enabled
+    pub fn cookie_store(&self) -> Option<Arc<dyn CookieStore>> {
+        self.config.cookie_store.clone()
+    }
+    
     /// Execute a form request
     pub fn post_form<T: Serialize, R: DeserializeOwned>(
         &self,
         url: &str,
         form: &T,
     ) -> Result<R> {
-        let response = self.request(Method::POST, url)?
-            .form(form)
-            .send()
-            .map_err(HttpError::from)?;
+        let builder = self.request(Method::POST, url)?.form(form);
+        let response = self.send(builder)?;
         
         self.process_json_response(response)
     }
     
     /// Execute a multipart form request
+    ///
+    /// Takes a reqwest `Form` or a `BlockingMultipartBuilder`.
     pub fn post_multipart<R: DeserializeOwned>(
         &self,
         url: &str,
-        form: reqwest::blocking::multipart::Form,
+        form: impl Into<Form>,
     ) -> Result<R> {
-        let response = self.request(Method::POST, url)?
-            .multipart(form)
-            .send()
-            .map_err(HttpError::from)?;
+        let builder = self.request(Method::POST, url)?.multipart(form.into());
+        let response = self.send(builder)?;
         
         self.process_json_response(response)
     }
     
+    /// Upload a large file without loading it into memory
+    pub fn upload_file(&self, method: Method, url: &str, file: ChunkedFile) -> Result<Response> {
+        let builder = self.request(method, url)?.file_body(file)?;
+        self.send(builder)
+    }
+    
     /// Download a file to bytes
     pub fn download_bytes(&self, url: &str) -> Result<Vec<u8>> {
         let response = self.get(url)?;
@@ -395,26 +730,207 @@ impl BlockingHttpClient {
     }
     
     /// Stream download to a writer
-    pub fn download_to_writer<W: std::io::Write>(
+    pub fn download_to_writer<W: Write>(
         &self,
         url: &str,
-        mut writer: W,
+        writer: W,
     ) -> Result<u64> {
-        let mut response = self.get(url)?;
+        self.download_with_progress(url, writer, |_, _| {})
+    }
+    
+    /// Stream download to a writer, reporting progress after each chunk
+    ///
+    /// `progress` gets the bytes written so far and the `Content-Length`,
+    /// when the server sent one.
+    pub fn download_with_progress<W, F>(
+        &self,
+        url: &str,
+        mut writer: W,
+        mut progress: F,
+    ) -> Result<u64>
+    where
+        W: Write,
+        F: FnMut(u64, Option<u64>),
+    {
+        let mut response = Self::success(self.get(url)?)?;
+        let total = response.content_length();
+        let mut written = 0;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = match response.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(HttpError::io(format!("Failed to read {}", url), e)),
+            };
+            writer
+                .write_all(&buf[..read])
+                .map_err(|e| HttpError::io("Failed to write download", e))?;
+            written += read as u64;
+            progress(written, total);
+        }
+        writer
+            .flush()
+            .map_err(|e| HttpError::io("Failed to write download", e))?;
+        Ok(written)
+    }
+    
+    /// GET `url` and read the body as it arrives
+    ///
+    /// Fails with `ResponseError` on a non-success status; read errors
+    /// later on surface through the reader.
+    pub fn get_reader(&self, url: &str) -> Result<impl Read> {
+        Self::success(self.get(url)?)
+    }
+    
+    /// GET `url` and iterate over the lines of the body as they arrive
+    ///
+    /// Suits NDJSON and CSV exports too large to hold in memory. Line
+    /// endings (`\n` or `\r\n`) are stripped.
+    pub fn get_lines(&self, url: &str) -> Result<impl Iterator<Item = Result<String>>> {
+        let url = url.to_string();
+        let reader = io::BufReader::new(self.get_reader(&url)?);
+        Ok(reader
+            .lines()
+            .map(move |line| line.map_err(|e| HttpError::io(format!("Failed to read {}", url), e))))
+    }
+    
+    /// Pass a success response through, turning others into `ResponseError`
+    fn success(response: Response) -> Result<Response> {
         let status = response.status();
-        
         if status.is_success() {
-            std::io::copy(&mut response, &mut writer)
-            .map_err(|e| HttpError::IoError(e.to_string()))
-        } else {
-            let body = response
-                .text()
-                .unwrap_or_else(|_| "Could not read error body".to_string());
-            Err(HttpError::ResponseError { status, body })
+            return Ok(response);
+        }
+        let body = response
+            .text()
+            .unwrap_or_else(|_| "Could not read error body".to_string());
+        Err(HttpError::ResponseError { status, body })
+    }
+    
+    /// Download to `path`, replacing it only once the download has finished
+    ///
+    /// The body is written to a temporary file next to `path` and renamed
+    /// into place, so readers never see a partial file. Each download gets
+    /// its own temporary file, so concurrent downloads to the same path
+    /// don't mix; the last one to finish wins.
+    pub fn download_to_file<P: AsRef<Path>>(&self, url: &str, path: P) -> Result<u64> {
+        let path = path.as_ref();
+        let part = part_path(path)?;
+        
+        let io_error = |e: io::Error| HttpError::io(format!("Failed to write {}", path.display()), e);
+        let created = File::options().write(true).create_new(true).open(&part);
+        let result = created.map_err(io_error).and_then(|file| {
+            let written = self.download_to_writer(url, io::BufWriter::new(&file))?;
+            file.sync_all().map_err(io_error)?;
+            Ok(written)
+        });
+        
+        match result.and_then(|written| fs::rename(&part, path).map(|_| written).map_err(io_error)) {
+            Ok(written) => Ok(written),
+            Err(e) => {
+                let _ = fs::remove_file(&part);
+                Err(e)
+            }
         }
     }
 }
 
+/// Temporary file next to `path`, unique per process and download
+fn part_path(path: &Path) -> Result<PathBuf> {
+    static PART_COUNTER: AtomicU64 = AtomicU64::new(0);
+    
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| HttpError::io(format!("Not a file path: {}", path.display()), io::ErrorKind::InvalidInput.into()))?;
+    let mut part_name = std::ffi::OsString::from(".");
+    part_name.push(file_name);
+    part_name.push(format!(
+        ".{}.{}.part",
+        std::process::id(),
+        PART_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    Ok(path.with_file_name(part_name))
+}
+
+/// Builder for multipart forms from text, files and readers
+///
+/// File parts get their MIME type from the file extension; in-memory parts
+/// with an unknown extension are recognized by their first bytes. The
+/// fallback is `application/octet-stream`. See `guess_content_type`.
+#[derive(Debug, Default)]
+pub struct BlockingMultipartBuilder {
+    form: Form,
+}
+
+impl BlockingMultipartBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add a text field
+    pub fn text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.form = self.form.text(name.into(), value.into());
+        self
+    }
+    
+    /// Add the file at `path`, named after its file name
+    pub fn file<P: AsRef<Path>>(mut self, name: impl Into<String>, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let part = Part::file(path)
+            .map_err(|e| HttpError::io(format!("Failed to open {}", path.display()), e))?;
+        self.form = self.form.part(name.into(), part);
+        Ok(self)
+    }
+    
+    /// Add a part streamed from `reader`
+    ///
+    /// The MIME type is guessed from `file_name` unless `mime` is given.
+    pub fn reader<R: Read + Send + 'static>(
+        self,
+        name: impl Into<String>,
+        reader: R,
+        file_name: impl Into<String>,
+        mime: Option<&str>,
+    ) -> Result<Self> {
+        let file_name = file_name.into();
+        let mime = mime.map(str::to_string).unwrap_or_else(|| guess_content_type(Some(&file_name), b""));
+        self.part(name, Part::reader(reader), file_name, &mime)
+    }
+    
+    /// Add an in-memory file
+    pub fn bytes(
+        self,
+        name: impl Into<String>,
+        bytes: impl Into<Vec<u8>>,
+        file_name: impl Into<String>,
+        mime: Option<&str>,
+    ) -> Result<Self> {
+        let (bytes, file_name) = (bytes.into(), file_name.into());
+        let mime = mime.map(str::to_string).unwrap_or_else(|| guess_content_type(Some(&file_name), &bytes));
+        self.part(name, Part::bytes(bytes), file_name, &mime)
+    }
+    
+    fn part(mut self, name: impl Into<String>, part: Part, file_name: String, mime: &str) -> Result<Self> {
+        let part = part
+            .file_name(file_name)
+            .mime_str(mime)
+            .map_err(|_| HttpError::HeaderError(format!("Invalid MIME type: {}", mime)))?;
+        self.form = self.form.part(name.into(), part);
+        Ok(self)
+    }
+    
+    /// Finish the form
+    pub fn build(self) -> Form {
+        self.form
+    }
+}
+
+impl From<BlockingMultipartBuilder> for Form {
+    fn from(builder: BlockingMultipartBuilder) -> Self {
+        builder.build()
+    }
+}
+
 /// Extension trait for blocking RequestBuilder
 pub trait BlockingRequestBuilderExt {
     fn with_query<T: Serialize>(self, params: &T) -> RequestBuilder;
@@ -422,6 +938,12 @@ pub trait BlockingRequestBuilderExt {
     where
         K: TryInto<HeaderName>,
         V: TryInto<HeaderValue>;
+    
+    /// Send `body` as is, labelled `content_type` or a type sniffed from its first bytes
+    fn raw_body(self, body: Vec<u8>, content_type: Option<ContentType>) -> RequestBuilder;
+    
+    /// Stream `file` as the body, with its length and content type
+    fn file_body(self, file: ChunkedFile) -> Result<RequestBuilder>;
 }
 
 impl BlockingRequestBuilderExt for RequestBuilder {
@@ -440,11 +962,23 @@ impl BlockingRequestBuilderExt for RequestBuilder {
             self
         }
     }
+    
+    fn raw_body(self, body: Vec<u8>, content_type: Option<ContentType>) -> RequestBuilder {
+        let content_type = content_type.or_else(|| ContentType::sniff(&body)).unwrap_or(ContentType::OctetStream);
+        self.header(CONTENT_TYPE, HeaderValue::from(content_type)).body(body)
+    }
+    
+    fn file_body(self, file: ChunkedFile) -> Result<RequestBuilder> {
+        let content_type = file.content_type().to_string();
+        Ok(self.with_header(CONTENT_TYPE, content_type).body(file.into_blocking_body()?))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::RawServer;
+    use crate::profile::HostProfile;
     
     #[test]
     fn test_blocking_client_config_creation() {
@@ -480,5 +1014,227 @@ mod tests {
             client.build_url("https://other.com/test").unwrap(),
             "https://other.com/test"
         );
+        
+        let config = BlockingClientConfig::new()
+            .with_base_url("https://api.example.com")
+            .with_path_prefix("/v3")
+            .unwrap();
+        let client = BlockingHttpClient::with_config(config).unwrap();
+        assert_eq!(client.build_url("/users").unwrap(), "https://api.example.com/v3/users");
+        assert_eq!(client.to_async().unwrap().config().path_prefix.as_deref(), Some("/v3"));
+    }
+    
+    #[test]
+    fn test_blocking_retries_follow_policy() {
+        const UNAVAILABLE: &str =
+            "HTTP/1.1 503 Service Unavailable\r\nretry-after: 0\r\nconnection: close\r\ncontent-length: 0\r\n\r\n";
+        const OK: &str = "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 2\r\n\r\nok";
+        
+        let server = RawServer::replying(&[UNAVAILABLE, UNAVAILABLE, OK]);
+        let config = BlockingClientConfig::new()
+            .with_base_url(server.url())
+            .with_retries(3, Duration::from_secs(10));
+        let client = BlockingHttpClient::with_config(config).unwrap();
+        let started = std::time::Instant::now();
+        let response = client.get("/data").unwrap();
+        assert_eq!(response.text().unwrap(), "ok");
+        assert!(started.elapsed() < Duration::from_secs(10));
+        
+        // POST is not idempotent, so the 503 is returned as is
+        let server = RawServer::replying(&[UNAVAILABLE, OK]);
+        let config = BlockingClientConfig::new()
+            .with_base_url(server.url())
+            .with_retries(3, Duration::from_millis(10));
+        let client = BlockingHttpClient::with_config(config).unwrap();
+        assert_eq!(client.post("/data").unwrap().status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    }
+    
+    #[test]
+    fn test_config_conversions() {
+        let store: Arc<dyn CookieStore> = Arc::new(MemoryCookieStore::new());
+        let config = ClientConfig::new()
+            .with_base_url("https://api.example.com/v1")
+            .unwrap()
+            .with_timeout(Duration::from_secs(5))
+            .with_default_header("x-team", "core")
+            .unwrap()
+            .with_retries(2, Duration::from_millis(50))
+            .with_cookie_store(store.clone());
+        let client = HttpClient::with_config(config).unwrap();
+        
+        let blocking = client.to_blocking().unwrap();
+        assert_eq!(blocking.config().base_url.as_deref(), Some("https://api.example.com/v1"));
+        assert_eq!(blocking.config().timeout, Some(Duration::from_secs(5)));
+        assert_eq!(blocking.config().default_headers["x-team"], "core");
+        assert_eq!(blocking.config().retry.max_retries, 2);
+        assert!(Arc::ptr_eq(&blocking.cookie_store().unwrap(), &store));
+        assert_eq!(blocking.build_url("users").unwrap(), "https://api.example.com/v1/users");
+        
+        let back = blocking.to_async().unwrap().config();
+        assert_eq!(back.base_url, client.config().base_url);
+        assert_eq!(back.retry, client.config().retry);
+        assert_eq!(back.default_headers, client.config().default_headers);
+        
+        let invalid = BlockingHttpClient::with_base_url("not a url");
+        assert!(matches!(invalid.to_async(), Err(HttpError::ConfigError(_))));
+        
+        let protected = HttpClient::with_config(ClientConfig::new().with_ssrf_protection(true)).unwrap();
+        assert!(matches!(protected.to_blocking(), Err(HttpError::ConfigError(_))));
+        let offline = ClientConfig::new().with_offline(true);
+        assert!(matches!(BlockingClientConfig::try_from(&offline), Err(HttpError::ConfigError(_))));
+        
+        // the blocking client would send default credentials to any host
+        for policy in [CrossHostPolicy::StripAuth, CrossHostPolicy::Reject] {
+            let config = ClientConfig::new()
+                .with_default_header("authorization", "Bearer secret")
+                .unwrap()
+                .with_cross_host_policy(policy);
+            let client = HttpClient::with_config(config).unwrap();
+            assert!(matches!(client.to_blocking(), Err(HttpError::ConfigError(_))));
+        }
+        let profiled = ClientConfig::new().with_host_profile("api.example.com", HostProfile::new());
+        assert!(matches!(BlockingClientConfig::try_from(&profiled), Err(HttpError::ConfigError(_))));
+    }
+    
+    #[test]
+    fn test_blocking_rate_limits_use_clock() {
+        use crate::clock::FakeClock;
+        
+        const LIMITED: &str = "HTTP/1.1 200 OK\r\nx-ratelimit-remaining: 0\r\nx-ratelimit-reset: 30\r\n\
+            connection: close\r\ncontent-length: 0\r\n\r\n";
+        const OK: &str = "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 0\r\n\r\n";
+        
+        let server = RawServer::replying(&[OK, OK, LIMITED, OK]);
+        let clock = FakeClock::new();
+        let config = BlockingClientConfig::new()
+            .with_base_url(server.url())
+            .with_rate_limit("127.0.0.1/limited", RateLimit::per_minute(1))
+            .unwrap()
+            .with_adaptive_throttle(AdaptiveThrottle::new().with_clock(Arc::new(clock.clone())))
+            .with_clock(Arc::new(clock.clone()));
+        let client = BlockingHttpClient::with_config(config).unwrap();
+        
+        client.get("/limited").unwrap();
+        client.get("/limited").unwrap();
+        assert_eq!(clock.sleeps(), [Duration::from_secs(60)]);
+        
+        // the spent quota holds back the next request until the reset
+        client.get("/other").unwrap();
+        client.get("/other").unwrap();
+        assert_eq!(clock.sleeps().last(), Some(&Duration::from_secs(30)));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_per_request_timeout() {
+        let server = RawServer::silent();
+        
+        let config = BlockingClientConfig::new()
+            .with_base_url(server.url())
+            .with_timeout(Duration::from_secs(30));
+        let client = BlockingHttpClient::with_config(config).unwrap();
+        let started = std::time::Instant::now();
+        let result = client.get_with_timeout("/slow", Duration::from_millis(100));
+        assert!(matches!(result, Err(HttpError::RequestError(e)) if e.is_timeout()));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+    
+    #[test]
+    fn test_get_reader_and_lines() {
+        let server = RawServer::replying(&[
+            "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 5\r\n\r\nbytes",
+            "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 24\r\n\r\n{\"id\":1}\r\n{\"id\":2}\n\nlast",
+            "HTTP/1.1 500 Internal Server Error\r\nconnection: close\r\ncontent-length: 4\r\n\r\nboom",
+        ]);
+        let client = BlockingHttpClient::with_base_url(server.url());
+        
+        let mut body = String::new();
+        client.get_reader("/raw").unwrap().read_to_string(&mut body).unwrap();
+        assert_eq!(body, "bytes");
+        
+        let lines: Vec<String> = client.get_lines("/export").unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(lines, ["{\"id\":1}", "{\"id\":2}", "", "last"]);
+        
+        assert!(matches!(
+            client.get_lines("/broken").err(),
+            Some(HttpError::ResponseError { body, .. }) if body == "boom"
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_multipart_builder_guesses_mime_types() {
+        use crate::test_support::StubServer;
+        use crate::transport::{Mock, MockResponse};
+        
+        let server = StubServer::start().await.unwrap();
+        server.stub(Mock::post("/upload").respond_with(MockResponse::json(reqwest::StatusCode::OK, &true).unwrap()));
+        
+        let dir = std::env::temp_dir().join(format!("rusty-http-multipart-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("notes.txt"), "file body").unwrap();
+        
+        let form = BlockingMultipartBuilder::new()
+            .text("title", "report")
+            .file("notes", dir.join("notes.txt"))
+            .unwrap()
+            .reader("data", io::Cursor::new(b"{}".to_vec()), "data.json", None)
+            .unwrap()
+            .bytes("blob", vec![1, 2], "blob", Some("image/png"))
+            .unwrap()
+            .bytes("scan", b"%PDF-1.7".to_vec(), "scan.bin", None)
+            .unwrap();
+        assert!(BlockingMultipartBuilder::new().file("missing", dir.join("missing.txt")).is_err());
+        
+        // the blocking client runs its own runtime, so keep it off this one
+        let url = server.url();
+        let ok: bool = tokio::task::spawn_blocking(move || {
+            BlockingHttpClient::with_base_url(url).post_multipart("/upload", form).unwrap()
+        })
+        .await
+        .unwrap();
+        assert!(ok);
+        
+        let body = String::from_utf8(server.requests()[0].body.clone().unwrap()).unwrap();
+        assert!(body.contains("filename=\"notes.txt\"\r\nContent-Type: text/plain\r\n\r\nfile body"));
+        assert!(body.contains("filename=\"data.json\"\r\nContent-Type: application/json"));
+        assert!(body.contains("filename=\"blob\"\r\nContent-Type: image/png"));
+        assert!(body.contains("filename=\"scan.bin\"\r\nContent-Type: application/pdf"));
+        assert!(body.contains("name=\"title\"\r\n\r\nreport"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_download_to_file_with_progress() {
+        let server = RawServer::replying(&[
+            "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 5\r\n\r\nhello",
+            "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 3\r\n\r\nabc",
+            "HTTP/1.1 404 Not Found\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+        ]);
+        let client = BlockingHttpClient::with_base_url(server.url());
+        
+        let mut seen = Vec::new();
+        let mut body = Vec::new();
+        let written = client
+            .download_with_progress("/hello", &mut body, |done, total| seen.push((done, total)))
+            .unwrap();
+        assert_eq!(written, 5);
+        assert_eq!(body, b"hello");
+        assert_eq!(seen.last(), Some(&(5, Some(5))));
+        
+        let dir = std::env::temp_dir().join(format!("rusty-http-download-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.txt");
+        assert_eq!(client.download_to_file("/abc", &path).unwrap(), 3);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abc");
+        
+        // a failed download leaves the previous file alone
+        assert!(client.download_to_file("/missing", &path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abc");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert_ne!(part_path(&path).unwrap(), part_path(&path).unwrap());
+        
+        // file errors keep their io::ErrorKind
+        let result = client.download_to_file("/abc", dir.join("missing").join("data.txt"));
+        assert!(matches!(result, Err(HttpError::Io { ref source, .. }) if source.kind() == io::ErrorKind::NotFound));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
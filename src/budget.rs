@@ -0,0 +1,124 @@
+// src/budget.rs
+// Wall-clock budget shared by every attempt of a request
+
+use crate::clock::{system_clock, Clock};
+use crate::error::{HttpError, Result};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    /// Budget of the request currently being executed
+    static CURRENT_BUDGET: Budget;
+}
+
+/// Total time a request may take across attempts, backoff and failover
+///
+/// The budget runs until response headers arrive; reading the body is not
+/// counted. Middleware can inspect the remaining time with `Budget::current`.
+/// Time is read from a `Clock`, the client's own when it starts the budget.
+#[derive(Debug, Clone)]
+pub struct Budget {
+    total: Duration,
+    deadline: Instant,
+    clock: Arc<dyn Clock>,
+}
+
+impl Budget {
+    /// Start a budget of `total` from now
+    pub fn new(total: Duration) -> Self {
+        Self::with_clock(total, system_clock())
+    }
+    
+    /// Start a budget of `total` from the current time of `clock`
+    pub fn with_clock(total: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            total,
+            deadline: clock.now() + total,
+            clock,
+        }
+    }
+    
+    /// Get the budget of the request being executed, if it has one
+    pub fn current() -> Option<Budget> {
+        CURRENT_BUDGET.try_with(Budget::clone).ok()
+    }
+    
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+    
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+    
+    /// Time left before the deadline
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(self.clock.now())
+    }
+    
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining().is_zero()
+    }
+    
+    /// Run `fut` with this budget visible to `Budget::current`
+    pub(crate) async fn scope<F: Future>(self, fut: F) -> F::Output {
+        CURRENT_BUDGET.scope(self, fut).await
+    }
+    
+    /// Run `fut`, failing with a timeout once the budget runs out
+    ///
+    /// The remaining time is read from the clock; the wait itself runs on
+    /// the tokio timer since a request in flight can't be cut short by a
+    /// clock that only moves when told to.
+    pub(crate) async fn limit<T, F: Future<Output = Result<T>>>(&self, fut: F) -> Result<T> {
+        let remaining = self.remaining();
+        if remaining.is_zero() {
+            return Err(HttpError::TimeoutError);
+        }
+        tokio::time::timeout(remaining, fut)
+            .await
+            .unwrap_or(Err(HttpError::TimeoutError))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_budget_scope_and_limit() {
+        assert!(Budget::current().is_none());
+        
+        let budget = Budget::new(Duration::from_millis(50));
+        budget
+            .clone()
+            .scope(async {
+                let current = Budget::current().unwrap();
+                assert_eq!(current.total(), Duration::from_millis(50));
+                assert!(current.remaining() <= Duration::from_millis(50));
+            })
+            .await;
+        
+        let slow = budget.limit(async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        });
+        assert!(matches!(slow.await, Err(HttpError::TimeoutError)));
+        assert!(budget.is_exhausted());
+    }
+    
+    #[tokio::test]
+    async fn test_budget_reads_clock() {
+        use crate::clock::FakeClock;
+        
+        let clock = FakeClock::new();
+        let budget = Budget::with_clock(Duration::from_secs(10), Arc::new(clock.clone()));
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(budget.remaining(), Duration::from_secs(6));
+        
+        clock.advance(Duration::from_secs(6));
+        assert!(budget.is_exhausted());
+        assert!(matches!(budget.limit(async { Ok(()) }).await, Err(HttpError::TimeoutError)));
+    }
+}
@@ -0,0 +1,86 @@
+// src/bulkhead.rs
+// Limits on concurrent in-flight requests
+
+use crate::error::{HttpError, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps the number of requests in flight at once
+///
+/// Requests over the cap queue until a slot frees up, or fail with
+/// `TimeoutError` if they wait longer than the queue timeout. Clones share
+/// the same slots.
+#[derive(Debug, Clone)]
+pub struct Bulkhead {
+    semaphore: Arc<Semaphore>,
+    max_in_flight: usize,
+}
+
+impl Bulkhead {
+    /// Allow at most `max_in_flight` concurrent requests, and at least one
+    pub fn new(max_in_flight: usize) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight,
+        }
+    }
+    
+    /// Get the configured cap
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+    
+    /// Get the number of requests currently holding a slot
+    pub fn in_flight(&self) -> usize {
+        self.max_in_flight - self.semaphore.available_permits()
+    }
+    
+    /// Take a slot if one is free right now
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        self.semaphore.clone().try_acquire_owned().ok()
+    }
+    
+    /// Wait for a slot, giving up after `queue_timeout` if set
+    ///
+    /// The slot is released when the returned permit is dropped.
+    pub async fn acquire(&self, queue_timeout: Option<Duration>) -> Result<OwnedSemaphorePermit> {
+        let acquire = self.semaphore.clone().acquire_owned();
+        
+        let permit = match queue_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, acquire).await.map_err(|_| {
+                log::warn!("Request waited over {:?} for one of {} slots", timeout, self.max_in_flight);
+                HttpError::TimeoutError
+            })?,
+            None => acquire.await,
+        };
+        
+        // the semaphore is never closed
+        Ok(permit.expect("bulkhead semaphore closed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_bulkhead_caps_in_flight() {
+        let bulkhead = Bulkhead::new(2);
+        let first = bulkhead.acquire(None).await.unwrap();
+        let _second = bulkhead.acquire(None).await.unwrap();
+        assert_eq!(bulkhead.in_flight(), 2);
+        
+        let queued = bulkhead.acquire(Some(Duration::from_millis(20))).await;
+        assert!(matches!(queued, Err(HttpError::TimeoutError)));
+        
+        drop(first);
+        assert_eq!(bulkhead.in_flight(), 1);
+        assert!(bulkhead.acquire(Some(Duration::from_millis(20))).await.is_ok());
+        
+        // a cap of zero would leave every request waiting forever
+        assert_eq!(Bulkhead::new(0).max_in_flight(), 1);
+        assert!(Bulkhead::new(0).try_acquire().is_some());
+    }
+}
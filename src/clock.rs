@@ -0,0 +1,124 @@
+// src/clock.rs
+// Injectable time source for retries and rate limiting
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of the current time and of delays
+///
+/// Retry backoff, `RateLimiter` and `AdaptiveThrottle` read time through a
+/// clock so tests can swap in a `FakeClock` and check schedules without
+/// sleeping for real. The blocking client waits with `sleep_blocking`.
+#[async_trait::async_trait]
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> Instant;
+    
+    async fn sleep(&self, duration: Duration);
+    
+    /// Block the current thread for `duration`
+    fn sleep_blocking(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// The real clock, sleeping on the tokio timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait::async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+    
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Get the clock used when none is configured
+pub(crate) fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+#[derive(Debug, Default)]
+struct FakeTime {
+    elapsed: Duration,
+    sleeps: Vec<Duration>,
+}
+
+/// Manually driven clock for tests
+///
+/// Time only moves through `advance` or `sleep`, and `sleep` returns at
+/// once after moving time forward and recording the requested delay.
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    start: Instant,
+    time: Arc<Mutex<FakeTime>>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            time: Arc::default(),
+        }
+    }
+    
+    /// Move time forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        self.time.lock().unwrap().elapsed += duration;
+    }
+    
+    /// Time passed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        self.time.lock().unwrap().elapsed
+    }
+    
+    /// Every delay passed to `sleep`, in order
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.time.lock().unwrap().sleeps.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+    
+    async fn sleep(&self, duration: Duration) {
+        self.sleep_blocking(duration);
+    }
+    
+    fn sleep_blocking(&self, duration: Duration) {
+        let mut time = self.time.lock().unwrap();
+        time.elapsed += duration;
+        time.sleeps.push(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_fake_clock_sleeps_instantly() {
+        let clock = FakeClock::new();
+        let started = clock.now();
+        
+        clock.sleep(Duration::from_secs(30)).await;
+        clock.advance(Duration::from_secs(5));
+        
+        assert_eq!(clock.now() - started, Duration::from_secs(35));
+        assert_eq!(clock.sleeps(), [Duration::from_secs(30)]);
+    }
+}
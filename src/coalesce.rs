@@ -0,0 +1,232 @@
+// src/coalesce.rs
+// Coalescing individual calls into batch requests
+
+use crate::batch::catch_panic;
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use reqwest::Request;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+
+type Merge<T> = Arc<dyn Fn(&HttpClient, Vec<T>) -> Result<Request> + Send + Sync>;
+type Split<R> = Arc<dyn Fn(&[u8]) -> Result<Vec<R>> + Send + Sync>;
+type Call<T, R> = (T, oneshot::Sender<Result<R>>);
+
+/// When `HttpClient::coalescer` sends a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coalescing {
+    /// How long the first call of a batch waits for others to join it
+    pub window: Duration,
+    /// A batch is sent at once when it reaches this many calls
+    pub max_batch: usize,
+    /// Batch requests in flight at once; calls wait for a slot beyond that
+    pub max_in_flight: usize,
+}
+
+impl Coalescing {
+    /// Collect calls for `window`, in batches of up to 100, 4 batches at a time
+    pub fn new(window: Duration) -> Self {
+        Self { window, max_batch: 100, max_in_flight: 4 }
+    }
+    
+    /// Send a batch as soon as it has `max_batch` calls
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+    
+    /// Send up to `max_in_flight` batch requests at once
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+}
+
+/// Handle for calls that are coalesced into batch requests
+///
+/// Calls made within one window are merged into a single request, and the
+/// batch response is split back into one result per call, in call order.
+/// When the batch request fails, every call in it fails. Calls wait while
+/// `max_in_flight` batches are being sent and the next one is full. Clones
+/// share the batching task, which stops once every clone has been dropped.
+pub struct Coalescer<T, R> {
+    calls: mpsc::Sender<Call<T, R>>,
+}
+
+impl<T, R> Clone for Coalescer<T, R> {
+    fn clone(&self) -> Self {
+        Self { calls: self.calls.clone() }
+    }
+}
+
+impl<T, R> fmt::Debug for Coalescer<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coalescer").finish_non_exhaustive()
+    }
+}
+
+impl<T: Send + 'static, R: Send + 'static> Coalescer<T, R> {
+    fn spawn(client: HttpClient, coalescing: Coalescing, merge: Merge<T>, split: Split<R>) -> Self {
+        let (calls, mut receiver) = mpsc::channel::<Call<T, R>>(coalescing.max_batch);
+        let in_flight = Arc::new(Semaphore::new(coalescing.max_in_flight));
+        tokio::spawn(async move {
+            while let Some(first) = receiver.recv().await {
+                let mut batch = vec![first];
+                let clock = client.config().clock.clone();
+                let window = clock.sleep(coalescing.window);
+                tokio::pin!(window);
+                while batch.len() < coalescing.max_batch {
+                    tokio::select! {
+                        biased;
+                        call = receiver.recv() => match call {
+                            Some(call) => batch.push(call),
+                            None => break,
+                        },
+                        _ = &mut window => break,
+                    }
+                }
+                
+                let Ok(permit) = in_flight.clone().acquire_owned().await else {
+                    break;
+                };
+                let (client, merge, split) = (client.clone(), merge.clone(), split.clone());
+                tokio::spawn(async move {
+                    let (items, replies): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+                    match catch_panic(send_batch(&client, &merge, &split, items)).await {
+                        Ok(results) => {
+                            for (reply, result) in replies.into_iter().zip(results) {
+                                let _ = reply.send(Ok(result));
+                            }
+                        }
+                        Err(e) => {
+                            for reply in replies {
+                                let _ = reply.send(Err(share_error(&e)));
+                            }
+                        }
+                    }
+                    drop(permit);
+                });
+            }
+        });
+        
+        Self { calls }
+    }
+    
+    /// Add `item` to the current batch and wait for its result
+    pub async fn call(&self, item: T) -> Result<R> {
+        let (reply, result) = oneshot::channel();
+        self.calls
+            .send((item, reply))
+            .await
+            .map_err(|_| HttpError::ConfigError("Coalescer has stopped".to_string()))?;
+        result
+            .await
+            .map_err(|_| HttpError::Unknown("Coalesced batch was dropped".to_string()))?
+    }
+}
+
+async fn send_batch<T, R>(client: &HttpClient, merge: &Merge<T>, split: &Split<R>, items: Vec<T>) -> Result<Vec<R>> {
+    let count = items.len();
+    let request = merge(client, items)?;
+    let response = client.execute(request).await?;
+    let status = response.status();
+    let body = response.bytes().await?;
+    if !status.is_success() {
+        let body = String::from_utf8_lossy(&body).into_owned();
+        return Err(HttpError::ResponseError { status, body });
+    }
+    
+    let results = split(&body)?;
+    if results.len() != count {
+        return Err(HttpError::SerializationError(format!(
+            "Batch of {} calls was split into {} results",
+            count,
+            results.len()
+        )));
+    }
+    Ok(results)
+}
+
+/// Copy a batch error for each call, keeping its variant where that's cheap
+fn share_error(error: &HttpError) -> HttpError {
+    match error {
+        HttpError::ResponseError { status, body } => HttpError::ResponseError { status: *status, body: body.clone() },
+        HttpError::SerializationError(message) => HttpError::SerializationError(message.clone()),
+        HttpError::ConfigError(message) => HttpError::ConfigError(message.clone()),
+        HttpError::TimeoutError => HttpError::TimeoutError,
+        HttpError::Unknown(message) => HttpError::Unknown(message.clone()),
+        other => HttpError::Unknown(format!("Batch request failed: {}", other)),
+    }
+}
+
+impl HttpClient {
+    /// Start coalescing calls into batch requests
+    ///
+    /// `merge` builds one request from the items of a batch and `split`
+    /// turns the body of a 2xx batch response into one result per item, in
+    /// the same order. Must be called inside a tokio runtime.
+    pub fn coalescer<T, R, M, S>(&self, coalescing: Coalescing, merge: M, split: S) -> Coalescer<T, R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        M: Fn(&HttpClient, Vec<T>) -> Result<Request> + Send + Sync + 'static,
+        S: Fn(&[u8]) -> Result<Vec<R>> + Send + Sync + 'static,
+    {
+        Coalescer::spawn(self.clone(), coalescing, Arc::new(merge), Arc::new(split))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+    
+    #[tokio::test]
+    async fn test_coalescer_batches_calls() {
+        let transport = MockTransport::new();
+        let names = MockResponse::json(StatusCode::OK, &json!(["ada", "grace", "linus"])).unwrap();
+        let batch = Mock::post("/users/batch").with_json_body(&json!([1, 2, 3])).unwrap();
+        transport.expect(batch.respond_with(names.clone()));
+        transport.expect(Mock::post("/users/batch").respond_with(names));
+        let client = transport.client();
+        
+        let coalescer = client.coalescer(
+            Coalescing::new(Duration::from_millis(20)).with_max_batch(3),
+            |client, ids: Vec<u32>| Ok(client.request(Method::POST, "/users/batch")?.json(&ids).build()?),
+            |body| Ok(serde_json::from_slice::<Vec<String>>(body)?),
+        );
+        
+        let (a, b, c) = tokio::join!(coalescer.call(1), coalescer.call(2), coalescer.call(3));
+        assert_eq!((a.unwrap(), b.unwrap(), c.unwrap()), ("ada".into(), "grace".into(), "linus".into()));
+        assert_eq!(transport.requests().len(), 1);
+        
+        // a batch of one that gets three results back
+        let error = coalescer.call(4).await.unwrap_err();
+        assert!(matches!(error, HttpError::SerializationError(_)));
+        assert_eq!(transport.requests().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_coalescer_survives_panicking_split() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::post("/batch").respond_with(MockResponse::json(StatusCode::OK, &json!([1])).unwrap()));
+        let client = transport.client();
+        
+        let coalescer = client.coalescer(
+            Coalescing::new(Duration::from_millis(1)),
+            |client, items: Vec<u32>| Ok(client.request(Method::POST, "/batch")?.json(&items).build()?),
+            |body| match serde_json::from_slice::<Vec<u32>>(body)?.as_slice() {
+                [1] => panic!("split failed"),
+                values => Ok(values.to_vec()),
+            },
+        );
+        
+        assert!(matches!(coalescer.call(1).await, Err(HttpError::Unknown(_))));
+        assert!(matches!(coalescer.call(2).await, Err(HttpError::Unknown(_))));
+        assert_eq!(transport.requests().len(), 2);
+    }
+}
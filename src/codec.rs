@@ -0,0 +1,162 @@
+// src/codec.rs
+// Body encoding and decoding chosen by media type
+
+use crate::error::{HttpError, Result};
+use crate::response::parse_json;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Converts bodies of one content type to and from serde values
+///
+/// Values pass through `serde_json::Value`, which keeps the trait
+/// object-safe; any serde type can be encoded or decoded through it.
+pub trait BodyCodec: Send + Sync + fmt::Debug {
+    fn encode(&self, value: &Value) -> Result<Vec<u8>>;
+    
+    fn decode(&self, body: &[u8]) -> Result<Value>;
+}
+
+/// Custom codecs keyed by media type
+///
+/// Registered codecs take precedence over the built-in formats. Media
+/// types are matched case-insensitively and without parameters, so a codec
+/// for `application/vnd.acme` also handles `application/vnd.acme; v=2`.
+#[derive(Debug, Clone, Default)]
+pub struct CodecRegistry {
+    codecs: HashMap<String, Arc<dyn BodyCodec>>,
+}
+
+impl CodecRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Use `codec` for bodies of `media_type`
+    pub fn register(&mut self, media_type: &str, codec: Arc<dyn BodyCodec>) {
+        self.codecs.insert(self::media_type(media_type), codec);
+    }
+    
+    /// Get the codec registered for a `Content-Type` value
+    pub fn get(&self, content_type: &str) -> Option<&Arc<dyn BodyCodec>> {
+        self.codecs.get(&media_type(content_type))
+    }
+    
+    /// Serialize `value` for `content_type`, falling back to JSON
+    pub fn encode<T: Serialize>(&self, content_type: &str, value: &T) -> Result<Vec<u8>> {
+        match self.get(content_type) {
+            Some(codec) => codec.encode(&serde_json::to_value(value)?),
+            None => Ok(serde_json::to_vec(value)?),
+        }
+    }
+    
+    /// Deserialize `body` with a registered codec or a built-in format
+    pub fn decode<T: DeserializeOwned>(&self, content_type: Option<&str>, body: &[u8]) -> Result<T> {
+        match content_type.and_then(|content_type| self.get(content_type)) {
+            Some(codec) => Ok(serde_json::from_value(codec.decode(body)?)?),
+            None => decode_body(content_type, body),
+        }
+    }
+}
+
+/// Media types `decode_body` understands, in order of preference
+pub fn decodable_types() -> Vec<&'static str> {
+    let mut types = vec!["application/json"];
+    #[cfg(feature = "xml")]
+    types.push("application/xml");
+    #[cfg(feature = "msgpack")]
+    types.push("application/msgpack");
+    #[cfg(feature = "yaml")]
+    types.push("application/yaml");
+    types.extend(["application/x-www-form-urlencoded", "text/plain"]);
+    types
+}
+
+/// `Accept` header value listing every decodable type, JSON first
+pub fn accept_header() -> String {
+    decodable_types()
+        .iter()
+        .enumerate()
+        .map(|(i, media_type)| match i {
+            0 => media_type.to_string(),
+            _ => format!("{};q=0.{}", media_type, 9 - i.min(8)),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Get the lowercase media type of a `Content-Type` value, without parameters
+pub(crate) fn media_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// Deserialize `body` in the format named by `content_type`
+///
+/// JSON (including `+json` types), form-urlencoded and `text/*` are always
+/// supported; XML, MessagePack and YAML need their features. A missing
+/// content type is read as JSON.
+pub fn decode_body<T: DeserializeOwned>(content_type: Option<&str>, body: &[u8]) -> Result<T> {
+    let media_type = content_type.map(media_type).unwrap_or_else(|| "application/json".to_string());
+    let decode_error = |e: &dyn std::fmt::Display| {
+        HttpError::SerializationError(format!("Failed to decode {} body: {}", media_type, e))
+    };
+    
+    match media_type.as_str() {
+        "application/json" => parse_json(body),
+        json if json.ends_with("+json") => parse_json(body),
+        #[cfg(feature = "xml")]
+        "application/xml" | "text/xml" => crate::xml::from_xml(&String::from_utf8_lossy(body)),
+        #[cfg(feature = "xml")]
+        xml if xml.ends_with("+xml") => crate::xml::from_xml(&String::from_utf8_lossy(body)),
+        #[cfg(feature = "msgpack")]
+        "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+            rmp_serde::from_slice(body).map_err(|e| decode_error(&e))
+        }
+        #[cfg(feature = "yaml")]
+        "application/yaml" | "application/x-yaml" | "text/yaml" => {
+            crate::yaml::from_yaml(&String::from_utf8_lossy(body))
+        }
+        "application/x-www-form-urlencoded" => serde_urlencoded::from_bytes(body).map_err(|e| decode_error(&e)),
+        text if text.starts_with("text/") => {
+            let text = String::from_utf8_lossy(body).into_owned();
+            T::deserialize(text.into_deserializer()).map_err(|e: serde::de::value::Error| decode_error(&e))
+        }
+        other => Err(HttpError::SerializationError(format!("Unsupported content type '{}'", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    
+    #[test]
+    fn test_decode_body_by_content_type() {
+        let json: HashMap<String, u32> =
+            decode_body(Some("application/problem+json; charset=utf-8"), br#"{"a": 1}"#).unwrap();
+        assert_eq!(json["a"], 1);
+        
+        let form: HashMap<String, String> = decode_body(Some("application/x-www-form-urlencoded"), b"a=b+c").unwrap();
+        assert_eq!(form["a"], "b c");
+        
+        let text: String = decode_body(Some("Text/Plain"), b"hello").unwrap();
+        assert_eq!(text, "hello");
+        
+        let missing: Vec<u8> = decode_body(None, b"[1, 2]").unwrap();
+        assert_eq!(missing, [1, 2]);
+        
+        assert!(decode_body::<String>(Some("image/png"), b"").is_err());
+        assert!(accept_header().starts_with("application/json, "));
+    }
+    
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_decode_msgpack_body() {
+        let body = rmp_serde::to_vec(&vec!["a", "b"]).unwrap();
+        let decoded: Vec<String> = decode_body(Some("application/x-msgpack"), &body).unwrap();
+        assert_eq!(decoded, ["a", "b"]);
+    }
+}
@@ -0,0 +1,443 @@
+// src/compression.rs
+// Compression of outgoing request bodies, and response decoding
+
+use crate::client::Decompression;
+use crate::error::{HttpError, Result};
+use reqwest::header::{HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH};
+use reqwest::{Method, Request, Response};
+use std::fmt;
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd"))]
+use std::io::{self, Write};
+use std::sync::Arc;
+
+/// Compresses request bodies for one `Content-Encoding`
+///
+/// `Gzip` comes with the `gzip` feature and `Zstd` with the `zstd` feature. Other
+/// encodings can be plugged in by implementing this trait on top of the
+/// compression library of choice.
+pub trait BodyEncoder: Send + Sync + fmt::Debug {
+    /// The `Content-Encoding` token, e.g. `gzip` or `zstd`
+    fn encoding(&self) -> &str;
+    
+    fn encode(&self, body: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// gzip (RFC 1952) at flate2's default level
+#[cfg(feature = "gzip")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gzip;
+
+#[cfg(feature = "gzip")]
+impl BodyEncoder for Gzip {
+    fn encoding(&self) -> &str {
+        "gzip"
+    }
+    
+    fn encode(&self, body: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::with_capacity(body.len() / 4), flate2::Compression::default());
+        encoder
+            .write_all(body)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| HttpError::IoError(format!("Failed to gzip request body: {}", e)))
+    }
+}
+
+/// zstd (RFC 8878) at `level`, 3 by default
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy)]
+pub struct Zstd {
+    pub level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl Default for Zstd {
+    fn default() -> Self {
+        Self { level: zstd::DEFAULT_COMPRESSION_LEVEL }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl BodyEncoder for Zstd {
+    fn encoding(&self) -> &str {
+        "zstd"
+    }
+    
+    fn encode(&self, body: &[u8]) -> Result<Vec<u8>> {
+        zstd::stream::encode_all(body, self.level)
+            .map_err(|e| HttpError::IoError(format!("Failed to zstd-compress request body: {}", e)))
+    }
+}
+
+/// Opt-in compression of request bodies above a size threshold
+///
+/// Only buffered bodies are compressed; streams and requests that already
+/// carry a `Content-Encoding` are sent unchanged.
+#[derive(Debug, Clone)]
+pub struct RequestCompression {
+    encoder: Arc<dyn BodyEncoder>,
+    min_size: usize,
+}
+
+impl RequestCompression {
+    /// Bodies smaller than this are rarely worth compressing
+    pub const DEFAULT_MIN_SIZE: usize = 1024;
+    
+    /// Compress with a custom encoder
+    pub fn new<E: BodyEncoder + 'static>(encoder: E) -> Self {
+        Self {
+            encoder: Arc::new(encoder),
+            min_size: Self::DEFAULT_MIN_SIZE,
+        }
+    }
+    
+    /// Compress with the built-in gzip encoder
+    #[cfg(feature = "gzip")]
+    pub fn gzip() -> Self {
+        Self::new(Gzip)
+    }
+    
+    /// Compress with zstd at the default level
+    #[cfg(feature = "zstd")]
+    pub fn zstd() -> Self {
+        Self::new(Zstd::default())
+    }
+    
+    /// Only compress bodies of at least `min_size` bytes
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+    
+    /// The `Content-Encoding` that compressed requests are sent with
+    pub fn encoding(&self) -> &str {
+        self.encoder.encoding()
+    }
+    
+    /// Compress the request body in place if it qualifies
+    pub(crate) fn apply(&self, request: &mut Request) -> Result<()> {
+        if request.headers().contains_key(CONTENT_ENCODING) {
+            return Ok(());
+        }
+        let body = match request.body().and_then(|body| body.as_bytes()) {
+            Some(body) if body.len() >= self.min_size => body,
+            _ => return Ok(()),
+        };
+        
+        let encoded = self.encoder.encode(body)?;
+        let encoding = HeaderValue::from_str(self.encoder.encoding())
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        request.headers_mut().insert(CONTENT_ENCODING, encoding);
+        request.headers_mut().remove(CONTENT_LENGTH);
+        *request.body_mut() = Some(encoded.into());
+        Ok(())
+    }
+}
+
+/// Marks a response whose body the client decompressed, with the coding it removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Decompressed(pub(crate) &'static str);
+
+/// Incremental decoder for one `Content-Encoding`
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd"))]
+enum BodyDecoder {
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    // HTTP's `deflate` is the zlib format (RFC 9110 8.4.1.2)
+    #[cfg(feature = "deflate")]
+    Deflate(flate2::write::ZlibDecoder<Vec<u8>>),
+    #[cfg(feature = "brotli")]
+    Brotli(Box<brotli_decompressor::DecompressorWriter<Vec<u8>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd"))]
+impl BodyDecoder {
+    /// A decoder for `encoding` if `decompression` enables it
+    fn new(encoding: &str, decompression: &Decompression) -> Option<(Self, &'static str)> {
+        let active = decompression.active();
+        let name = active.into_iter().find(|name| encoding.eq_ignore_ascii_case(name))?;
+        let decoder = match name {
+            #[cfg(feature = "gzip")]
+            "gzip" => BodyDecoder::Gzip(flate2::write::GzDecoder::new(Vec::new())),
+            #[cfg(feature = "deflate")]
+            "deflate" => BodyDecoder::Deflate(flate2::write::ZlibDecoder::new(Vec::new())),
+            #[cfg(feature = "brotli")]
+            "br" => BodyDecoder::Brotli(Box::new(brotli_decompressor::DecompressorWriter::new(Vec::new(), 4096))),
+            #[cfg(feature = "zstd")]
+            "zstd" => BodyDecoder::Zstd(zstd::stream::write::Decoder::new(Vec::new()).ok()?),
+            _ => return None,
+        };
+        Some((decoder, name))
+    }
+    
+    /// Feed `chunk` and take whatever has been decoded so far
+    fn decode(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let output = match self {
+            #[cfg(feature = "gzip")]
+            BodyDecoder::Gzip(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            #[cfg(feature = "deflate")]
+            BodyDecoder::Deflate(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            #[cfg(feature = "brotli")]
+            BodyDecoder::Brotli(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            #[cfg(feature = "zstd")]
+            BodyDecoder::Zstd(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.flush()?;
+                decoder.get_mut()
+            }
+        };
+        Ok(std::mem::take(output))
+    }
+    
+    /// Check the stream is complete and take the rest of the output
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            BodyDecoder::Gzip(decoder) => decoder.finish(),
+            #[cfg(feature = "deflate")]
+            BodyDecoder::Deflate(decoder) => decoder.finish(),
+            #[cfg(feature = "brotli")]
+            BodyDecoder::Brotli(mut decoder) => {
+                decoder.close()?;
+                Ok(std::mem::take(decoder.get_mut()))
+            }
+            #[cfg(feature = "zstd")]
+            BodyDecoder::Zstd(mut decoder) => {
+                decoder.flush()?;
+                Ok(decoder.into_inner())
+            }
+        }
+    }
+}
+
+/// Whether `response` to a `method` request can carry a body at all
+///
+/// Responses to HEAD requests, 1xx, 204 and 304 responses and ones
+/// declaring `Content-Length: 0` keep their `Content-Encoding` but have nothing to
+/// decode (RFC 9110 6.4.1), so they are passed through as they are.
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd"))]
+fn has_body(method: &Method, response: &Response) -> bool {
+    let status = response.status();
+    let empty = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|length| length.trim() == "0");
+    *method != Method::HEAD
+        && !status.is_informational()
+        && status != reqwest::StatusCode::NO_CONTENT
+        && status != reqwest::StatusCode::NOT_MODIFIED
+        && !empty
+}
+
+/// Decode `response` if its `Content-Encoding` is one `decompression` enables
+///
+/// The body is decoded as it streams in, and a decoding error surfaces as
+/// an error reading the body. Decoded responses lose `Content-Encoding` and
+/// `Content-Length` and are marked for `ResponseExt::was_decompressed`;
+/// other responses, including ones with several codings or no body to
+/// decode, are returned unchanged. A body that turns out to be empty
+/// despite its `Content-Encoding` decodes to an empty body.
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd"))]
+pub(crate) fn decompress(response: Response, method: &Method, decompression: &Decompression) -> Response {
+    use reqwest::ResponseBuilderExt;
+    
+    if !has_body(method, &response) {
+        return response;
+    }
+    let encoding = match response.headers().get(CONTENT_ENCODING).and_then(|value| value.to_str().ok()) {
+        Some(encoding) => encoding.trim(),
+        None => return response,
+    };
+    let (mut decoder, name) = match BodyDecoder::new(encoding, decompression) {
+        Some(decoder) => decoder,
+        None => return response,
+    };
+    
+    let (status, version, url) = (response.status(), response.version(), response.url().clone());
+    let mut headers = response.headers().clone();
+    headers.remove(CONTENT_ENCODING);
+    headers.remove(CONTENT_LENGTH);
+    
+    let (mut sender, body) = hyper::Body::channel();
+    let mut source = response;
+    tokio::spawn(async move {
+        let mut received = false;
+        loop {
+            let decoded = match source.chunk().await {
+                Ok(Some(chunk)) if chunk.is_empty() => continue,
+                Ok(Some(chunk)) => {
+                    received = true;
+                    decoder.decode(&chunk)
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    log::debug!("Failed to read {} body: {}", name, e);
+                    sender.abort();
+                    return;
+                }
+            };
+            match decoded {
+                Ok(decoded) if decoded.is_empty() => {}
+                Ok(decoded) => {
+                    if sender.send_data(decoded.into()).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    log::debug!("Failed to decode {} body: {}", name, e);
+                    sender.abort();
+                    return;
+                }
+            }
+        }
+        // an encoded empty body is still an empty body
+        if !received {
+            return;
+        }
+        match decoder.finish() {
+            Ok(rest) if rest.is_empty() => {}
+            Ok(rest) => {
+                let _ = sender.send_data(rest.into()).await;
+            }
+            Err(e) => {
+                log::debug!("Truncated {} body: {}", name, e);
+                sender.abort();
+            }
+        }
+    });
+    
+    let mut builder = hyper::Response::builder()
+        .status(status)
+        .version(version)
+        .url(url)
+        .extension(Decompressed(name));
+    if let Some(response_headers) = builder.headers_mut() {
+        *response_headers = headers;
+    }
+    builder.body(body).expect("valid response parts").into()
+}
+
+/// Without a decoder compiled in there is nothing to decompress
+#[cfg(not(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd")))]
+pub(crate) fn decompress(response: Response, _method: &Method, _decompression: &Decompression) -> Response {
+    response
+}
+
+#[cfg(all(test, any(feature = "gzip", feature = "zstd")))]
+mod tests {
+    use super::*;
+    
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_round_trip() {
+        use std::io::Read;
+        
+        let body = br#"{"event":"page_view","path":"/"}"#.repeat(100);
+        let encoded = Gzip.encode(&body).unwrap();
+        assert!(encoded.len() < body.len() / 10);
+        
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(&encoded[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, body);
+    }
+    
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_round_trip() {
+        let body = br#"{"event":"page_view","path":"/"}"#.repeat(100);
+        let compression = RequestCompression::zstd().with_min_size(16);
+        assert_eq!(compression.encoding(), "zstd");
+        
+        let encoded = Zstd::default().encode(&body).unwrap();
+        assert!(encoded.len() < body.len() / 10);
+        assert_eq!(zstd::stream::decode_all(&encoded[..]).unwrap(), body);
+    }
+    
+    #[cfg(all(feature = "gzip", feature = "deflate"))]
+    #[test]
+    fn test_body_decoder_streams_chunks() {
+        let body = br#"{"event":"page_view","path":"/"}"#.repeat(100);
+        let all = Decompression::all();
+        
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(&body).unwrap();
+        for (encoding, encoded) in [("GZIP", Gzip.encode(&body).unwrap()), ("deflate", zlib.finish().unwrap())] {
+            let (mut decoder, _) = BodyDecoder::new(encoding, &all).unwrap();
+            let mut decoded = Vec::new();
+            for chunk in encoded.chunks(7) {
+                decoded.extend(decoder.decode(chunk).unwrap());
+            }
+            decoded.extend(decoder.finish().unwrap());
+            assert_eq!(decoded, body);
+        }
+        
+        let encoded = Gzip.encode(&body).unwrap();
+        let (mut truncated, name) = BodyDecoder::new("gzip", &all).unwrap();
+        assert_eq!(name, "gzip");
+        truncated.decode(&encoded[..encoded.len() / 2]).unwrap();
+        assert!(truncated.finish().is_err());
+        assert!(BodyDecoder::new("gzip", &Decompression::none()).is_none());
+    }
+    
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_empty_encoded_bodies_pass_through() {
+        let all = Decompression::all();
+        let gzipped = |status: u16, length: Option<&str>| {
+            let mut builder = hyper::Response::builder()
+                .status(status)
+                .header(CONTENT_ENCODING, "gzip");
+            if let Some(length) = length {
+                builder = builder.header(CONTENT_LENGTH, length);
+            }
+            Response::from(builder.body(Vec::new()).unwrap())
+        };
+        
+        let cases = [
+            (Method::HEAD, gzipped(200, Some("1234")), false),
+            (Method::GET, gzipped(103, None), false),
+            (Method::GET, gzipped(204, None), false),
+            (Method::GET, gzipped(304, None), false),
+            (Method::GET, gzipped(200, Some("0")), false),
+            // no length and no bytes: decoded, and decodes to nothing
+            (Method::GET, gzipped(200, None), true),
+        ];
+        for (method, response, decoded) in cases {
+            let status = response.status();
+            let response = decompress(response, &method, &all);
+            assert_eq!(response.extensions().get::<Decompressed>().is_some(), decoded, "{} {}", method, status);
+            assert_eq!(response.text().await.unwrap(), "", "{} {}", method, status);
+        }
+    }
+    
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_request_compression_threshold() {
+        let compression = RequestCompression::gzip().with_min_size(16);
+        let url = "http://example.com/ingest".parse::<reqwest::Url>().unwrap();
+        
+        let mut small = Request::new(Method::POST, url.clone());
+        *small.body_mut() = Some(b"tiny".to_vec().into());
+        compression.apply(&mut small).unwrap();
+        assert!(small.headers().get(CONTENT_ENCODING).is_none());
+        
+        let mut large = Request::new(Method::POST, url);
+        *large.body_mut() = Some(vec![b'x'; 4096].into());
+        compression.apply(&mut large).unwrap();
+        assert_eq!(large.headers()[CONTENT_ENCODING], "gzip");
+        let body = large.body().and_then(|body| body.as_bytes()).unwrap();
+        assert_eq!(body[..2], [0x1f, 0x8b]);
+        assert!(body.len() < 100);
+    }
+}
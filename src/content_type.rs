@@ -0,0 +1,126 @@
+// src/content_type.rs
+// Common media types and content type guessing for uploads
+
+use reqwest::header::HeaderValue;
+use std::fmt;
+use std::path::Path;
+
+/// Frequently used media types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentType {
+    Json,
+    NdJson,
+    Xml,
+    Yaml,
+    MsgPack,
+    FormUrlEncoded,
+    MultipartFormData,
+    Text,
+    Html,
+    Csv,
+    OctetStream,
+    Pdf,
+    Zip,
+    Gzip,
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+}
+
+impl ContentType {
+    /// The media type, without parameters
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::NdJson => "application/x-ndjson",
+            Self::Xml => "application/xml",
+            Self::Yaml => "application/yaml",
+            Self::MsgPack => "application/msgpack",
+            Self::FormUrlEncoded => "application/x-www-form-urlencoded",
+            Self::MultipartFormData => "multipart/form-data",
+            Self::Text => "text/plain",
+            Self::Html => "text/html",
+            Self::Csv => "text/csv",
+            Self::OctetStream => "application/octet-stream",
+            Self::Pdf => "application/pdf",
+            Self::Zip => "application/zip",
+            Self::Gzip => "application/gzip",
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+        }
+    }
+    
+    /// Recognize a file format from its first bytes
+    ///
+    /// Covers the binary formats with reliable signatures: PDF, ZIP, gzip,
+    /// PNG, JPEG, GIF and WebP.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        let signatures: [(&[u8], Self); 7] = [
+            (b"%PDF-", Self::Pdf),
+            (b"PK\x03\x04", Self::Zip),
+            (b"\x1f\x8b", Self::Gzip),
+            (b"\x89PNG\r\n\x1a\n", Self::Png),
+            (b"\xff\xd8\xff", Self::Jpeg),
+            (b"GIF87a", Self::Gif),
+            (b"GIF89a", Self::Gif),
+        ];
+        if let Some((_, content_type)) = signatures.iter().find(|(signature, _)| bytes.starts_with(signature)) {
+            return Some(*content_type);
+        }
+        if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            return Some(Self::Webp);
+        }
+        None
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for ContentType {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<ContentType> for HeaderValue {
+    fn from(content_type: ContentType) -> Self {
+        HeaderValue::from_static(content_type.as_str())
+    }
+}
+
+/// Guess the media type of an upload
+///
+/// The file name's extension is tried first, then the leading bytes of
+/// the content, falling back to `application/octet-stream`. Extensions
+/// that only say "binary", like `.bin`, defer to the content.
+pub fn guess_content_type(file_name: Option<&str>, head: &[u8]) -> String {
+    file_name
+        .and_then(|file_name| mime_guess::from_path(Path::new(file_name)).first())
+        .map(|mime| mime.essence_str().to_string())
+        .filter(|mime| mime != ContentType::OctetStream.as_str())
+        .or_else(|| ContentType::sniff(head).map(|content_type| content_type.as_str().to_string()))
+        .unwrap_or_else(|| ContentType::OctetStream.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_guess_content_type() {
+        assert_eq!(guess_content_type(Some("report.csv"), b""), "text/csv");
+        assert_eq!(guess_content_type(Some("photo.JPG"), b""), "image/jpeg");
+        assert_eq!(guess_content_type(Some("upload.bin"), b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(guess_content_type(None, b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(guess_content_type(None, b"%PDF-1.7"), "application/pdf");
+        assert_eq!(guess_content_type(None, b"plain"), "application/octet-stream");
+        assert_eq!(HeaderValue::from(ContentType::NdJson), "application/x-ndjson");
+    }
+}
@@ -0,0 +1,411 @@
+// src/cookies.rs
+// Cookie storage and persistence
+
+use crate::error::{HttpError, Result};
+use reqwest::header::HeaderValue;
+use reqwest::Url;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// A stored cookie
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Domain the cookie is sent to, if known
+    pub domain: Option<String>,
+    /// Path prefix the cookie is sent for, if known
+    pub path: Option<String>,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+impl Cookie {
+    /// Create a cookie with just a name and value
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+        }
+    }
+}
+
+/// Storage for cookies received from servers
+///
+/// Implement this to keep cookies somewhere other than memory. `save` and
+/// `load` are no-ops for stores without backing storage.
+pub trait CookieStore: Send + Sync + fmt::Debug {
+    /// Store the cookies from `Set-Cookie` headers of a response to `url`
+    fn set_cookies(&self, url: &Url, set_cookie_headers: &mut dyn Iterator<Item = &HeaderValue>);
+    
+    /// Get the `Cookie` header value to send with a request to `url`
+    fn cookies(&self, url: &Url) -> Option<HeaderValue>;
+    
+    /// List the cookies that would be sent with a request to `url`
+    ///
+    /// The default implementation parses `cookies`, so only names and
+    /// values are filled in.
+    fn list(&self, url: &Url) -> Vec<Cookie> {
+        let header = match self.cookies(url) {
+            Some(header) => header,
+            None => return Vec::new(),
+        };
+        
+        header
+            .to_str()
+            .unwrap_or_default()
+            .split(';')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .map(|(name, value)| Cookie::new(name, value))
+            .collect()
+    }
+    
+    /// Insert a cookie as if `url` had responded with `set_cookie`
+    ///
+    /// `set_cookie` uses `Set-Cookie` syntax, e.g. `"token=abc; Path=/"`.
+    fn insert(&self, url: &Url, set_cookie: &str) -> Result<()> {
+        let value = HeaderValue::from_str(set_cookie)
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        self.set_cookies(url, &mut std::iter::once(&value));
+        Ok(())
+    }
+    
+    /// Remove the cookies named `name` that would be sent to `url`
+    ///
+    /// Returns whether any cookie was removed.
+    fn remove(&self, _url: &Url, _name: &str) -> Result<bool> {
+        Err(HttpError::ConfigError(format!("{:?} does not support removing cookies", self)))
+    }
+    
+    /// Remove every cookie
+    fn clear(&self) -> Result<()> {
+        Err(HttpError::ConfigError(format!("{:?} does not support clearing cookies", self)))
+    }
+    
+    /// Write the cookies to backing storage
+    fn save(&self) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Replace the cookies with those in backing storage
+    fn load(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// In-memory cookie store following RFC 6265 domain and path rules
+#[derive(Debug, Default)]
+pub struct MemoryCookieStore {
+    store: RwLock<cookie_store::CookieStore>,
+}
+
+impl MemoryCookieStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Write unexpired cookies as JSON lines, including session cookies
+    fn write_json<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let store = self.store.read().unwrap();
+        for cookie in store.iter_unexpired() {
+            writeln!(writer, "{}", serde_json::to_string(cookie)?)
+                .map_err(|e| HttpError::IoError(e.to_string()))?;
+        }
+        Ok(())
+    }
+    
+    /// Replace the cookies with JSON lines read from `reader`
+    fn read_json<R: std::io::BufRead>(&self, reader: R) -> Result<()> {
+        let loaded = cookie_store::CookieStore::load_json(reader)
+            .map_err(|e| HttpError::IoError(format!("Failed to load cookies: {}", e)))?;
+        *self.store.write().unwrap() = loaded;
+        Ok(())
+    }
+}
+
+impl CookieStore for MemoryCookieStore {
+    fn set_cookies(&self, url: &Url, set_cookie_headers: &mut dyn Iterator<Item = &HeaderValue>) {
+        let cookies = set_cookie_headers
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| cookie_store::RawCookie::parse(value.to_string()).ok());
+        
+        self.store.write().unwrap().store_response_cookies(cookies, url);
+    }
+    
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .store
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        
+        if header.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&header).ok()
+        }
+    }
+    
+    fn list(&self, url: &Url) -> Vec<Cookie> {
+        self.store
+            .read()
+            .unwrap()
+            .matches(url)
+            .into_iter()
+            .map(|cookie| Cookie {
+                name: cookie.name().to_string(),
+                value: cookie.value().to_string(),
+                domain: Some(String::from(&cookie.domain)),
+                path: Some(String::from(&cookie.path)),
+                secure: cookie.secure().unwrap_or(false),
+                http_only: cookie.http_only().unwrap_or(false),
+            })
+            .collect()
+    }
+    
+    fn insert(&self, url: &Url, set_cookie: &str) -> Result<()> {
+        let mut store = self.store.write().unwrap();
+        store
+            .parse(set_cookie, url)
+            .map(|_| ())
+            .map_err(|e| HttpError::HeaderError(format!("Invalid cookie '{}': {}", set_cookie, e)))
+    }
+    
+    fn remove(&self, url: &Url, name: &str) -> Result<bool> {
+        let mut store = self.store.write().unwrap();
+        let matching: Vec<(String, String)> = store
+            .matches(url)
+            .into_iter()
+            .filter(|cookie| cookie.name() == name)
+            .map(|cookie| (String::from(&cookie.domain), String::from(&cookie.path)))
+            .collect();
+        
+        for (domain, path) in &matching {
+            store.remove(domain, path, name);
+        }
+        Ok(!matching.is_empty())
+    }
+    
+    fn clear(&self) -> Result<()> {
+        self.store.write().unwrap().clear();
+        Ok(())
+    }
+}
+
+/// Cookie store persisted to a JSON file
+///
+/// Cookies live in memory while the client runs; call `save` to write them
+/// out (session cookies included, so CLI logins survive between runs) and
+/// `load` to pick up changes made by another process.
+#[derive(Debug)]
+pub struct JsonFileCookieStore {
+    path: PathBuf,
+    cookies: MemoryCookieStore,
+}
+
+impl JsonFileCookieStore {
+    /// Open a store at `path`, loading its cookies if the file exists
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let store = Self {
+            path: path.into(),
+            cookies: MemoryCookieStore::new(),
+        };
+        store.load()?;
+        Ok(store)
+    }
+    
+    /// Get the file backing this store
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl CookieStore for JsonFileCookieStore {
+    fn set_cookies(&self, url: &Url, set_cookie_headers: &mut dyn Iterator<Item = &HeaderValue>) {
+        self.cookies.set_cookies(url, set_cookie_headers);
+    }
+    
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        self.cookies.cookies(url)
+    }
+    
+    fn list(&self, url: &Url) -> Vec<Cookie> {
+        self.cookies.list(url)
+    }
+    
+    fn insert(&self, url: &Url, set_cookie: &str) -> Result<()> {
+        self.cookies.insert(url, set_cookie)
+    }
+    
+    fn remove(&self, url: &Url, name: &str) -> Result<bool> {
+        self.cookies.remove(url, name)
+    }
+    
+    fn clear(&self) -> Result<()> {
+        self.cookies.clear()
+    }
+    
+    fn save(&self) -> Result<()> {
+        let io_error = |e: std::io::Error| {
+            HttpError::IoError(format!("Failed to save cookies to {}: {}", self.path.display(), e))
+        };
+        
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        
+        // write to a temporary file first so a crash can't truncate the jar
+        // and keep it readable by the owner only, as it holds session tokens
+        let tmp_path = self.path.with_extension("tmp");
+        let _ = fs::remove_file(&tmp_path);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&tmp_path).map_err(io_error)?;
+        self.cookies.write_json(&mut file)?;
+        file.sync_all().map_err(io_error)?;
+        fs::rename(&tmp_path, &self.path).map_err(io_error)
+    }
+    
+    fn load(&self) -> Result<()> {
+        match fs::File::open(&self.path) {
+            Ok(file) => self.cookies.read_json(BufReader::new(file)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.cookies.read_json(std::io::empty())
+            }
+            Err(e) => Err(HttpError::IoError(format!(
+                "Failed to load cookies from {}: {}",
+                self.path.display(),
+                e
+            ))),
+        }
+    }
+}
+
+tokio::task_local! {
+    /// Set while a request that opted out of cookies is in flight
+    static COOKIES_SUPPRESSED: bool;
+}
+
+/// Run a request future with the cookie store disabled
+pub(crate) async fn without_cookies<F: Future>(request: F) -> F::Output {
+    COOKIES_SUPPRESSED.scope(true, request).await
+}
+
+fn cookies_suppressed() -> bool {
+    COOKIES_SUPPRESSED.try_with(|suppressed| *suppressed).unwrap_or(false)
+}
+
+/// Adapter letting reqwest use a `CookieStore`
+pub(crate) struct ReqwestCookieStore(pub(crate) Arc<dyn CookieStore>);
+
+impl reqwest::cookie::CookieStore for ReqwestCookieStore {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        if !cookies_suppressed() {
+            self.0.set_cookies(url, cookie_headers);
+        }
+    }
+    
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        if cookies_suppressed() {
+            None
+        } else {
+            self.0.cookies(url)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn store_response(store: &dyn CookieStore, url: &str, set_cookies: &[&'static str]) {
+        let headers: Vec<HeaderValue> = set_cookies.iter().map(|v| HeaderValue::from_static(v)).collect();
+        store.set_cookies(&Url::parse(url).unwrap(), &mut headers.iter());
+    }
+    
+    #[test]
+    fn test_memory_store_domain_and_path_rules() {
+        let store = MemoryCookieStore::new();
+        store_response(&store, "https://api.example.com/auth/login", &[
+            "session=abc; Path=/",
+            "scoped=1; Path=/auth",
+        ]);
+        
+        let cookies = |url: &str| store.cookies(&Url::parse(url).unwrap());
+        assert_eq!(cookies("https://api.example.com/users").unwrap(), "session=abc");
+        let auth = cookies("https://api.example.com/auth/refresh").unwrap();
+        assert!(auth.to_str().unwrap().contains("scoped=1"));
+        assert!(cookies("https://other.example.com/").is_none());
+    }
+    
+    #[test]
+    fn test_insert_list_remove_clear() {
+        let store = MemoryCookieStore::new();
+        let url = Url::parse("https://example.com/app").unwrap();
+        
+        store.insert(&url, "token=abc; Path=/; Secure; HttpOnly").unwrap();
+        store.insert(&url, "theme=dark; Path=/").unwrap();
+        
+        let mut cookies = store.list(&url);
+        cookies.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[1].name, "token");
+        assert_eq!(cookies[1].value, "abc");
+        assert_eq!(cookies[1].domain.as_deref(), Some("example.com"));
+        assert!(cookies[1].secure && cookies[1].http_only);
+        
+        assert!(store.remove(&url, "token").unwrap());
+        assert!(!store.remove(&url, "token").unwrap());
+        assert_eq!(store.list(&url), vec![Cookie {
+            domain: Some("example.com".to_string()),
+            path: Some("/".to_string()),
+            ..Cookie::new("theme", "dark")
+        }]);
+        
+        store.clear().unwrap();
+        assert!(store.list(&url).is_empty());
+        assert!(store.insert(&url, "not a cookie").is_err());
+    }
+    
+    #[test]
+    fn test_json_file_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("rusty-http-cookies-{}", std::process::id()));
+        let path = dir.join("cookies.json");
+        let _ = fs::remove_dir_all(&dir);
+        
+        let store = JsonFileCookieStore::open(&path).unwrap();
+        store_response(&store, "https://example.com/", &[
+            "session=abc; Path=/",
+            "remember=yes; Path=/; Max-Age=3600",
+            "gone=1; Path=/; Max-Age=0",
+        ]);
+        store.save().unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        
+        let reopened = JsonFileCookieStore::open(&path).unwrap();
+        let header = reopened.cookies(&Url::parse("https://example.com/").unwrap()).unwrap();
+        let header = header.to_str().unwrap();
+        assert!(header.contains("session=abc"));
+        assert!(header.contains("remember=yes"));
+        assert!(!header.contains("gone"));
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,464 @@
+// src/dns.rs
+// Pluggable DNS resolution
+
+use crate::clock::{system_clock, Clock};
+use crate::error::{HttpError, Result};
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Trait for implementing custom DNS resolution
+///
+/// Resolvers only return IP addresses; the port is always taken from the
+/// request URL.
+#[async_trait::async_trait]
+pub trait DnsResolver: Send + Sync + fmt::Debug {
+    /// Resolve a hostname to one or more IP addresses
+    async fn resolve(&self, host: &str) -> Result<Vec<IpAddr>>;
+    
+    /// Resolve a hostname, also reporting how long the answer may be cached
+    ///
+    /// Resolvers that know the record TTL should override this; the
+    /// default reports none.
+    async fn resolve_with_ttl(&self, host: &str) -> Result<(Vec<IpAddr>, Option<Duration>)> {
+        Ok((self.resolve(host).await?, None))
+    }
+    
+    /// Get the name of this resolver for debugging
+    fn name(&self) -> &'static str;
+}
+
+/// Whether `ip` is in a private, loopback, link-local or otherwise reserved range
+///
+/// Covers the ranges a server fetching user-supplied URLs should never
+/// reach, such as `10.0.0.0/8`, `127.0.0.0/8`, `169.254.0.0/16` (including
+/// the cloud metadata address `169.254.169.254`), `100.64.0.0/10`,
+/// `198.18.0.0/15`, `fc00::/7`, `fe80::/10` and `fec0::/10`. IPv6 forms
+/// that embed an IPv4 address (IPv4-mapped, IPv4-compatible, NAT64
+/// `64:ff9b::/96` and 6to4 `2002::/16`) are checked as that IPv4 address.
+pub fn is_internal_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 192 && b == 0 && ip.octets()[2] == 0)
+                || (a == 198 && (b & 0xfe) == 18)
+                || a >= 240
+        }
+        IpAddr::V6(ip) => {
+            if ip.is_loopback() || ip.is_unspecified() {
+                return true;
+            }
+            if let Some(embedded) = embedded_ipv4(ip) {
+                return is_internal_address(IpAddr::V4(embedded));
+            }
+            let first = ip.segments()[0];
+            ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first & 0xffc0) == 0xfec0
+        }
+    }
+}
+
+/// The IPv4 address carried by an IPv4-mapped, IPv4-compatible, NAT64 or 6to4 address
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let octets = ip.octets();
+    let tail = |at: usize| Ipv4Addr::new(octets[at], octets[at + 1], octets[at + 2], octets[at + 3]);
+    match ip.segments() {
+        [0, 0, 0, 0, 0, 0xffff | 0, _, _] | [0x64, 0xff9b, 0, 0, 0, 0, _, _] => Some(tail(12)),
+        [0x2002, ..] => Some(tail(2)),
+        _ => None,
+    }
+}
+
+/// Resolver backed by the operating system (`getaddrinfo`)
+///
+/// Useful as the fallback inside custom resolvers that only handle some names.
+#[derive(Debug, Clone, Default)]
+pub struct SystemResolver;
+
+impl SystemResolver {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait::async_trait]
+impl DnsResolver for SystemResolver {
+    async fn resolve(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let addrs = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|e| HttpError::IoError(format!("DNS lookup for '{}' failed: {}", host, e)))?;
+        Ok(addrs.map(|addr| addr.ip()).collect())
+    }
+    
+    fn name(&self) -> &'static str {
+        "SystemResolver"
+    }
+}
+
+/// Counters for a `DnsCache`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DnsCacheStats {
+    /// Lookups answered with cached addresses
+    pub hits: u64,
+    /// Lookups that went to the resolver
+    pub misses: u64,
+    /// Lookups answered with a cached failure
+    pub negative_hits: u64,
+    /// Hosts with an unexpired cached answer
+    pub entries: usize,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    lookup: std::result::Result<Vec<IpAddr>, String>,
+    expires: Instant,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    stats: DnsCacheStats,
+}
+
+/// In-process cache in front of the configured resolver
+///
+/// Answers are kept for the TTL the resolver reports, clamped to
+/// `[min_ttl, max_ttl]`; answers without a TTL (the system resolver never
+/// reports one) are kept for `min_ttl`. Failed lookups are cached for the
+/// negative TTL so a missing host doesn't hammer the resolver. Expired
+/// entries are dropped as the cache is used, and once `max_entries` hosts
+/// are cached the one closest to expiry makes room for a new host. Clones
+/// share the same entries and counters.
+#[derive(Debug, Clone)]
+pub struct DnsCache {
+    min_ttl: Duration,
+    max_ttl: Duration,
+    negative_ttl: Duration,
+    max_entries: usize,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl Default for DnsCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DnsCache {
+    /// Cache up to 1024 hosts for 30 seconds to 5 minutes, and failures for 5 seconds
+    pub fn new() -> Self {
+        Self {
+            min_ttl: Duration::from_secs(30),
+            max_ttl: Duration::from_secs(300),
+            negative_ttl: Duration::from_secs(5),
+            max_entries: 1024,
+            clock: system_clock(),
+            state: Arc::default(),
+        }
+    }
+    
+    /// Clamp record TTLs to `min..=max`
+    pub fn with_ttl(mut self, min: Duration, max: Duration) -> Self {
+        self.min_ttl = min;
+        self.max_ttl = max.max(min);
+        self
+    }
+    
+    /// Cache failed lookups for `ttl`; zero disables negative caching
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+    
+    /// Cache at most `max_entries` hosts
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+    
+    /// Read time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Snapshot of the hit and miss counters
+    pub fn stats(&self) -> DnsCacheStats {
+        let state = self.state.lock().unwrap();
+        let now = self.clock.now();
+        DnsCacheStats {
+            entries: state.entries.values().filter(|entry| entry.expires > now).count(),
+            ..state.stats
+        }
+    }
+    
+    /// Drop every cached answer, keeping the counters
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+    
+    /// Resolve `host` through the cache, asking `resolver` on a miss
+    pub async fn resolve(&self, resolver: &dyn DnsResolver, host: &str) -> Result<Vec<IpAddr>> {
+        let key = host.to_ascii_lowercase();
+        {
+            let mut state = self.state.lock().unwrap();
+            let now = self.clock.now();
+            let cached = match state.entries.get(&key) {
+                Some(entry) if entry.expires > now => Some(entry.lookup.clone()),
+                Some(_) => {
+                    state.entries.remove(&key);
+                    None
+                }
+                None => None,
+            };
+            match cached {
+                Some(Ok(ips)) => {
+                    state.stats.hits += 1;
+                    return Ok(ips);
+                }
+                Some(Err(message)) => {
+                    state.stats.negative_hits += 1;
+                    return Err(HttpError::IoError(message));
+                }
+                None => state.stats.misses += 1,
+            }
+        }
+        
+        let result = resolver.resolve_with_ttl(host).await;
+        let (lookup, ttl) = match &result {
+            Ok((ips, ttl)) => (Ok(ips.clone()), ttl.unwrap_or(self.min_ttl).clamp(self.min_ttl, self.max_ttl)),
+            Err(e) => (Err(e.to_string()), self.negative_ttl),
+        };
+        if !ttl.is_zero() {
+            let now = self.clock.now();
+            let mut state = self.state.lock().unwrap();
+            state.entries.retain(|_, entry| entry.expires > now);
+            if state.entries.len() >= self.max_entries && !state.entries.contains_key(&key) {
+                let soonest = state.entries.iter().min_by_key(|(_, entry)| entry.expires).map(|(host, _)| host.clone());
+                if let Some(soonest) = soonest {
+                    state.entries.remove(&soonest);
+                }
+            }
+            state.entries.insert(key, CacheEntry { lookup, expires: now + ttl });
+        }
+        result.map(|(ips, _)| ips)
+    }
+    
+    /// Wrap `resolver` so its lookups go through this cache
+    pub(crate) fn wrap(&self, resolver: Arc<dyn DnsResolver>) -> Arc<dyn DnsResolver> {
+        Arc::new(CachingResolver {
+            cache: self.clone(),
+            resolver,
+        })
+    }
+}
+
+/// A resolver whose answers are cached by a `DnsCache`
+#[derive(Debug)]
+struct CachingResolver {
+    cache: DnsCache,
+    resolver: Arc<dyn DnsResolver>,
+}
+
+#[async_trait::async_trait]
+impl DnsResolver for CachingResolver {
+    async fn resolve(&self, host: &str) -> Result<Vec<IpAddr>> {
+        self.cache.resolve(self.resolver.as_ref(), host).await
+    }
+    
+    fn name(&self) -> &'static str {
+        self.resolver.name()
+    }
+}
+
+/// Which IP address families to connect with, and in what order
+///
+/// When both families are allowed, the connector tries the preferred family
+/// first and races the other one shortly after ("happy eyeballs"), so a
+/// broken IPv6 route costs a short delay instead of a full connect timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressFamilyPolicy {
+    /// Use addresses in the order the resolver returned them
+    #[default]
+    Any,
+    /// Try IPv4 addresses first, then fall back to IPv6
+    PreferIpv4,
+    /// Try IPv6 addresses first, then fall back to IPv4
+    PreferIpv6,
+    /// Only connect over IPv4
+    Ipv4Only,
+    /// Only connect over IPv6
+    Ipv6Only,
+}
+
+impl AddressFamilyPolicy {
+    /// Filter and order resolved addresses according to the policy
+    pub fn apply(self, ips: Vec<IpAddr>) -> Vec<IpAddr> {
+        let (v4, v6): (Vec<IpAddr>, Vec<IpAddr>) = ips.iter().partition(|ip| ip.is_ipv4());
+        match self {
+            AddressFamilyPolicy::Any => ips,
+            AddressFamilyPolicy::PreferIpv4 => v4.into_iter().chain(v6).collect(),
+            AddressFamilyPolicy::PreferIpv6 => v6.into_iter().chain(v4).collect(),
+            AddressFamilyPolicy::Ipv4Only => v4,
+            AddressFamilyPolicy::Ipv6Only => v6,
+        }
+    }
+}
+
+/// Adapter exposing a `DnsResolver` to reqwest
+pub(crate) struct ReqwestResolver {
+    pub(crate) resolver: Arc<dyn DnsResolver>,
+    pub(crate) policy: AddressFamilyPolicy,
+    /// Drop addresses for which `is_internal_address` holds
+    pub(crate) block_internal: bool,
+}
+
+impl reqwest::dns::Resolve for ReqwestResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let resolver = self.resolver.clone();
+        let policy = self.policy;
+        let block_internal = self.block_internal;
+        Box::pin(async move {
+            let mut ips = policy.apply(resolver.resolve(name.as_str()).await?);
+            if block_internal {
+                let resolved = ips.len();
+                ips.retain(|ip| !is_internal_address(*ip));
+                if ips.is_empty() && resolved > 0 {
+                    return Err(HttpError::BlockedAddress(name.as_str().to_string()).into());
+                }
+            }
+            if ips.is_empty() {
+                return Err(format!("{} returned no addresses for '{}'", resolver.name(), name).into());
+            }
+            let addrs: reqwest::dns::Addrs =
+                Box::new(ips.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_address_family_policy() {
+        let v4 = IpAddr::from([10, 0, 0, 1]);
+        let v6 = IpAddr::from([0xfd00, 0, 0, 0, 0, 0, 0, 1]);
+        let ips = vec![v6, v4];
+        
+        assert_eq!(AddressFamilyPolicy::Any.apply(ips.clone()), vec![v6, v4]);
+        assert_eq!(AddressFamilyPolicy::PreferIpv4.apply(ips.clone()), vec![v4, v6]);
+        assert_eq!(AddressFamilyPolicy::PreferIpv6.apply(ips.clone()), vec![v6, v4]);
+        assert_eq!(AddressFamilyPolicy::Ipv4Only.apply(ips.clone()), vec![v4]);
+        assert_eq!(AddressFamilyPolicy::Ipv6Only.apply(ips), vec![v6]);
+    }
+    
+    #[test]
+    fn test_internal_addresses() {
+        for ip in [
+            "10.1.2.3", "172.16.0.1", "192.168.1.1", "127.0.0.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "198.19.0.1", "::1", "fd00:ec2::254", "fe80::1", "fec0::1", "::ffff:10.0.0.1", "::127.0.0.1",
+            "64:ff9b::a9fe:a9fe", "2002:c0a8:0101::1",
+        ] {
+            assert!(is_internal_address(ip.parse().unwrap()), "{} should be internal", ip);
+        }
+        for ip in [
+            "93.184.216.34", "8.8.8.8", "172.32.0.1", "198.20.0.1", "2606:4700::1111", "::ffff:1.1.1.1",
+            "64:ff9b::808:808", "2002:808:808::1",
+        ] {
+            assert!(!is_internal_address(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_dns_cache_ttl_and_negative_caching() {
+        use crate::clock::FakeClock;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        #[derive(Debug, Default)]
+        struct CountingResolver(AtomicUsize);
+        
+        #[async_trait::async_trait]
+        impl DnsResolver for CountingResolver {
+            async fn resolve(&self, host: &str) -> Result<Vec<IpAddr>> {
+                Ok(self.resolve_with_ttl(host).await?.0)
+            }
+            
+            async fn resolve_with_ttl(&self, host: &str) -> Result<(Vec<IpAddr>, Option<Duration>)> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                match host {
+                    "short.test" => Ok((vec![IpAddr::from([10, 0, 0, 1])], Some(Duration::from_secs(1)))),
+                    "long.test" => Ok((vec![IpAddr::from([10, 0, 0, 2])], Some(Duration::from_secs(86400)))),
+                    _ => Err(HttpError::IoError(format!("no such host '{}'", host))),
+                }
+            }
+            
+            fn name(&self) -> &'static str {
+                "CountingResolver"
+            }
+        }
+        
+        let clock = FakeClock::new();
+        let resolver = CountingResolver::default();
+        let cache = DnsCache::new()
+            .with_ttl(Duration::from_secs(10), Duration::from_secs(60))
+            .with_negative_ttl(Duration::from_secs(2))
+            .with_clock(Arc::new(clock.clone()));
+        let lookups = || resolver.0.load(Ordering::SeqCst);
+        
+        cache.resolve(&resolver, "short.test").await.unwrap();
+        cache.resolve(&resolver, "SHORT.test").await.unwrap();
+        cache.resolve(&resolver, "long.test").await.unwrap();
+        assert!(cache.resolve(&resolver, "missing.test").await.is_err());
+        assert!(cache.resolve(&resolver, "missing.test").await.is_err());
+        assert_eq!(lookups(), 3);
+        
+        // short.test is held for the 10s minimum, long.test capped at 60s
+        clock.advance(Duration::from_secs(5));
+        cache.resolve(&resolver, "short.test").await.unwrap();
+        assert!(cache.resolve(&resolver, "missing.test").await.is_err());
+        assert_eq!(lookups(), 4);
+        clock.advance(Duration::from_secs(10));
+        cache.resolve(&resolver, "short.test").await.unwrap();
+        cache.resolve(&resolver, "long.test").await.unwrap();
+        assert_eq!(lookups(), 5);
+        clock.advance(Duration::from_secs(60));
+        cache.resolve(&resolver, "long.test").await.unwrap();
+        assert_eq!(lookups(), 6);
+        
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.negative_hits, stats.entries), (3, 6, 1, 1));
+        
+        // a full cache evicts the entry closest to expiry
+        let small = DnsCache::new().with_max_entries(2).with_clock(Arc::new(clock.clone()));
+        small.resolve(&resolver, "long.test").await.unwrap();
+        small.resolve(&resolver, "short.test").await.unwrap();
+        assert!(small.resolve(&resolver, "other.test").await.is_err());
+        assert_eq!(small.stats().entries, 2);
+        small.resolve(&resolver, "long.test").await.unwrap();
+        assert_eq!(small.stats().hits, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_system_resolver_localhost() {
+        let ips = SystemResolver::new().resolve("localhost").await.unwrap();
+        assert!(ips.iter().any(|ip| ip.is_loopback()));
+    }
+}
@@ -0,0 +1,454 @@
+// src/endpoints.rs
+// Multiple base URLs with health checking
+
+use crate::error::{HttpError, Result};
+use reqwest::{StatusCode, Url};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Background health probe settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheck {
+    /// Path probed on each endpoint, relative to the endpoint URL
+    pub path: String,
+    pub interval: Duration,
+    pub timeout: Duration,
+    /// Status that counts as healthy; any 2xx when `None`
+    pub expected_status: Option<StatusCode>,
+    /// Consecutive failures before an endpoint is marked unhealthy
+    pub unhealthy_threshold: u32,
+    /// Consecutive successes before an unhealthy endpoint is marked healthy
+    pub healthy_threshold: u32,
+}
+
+impl HealthCheck {
+    /// Probe `path` every 10 seconds
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(2),
+            expected_status: None,
+            unhealthy_threshold: 2,
+            healthy_threshold: 2,
+        }
+    }
+    
+    /// Set how often endpoints are probed
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+    
+    /// Set the timeout for each probe
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Only count this exact status as healthy
+    pub fn with_expected_status(mut self, status: StatusCode) -> Self {
+        self.expected_status = Some(status);
+        self
+    }
+    
+    /// Set how many consecutive results flip an endpoint's state
+    pub fn with_thresholds(mut self, unhealthy: u32, healthy: u32) -> Self {
+        self.unhealthy_threshold = unhealthy.max(1);
+        self.healthy_threshold = healthy.max(1);
+        self
+    }
+    
+    fn is_success(&self, status: StatusCode) -> bool {
+        match self.expected_status {
+            Some(expected) => status == expected,
+            None => status.is_success(),
+        }
+    }
+}
+
+/// Snapshot of one endpoint's health
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointHealth {
+    pub url: Url,
+    pub healthy: bool,
+    /// When the endpoint was last probed
+    pub last_checked: Option<Instant>,
+}
+
+/// A base URL with its weight, health and load
+#[derive(Debug)]
+pub struct Endpoint {
+    url: Url,
+    weight: u32,
+    in_flight: AtomicUsize,
+    healthy: AtomicBool,
+    failures: AtomicU32,
+    successes: AtomicU32,
+    last_checked: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn new(url: Url, weight: u32) -> Self {
+        Self {
+            url,
+            weight: weight.max(1),
+            in_flight: AtomicUsize::new(0),
+            healthy: AtomicBool::new(true),
+            failures: AtomicU32::new(0),
+            successes: AtomicU32::new(0),
+            last_checked: Mutex::new(None),
+        }
+    }
+    
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+    
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+    
+    /// Requests currently being sent to this endpoint
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+    
+    /// Whether `url` was resolved against this endpoint
+    fn serves(&self, url: &Url) -> bool {
+        url.origin() == self.url.origin() && url.path().starts_with(self.url.path())
+    }
+    
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+    
+    /// Record a probe result, flipping health once a threshold is reached
+    fn record(&self, success: bool, check: &HealthCheck) {
+        *self.last_checked.lock().unwrap() = Some(Instant::now());
+        
+        if success {
+            self.failures.store(0, Ordering::Relaxed);
+            let successes = self.successes.fetch_add(1, Ordering::Relaxed) + 1;
+            if !self.is_healthy() && successes >= check.healthy_threshold {
+                log::info!("Endpoint {} is healthy again", self.url);
+                self.healthy.store(true, Ordering::Relaxed);
+            }
+        } else {
+            self.successes.store(0, Ordering::Relaxed);
+            let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+            if self.is_healthy() && failures >= check.unhealthy_threshold {
+                log::warn!("Endpoint {} marked unhealthy", self.url);
+                self.healthy.store(false, Ordering::Relaxed);
+            }
+        }
+    }
+    
+    fn health(&self) -> EndpointHealth {
+        EndpointHealth {
+            url: self.url.clone(),
+            healthy: self.is_healthy(),
+            last_checked: *self.last_checked.lock().unwrap(),
+        }
+    }
+}
+
+/// Chooses which endpoint serves a request
+///
+/// `candidates` holds the healthy endpoints in configured order, or every
+/// endpoint when none are healthy, and is never empty.
+pub trait Balancer: Send + Sync + Debug {
+    fn select<'a>(&self, candidates: &[&'a Endpoint]) -> &'a Endpoint;
+}
+
+/// Always use the first candidate, so later endpoints only act as failovers
+#[derive(Debug, Default)]
+pub struct PriorityBalancer;
+
+impl Balancer for PriorityBalancer {
+    fn select<'a>(&self, candidates: &[&'a Endpoint]) -> &'a Endpoint {
+        candidates[0]
+    }
+}
+
+/// Rotate through the candidates
+#[derive(Debug, Default)]
+pub struct RoundRobinBalancer {
+    next: AtomicUsize,
+}
+
+impl RoundRobinBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Balancer for RoundRobinBalancer {
+    fn select<'a>(&self, candidates: &[&'a Endpoint]) -> &'a Endpoint {
+        candidates[self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()]
+    }
+}
+
+/// Rotate through the candidates in proportion to their weights
+#[derive(Debug, Default)]
+pub struct WeightedBalancer {
+    next: AtomicUsize,
+}
+
+impl WeightedBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Balancer for WeightedBalancer {
+    fn select<'a>(&self, candidates: &[&'a Endpoint]) -> &'a Endpoint {
+        let total: usize = candidates.iter().map(|endpoint| endpoint.weight() as usize).sum();
+        let mut slot = self.next.fetch_add(1, Ordering::Relaxed) % total;
+        for endpoint in candidates {
+            let weight = endpoint.weight() as usize;
+            if slot < weight {
+                return endpoint;
+            }
+            slot -= weight;
+        }
+        candidates[0]
+    }
+}
+
+/// Use the candidate with the fewest requests in flight
+#[derive(Debug, Default)]
+pub struct LeastInFlightBalancer;
+
+impl Balancer for LeastInFlightBalancer {
+    fn select<'a>(&self, candidates: &[&'a Endpoint]) -> &'a Endpoint {
+        candidates
+            .iter()
+            .min_by_key(|endpoint| endpoint.in_flight())
+            .copied()
+            .unwrap_or(candidates[0])
+    }
+}
+
+/// Marks a request as in flight to an endpoint until dropped
+#[derive(Debug)]
+pub struct InFlightGuard<'a> {
+    endpoint: &'a Endpoint,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.endpoint.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The base URLs a client spreads relative requests over
+#[derive(Debug)]
+pub struct EndpointSet {
+    endpoints: Vec<Endpoint>,
+    balancer: Arc<dyn Balancer>,
+}
+
+impl EndpointSet {
+    /// Create a set from weighted URLs, failing over in the given order
+    pub fn new(urls: Vec<(Url, u32)>) -> Result<Self> {
+        if urls.is_empty() {
+            return Err(HttpError::ConfigError("At least one endpoint is required".to_string()));
+        }
+        
+        Ok(Self {
+            endpoints: urls.into_iter().map(|(url, weight)| Endpoint::new(url, weight)).collect(),
+            balancer: Arc::new(PriorityBalancer),
+        })
+    }
+    
+    /// Use a different selection strategy
+    pub fn with_balancer(mut self, balancer: Arc<dyn Balancer>) -> Self {
+        self.balancer = balancer;
+        self
+    }
+    
+    pub fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+    
+    /// Pick the endpoint for the next request
+    pub fn select(&self) -> &Endpoint {
+        let healthy: Vec<&Endpoint> = self.endpoints.iter().filter(|endpoint| endpoint.is_healthy()).collect();
+        if healthy.is_empty() {
+            let all: Vec<&Endpoint> = self.endpoints.iter().collect();
+            self.balancer.select(&all)
+        } else {
+            self.balancer.select(&healthy)
+        }
+    }
+    
+    /// Find the endpoint `url` was resolved against
+    pub fn endpoint_for(&self, url: &Url) -> Option<&Endpoint> {
+        self.endpoints.iter().find(|endpoint| endpoint.serves(url))
+    }
+    
+    /// Move `url` to another endpoint that is not in `tried`
+    ///
+    /// Returns `None` when `url` is not on an endpoint or every endpoint has
+    /// been tried.
+    pub fn failover(&self, url: &Url, tried: &[Url]) -> Option<Url> {
+        let from = self.endpoint_for(url)?;
+        let candidates: Vec<&Endpoint> = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| !tried.contains(&endpoint.url) && endpoint.url != from.url)
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        
+        let healthy: Vec<&Endpoint> = candidates.iter().copied().filter(|endpoint| endpoint.is_healthy()).collect();
+        let to = if healthy.is_empty() {
+            self.balancer.select(&candidates)
+        } else {
+            self.balancer.select(&healthy)
+        };
+        
+        let prefix = from.url.as_str().trim_end_matches('/');
+        let rest = url.as_str().strip_prefix(prefix)?;
+        Url::parse(&format!("{}{}", to.url.as_str().trim_end_matches('/'), rest)).ok()
+    }
+    
+    /// Count a request to `url` against the endpoint it was resolved to
+    pub fn track(&self, url: &Url) -> Option<InFlightGuard<'_>> {
+        let endpoint = self.endpoint_for(url)?;
+        endpoint.in_flight.fetch_add(1, Ordering::Relaxed);
+        Some(InFlightGuard { endpoint })
+    }
+    
+    /// Get the health of every endpoint
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.endpoints.iter().map(Endpoint::health).collect()
+    }
+    
+    /// Probe every endpoint once
+    pub async fn probe(&self, client: &reqwest::Client, check: &HealthCheck) {
+        let probes = self.endpoints.iter().map(|endpoint| async move {
+            let success = match endpoint.url.join(check.path.trim_start_matches('/')) {
+                Ok(url) => match client.get(url).timeout(check.timeout).send().await {
+                    Ok(response) => check.is_success(response.status()),
+                    Err(e) => {
+                        log::debug!("Health probe of {} failed: {}", endpoint.url, e);
+                        false
+                    }
+                },
+                Err(_) => false,
+            };
+            endpoint.record(success, check);
+        });
+        
+        futures::future::join_all(probes).await;
+    }
+}
+
+/// Handle to running health checks; dropping it stops them
+#[derive(Debug)]
+pub struct HealthCheckHandle {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl HealthCheckHandle {
+    pub(crate) fn spawn(endpoints: Arc<EndpointSet>, client: reqwest::Client, check: HealthCheck) -> Self {
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(check.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                endpoints.probe(&client, &check).await;
+            }
+        });
+        
+        Self { task }
+    }
+    
+    /// Stop probing
+    pub fn stop(self) {}
+}
+
+impl Drop for HealthCheckHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn urls(urls: &[&str]) -> Vec<(Url, u32)> {
+        urls.iter().map(|url| (Url::parse(url).unwrap(), 1)).collect()
+    }
+    
+    fn hosts(set: &EndpointSet, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|_| set.select().url().host_str().unwrap().to_string())
+            .collect()
+    }
+    
+    #[test]
+    fn test_thresholds_and_failover_selection() {
+        let set = EndpointSet::new(urls(&["http://a.example/", "http://b.example/"])).unwrap();
+        let check = HealthCheck::new("/health").with_thresholds(2, 1);
+        let primary = &set.endpoints()[0];
+        
+        primary.record(false, &check);
+        assert!(primary.is_healthy());
+        primary.record(false, &check);
+        assert!(!primary.is_healthy());
+        assert_eq!(set.select().url().host_str(), Some("b.example"));
+        
+        set.endpoints()[1].record(false, &check);
+        set.endpoints()[1].record(false, &check);
+        assert_eq!(set.select().url().host_str(), Some("a.example"));
+        
+        primary.record(true, &check);
+        assert!(primary.is_healthy());
+        assert!(set.health()[0].last_checked.is_some());
+        assert!(EndpointSet::new(Vec::new()).is_err());
+    }
+    
+    #[test]
+    fn test_balancers() {
+        let set = EndpointSet::new(urls(&["http://a.example/", "http://b.example/"]))
+            .unwrap()
+            .with_balancer(Arc::new(RoundRobinBalancer::new()));
+        assert_eq!(hosts(&set, 4), ["a.example", "b.example", "a.example", "b.example"]);
+        
+        let weighted = vec![
+            (Url::parse("http://a.example/").unwrap(), 3),
+            (Url::parse("http://b.example/").unwrap(), 1),
+        ];
+        let set = EndpointSet::new(weighted).unwrap().with_balancer(Arc::new(WeightedBalancer::new()));
+        assert_eq!(hosts(&set, 4), ["a.example", "a.example", "a.example", "b.example"]);
+        
+        let set = EndpointSet::new(urls(&["http://a.example/api/", "http://b.example/"]))
+            .unwrap()
+            .with_balancer(Arc::new(LeastInFlightBalancer));
+        let guard = set.track(&Url::parse("http://a.example/api/users").unwrap()).unwrap();
+        assert_eq!(set.endpoints()[0].in_flight(), 1);
+        assert_eq!(set.select().url().host_str(), Some("b.example"));
+        drop(guard);
+        assert_eq!(set.endpoints()[0].in_flight(), 0);
+        assert_eq!(set.select().url().host_str(), Some("a.example"));
+        assert!(set.track(&Url::parse("http://a.example/other").unwrap()).is_none());
+    }
+    
+    #[test]
+    fn test_failover_rebases_url() {
+        let set = EndpointSet::new(urls(&["http://a.example/api", "http://b.example/v2/"])).unwrap();
+        let url = Url::parse("http://a.example/api/users?page=2").unwrap();
+        
+        let moved = set.failover(&url, &[]).unwrap();
+        assert_eq!(moved.as_str(), "http://b.example/v2/users?page=2");
+        assert!(set.failover(&moved, &[url.clone(), set.endpoints()[0].url().clone()]).is_none());
+        assert!(set.failover(&Url::parse("http://other.example/").unwrap(), &[]).is_none());
+    }
+}
@@ -1,5 +1,8 @@
 
+use crate::graphql::GraphqlError;
+use crate::jsonrpc::JsonRpcError;
 use reqwest::StatusCode;
+use std::fmt;
 use thiserror::Error;
 
 /// Custom error type for the HTTP client SDK
@@ -38,12 +41,70 @@ pub enum HttpError {
     #[error("IO error: {0}")]
     IoError(String),
 
+    #[error("IO error: {context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Offline mode blocked a network request to {0}")]
+    OfflineViolation(String),
+
+    #[error("Connection pool exhausted: {0}")]
+    PoolExhausted(String),
+
+    #[error("Request to {0} leaves the base URL's host")]
+    CrossHostViolation(String),
+
+    #[error("Background queue is full, dropped request to {0}")]
+    QueueFull(String),
+
+    #[error("Request to {0} blocked: it resolves to a private or reserved address")]
+    BlockedAddress(String),
+
+    #[error("All {} requests failed: {}", .0.len(), describe_failures(.0))]
+    AllFailed(Vec<(String, HttpError)>),
+
+    #[error("GraphQL error: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    GraphqlErrors(Vec<GraphqlError>),
+
+    #[error("JSON-RPC error: {0}")]
+    JsonRpcError(JsonRpcError),
+
+    #[error("XML-RPC fault: {0}")]
+    XmlRpcFault(XmlRpcFault),
+
     #[error("Unknown error: {0}")]
 Unknown(String),
 
 
 
   
+}
+
+/// A `<fault>` returned instead of an XML-RPC method result
+///
+/// Defined here rather than in `xmlrpc` so `HttpError` has the same
+/// variants whether or not the `xml` feature is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlRpcFault {
+    pub code: i64,
+    pub message: String,
+}
+
+impl fmt::Display for XmlRpcFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+fn describe_failures(failures: &[(String, HttpError)]) -> String {
+    failures
+        .iter()
+        .map(|(url, error)| format!("{}: {}", url, error))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 /// Result type alias to simplify return types
@@ -55,6 +116,16 @@ impl From<serde_json::Error> for HttpError {
     }
 }
 
+impl HttpError {
+    /// An `Io` error for `source`, saying what was being done in `context`
+    pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        HttpError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+}
+
 impl From<url::ParseError> for HttpError {
     fn from(err: url::ParseError) -> Self {
         HttpError::UrlError(err.to_string())
@@ -79,4 +150,16 @@ mod tests {
         };
         assert_eq!(error.to_string(), "HTTP error 404 Not Found: Not found");
     }
+    
+    #[test]
+    fn test_io_error_keeps_source() {
+        let source = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let error = HttpError::io("Failed to write out.bin", source);
+        assert_eq!(error.to_string(), "IO error: Failed to write out.bin: denied");
+        match &error {
+            HttpError::Io { source, .. } => assert_eq!(source.kind(), std::io::ErrorKind::PermissionDenied),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(std::error::Error::source(&error).is_some());
+    }
 }
\ No newline at end of file
@@ -20,7 +20,7 @@ async fn run_example() -> Result<()> {
     // Create a client with base URL and default headers
     let client = HttpClient::with_config(
         ClientConfig::new()
-            .with_base_url("https://jsonplaceholder.typicode.com")
+            .with_base_url("https://jsonplaceholder.typicode.com")?
             .with_json_headers()?
             .with_timeout(Duration::from_secs(30))
     )?;
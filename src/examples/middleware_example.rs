@@ -27,7 +27,7 @@ async fn run_middleware_example() -> Result<()> {
     // Create a client with multiple middleware
     let client = HttpClient::with_config(
         ClientConfig::new()
-            .with_base_url("https://jsonplaceholder.typicode.com")
+            .with_base_url("https://jsonplaceholder.typicode.com")?
             .with_json_headers()?
             .with_timeout(Duration::from_secs(30))
     )?
@@ -72,7 +72,7 @@ async fn run_middleware_example() -> Result<()> {
     println!("\n3. Client with API key middleware:");
     let api_key_client = HttpClient::with_config(
         ClientConfig::new()
-            .with_base_url("https://api.example.com")
+            .with_base_url("https://api.example.com")?
             .with_json_headers()?
     )?
     .with_middleware(AuthMiddleware::api_key("X-API-Key", "your-secret-api-key"))
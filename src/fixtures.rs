@@ -0,0 +1,93 @@
+// src/fixtures.rs
+// JSON fixture files as mock responses
+
+use crate::error::{HttpError, Result};
+use crate::transport::{render_template, Mock, MockHandle, MockResponse, MockTransport};
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Loads JSON fixture files from a directory as `MockResponse`s
+///
+/// Fixture bodies may contain `{{name}}` placeholders. Variables set with
+/// `with_var` are filled in when the file is loaded; any placeholders left
+/// over are filled per request from the mock's `{name}` path segments.
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    dir: PathBuf,
+    vars: HashMap<String, String>,
+}
+
+impl Fixtures {
+    /// Load fixtures relative to `dir`
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            vars: HashMap::new(),
+        }
+    }
+    
+    /// Fill `{{name}}` with `value` in every fixture
+    pub fn with_var(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.vars.insert(name.into(), value.to_string());
+        self
+    }
+    
+    /// Load `name` as a `200 OK` JSON response
+    pub fn load(&self, name: &str) -> Result<MockResponse> {
+        self.load_with_status(name, StatusCode::OK)
+    }
+    
+    /// Load `name` as a JSON response with `status`
+    pub fn load_with_status(&self, name: &str, status: StatusCode) -> Result<MockResponse> {
+        let path = self.dir.join(name);
+        let template = fs::read_to_string(&path)
+            .map_err(|e| HttpError::IoError(format!("Failed to read fixture {}: {}", path.display(), e)))?;
+        let body = render_template(&template, |name| self.vars.get(name).map(String::as_str));
+        
+        Ok(MockResponse::new(status)
+            .with_header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .with_body(body)
+            .templated())
+    }
+    
+    /// Answer requests matching `mock` with the fixture `name`
+    pub fn register(&self, transport: &MockTransport, mock: Mock, name: &str) -> Result<MockHandle> {
+        Ok(transport.expect(mock.respond_with(self.load(name)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[tokio::test]
+    async fn test_fixtures_fill_vars_and_path_params() {
+        let dir = std::env::temp_dir().join(format!("rusty-http-fixtures-{}", std::process::id()));
+        fs::create_dir_all(dir.join("users")).unwrap();
+        fs::write(dir.join("users/show.json"), r#"{"id": {{id}}, "org": "{{ org }}"}"#).unwrap();
+        
+        let transport = MockTransport::new();
+        let fixtures = Fixtures::new(&dir).with_var("org", "acme");
+        let show = fixtures
+            .register(&transport, Mock::get("/users/{id}"), "users/show.json")
+            .unwrap();
+        transport.expect(Mock::get("/files/*"));
+        assert!(fixtures.load("missing.json").is_err());
+        
+        let client = transport.client();
+        let user: serde_json::Value = client.get_json("/users/7").await.unwrap();
+        assert_eq!(user, json!({"id": 7, "org": "acme"}));
+        let user: serde_json::Value = client.get_json("/users/12").await.unwrap();
+        assert_eq!(user["id"], 12);
+        
+        assert!(client.get("/users/7/posts").await.is_err());
+        assert!(client.get("/files/a/b.txt").await.is_ok());
+        assert_eq!(show.calls(), 2);
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,56 @@
+// src/global.rs
+// Process-wide default client and free-function shortcuts
+
+use crate::client::{ClientConfig, HttpClient};
+use crate::error::{HttpError, Result};
+use reqwest::Response;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::OnceLock;
+
+static DEFAULT_CLIENT: OnceLock<HttpClient> = OnceLock::new();
+
+/// Initialize the process-wide default client with a custom configuration
+///
+/// Must be called before the default client is first used; fails with a
+/// `ConfigError` if it has already been initialized.
+pub fn init_default_client(config: ClientConfig) -> Result<()> {
+    let client = HttpClient::with_config(config)?;
+    DEFAULT_CLIENT.set(client).map_err(|_| {
+        HttpError::ConfigError("default client is already initialized".to_string())
+    })
+}
+
+/// Get the process-wide default client, creating it with default settings if needed
+pub fn default_client() -> &'static HttpClient {
+    DEFAULT_CLIENT.get_or_init(HttpClient::new)
+}
+
+/// Send a GET request with the default client
+pub async fn get(url: &str) -> Result<Response> {
+    default_client().get(url).await
+}
+
+/// Send a GET request with the default client and deserialize the response as JSON
+pub async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    default_client().get_json(url).await
+}
+
+/// Send a POST request with a JSON body using the default client
+pub async fn post_json<T: Serialize, R: DeserializeOwned>(url: &str, body: &T) -> Result<R> {
+    default_client().post_json(url, body).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_default_client_initialized_once() {
+        let client = default_client();
+        assert!(std::ptr::eq(client, default_client()));
+        assert!(matches!(
+            init_default_client(ClientConfig::new()),
+            Err(HttpError::ConfigError(_))
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+// src/graphql.rs
+// GraphQL queries over HttpClient
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use crate::response::parse_json;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// An entry of a GraphQL response's `errors` list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphqlError {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<GraphqlLocation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Map<String, Value>>,
+}
+
+impl GraphqlError {
+    /// The `extensions.code` value, if the server sent one
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+}
+
+impl fmt::Display for GraphqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if !self.path.is_empty() {
+            let path: Vec<String> = self.path.iter().map(|segment| segment.to_string().replace('"', "")).collect();
+            write!(f, " (at {})", path.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+/// Position in the query document an error refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphqlLocation {
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    data: Option<Value>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+/// A GraphQL operation being built; see `HttpClient::graphql`
+#[derive(Debug)]
+pub struct GraphqlRequest<'a> {
+    client: &'a HttpClient,
+    url: String,
+    query: String,
+    variables: Result<Option<Value>>,
+    operation_name: Option<String>,
+    persisted: bool,
+}
+
+impl<'a> GraphqlRequest<'a> {
+    /// Set the query or mutation document
+    pub fn query(mut self, document: impl Into<String>) -> Self {
+        self.query = document.into();
+        self
+    }
+    
+    /// Set the operation's variables
+    ///
+    /// Serialization errors are reported by `send`.
+    pub fn variables<V: Serialize>(mut self, variables: &V) -> Self {
+        self.variables = serde_json::to_value(variables).map(Some).map_err(HttpError::from);
+        self
+    }
+    
+    /// Pick an operation from a document containing several
+    pub fn operation_name(mut self, name: impl Into<String>) -> Self {
+        self.operation_name = Some(name.into());
+        self
+    }
+    
+    /// Send the query as an automatic persisted query
+    ///
+    /// Only the document's SHA-256 hash is sent at first; if the server
+    /// doesn't know it yet the request is repeated with the full document.
+    pub fn persisted(mut self) -> Self {
+        self.persisted = true;
+        self
+    }
+    
+    /// Send the operation and deserialize its `data`
+    ///
+    /// A response with a non-empty `errors` list fails with
+    /// `HttpError::GraphqlErrors`, even if it also carries partial data.
+    pub async fn send<T: DeserializeOwned>(mut self) -> Result<T> {
+        let variables = std::mem::replace(&mut self.variables, Ok(None))?;
+        let mut body = Map::new();
+        if let Some(variables) = variables {
+            body.insert("variables".to_string(), variables);
+        }
+        if let Some(name) = &self.operation_name {
+            body.insert("operationName".to_string(), name.clone().into());
+        }
+        
+        if self.persisted {
+            let hash: String = Sha256::digest(self.query.as_bytes())
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            body.insert(
+                "extensions".to_string(),
+                json!({"persistedQuery": {"version": 1, "sha256Hash": hash}}),
+            );
+            
+            let envelope = self.post(&body).await?;
+            let not_found = envelope.errors.iter().any(|error| {
+                error.code() == Some("PERSISTED_QUERY_NOT_FOUND") || error.message == "PersistedQueryNotFound"
+            });
+            if !not_found {
+                return into_data(envelope);
+            }
+        }
+        
+        body.insert("query".to_string(), self.query.clone().into());
+        into_data(self.post(&body).await?)
+    }
+    
+    async fn post(&self, body: &Map<String, Value>) -> Result<Envelope> {
+        let request = self.client.request(Method::POST, &self.url)?.json(body).build()?;
+        let response = self.client.execute(request).await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        
+        // servers may answer errors with 4xx, but only a GraphQL body says why
+        match serde_json::from_slice::<Envelope>(&body) {
+            Ok(envelope) if status.is_success() || !envelope.errors.is_empty() => Ok(envelope),
+            _ if !status.is_success() => Err(HttpError::ResponseError {
+                status,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            }),
+            _ => parse_json(&body),
+        }
+    }
+}
+
+fn into_data<T: DeserializeOwned>(envelope: Envelope) -> Result<T> {
+    if !envelope.errors.is_empty() {
+        return Err(HttpError::GraphqlErrors(envelope.errors));
+    }
+    Ok(serde_json::from_value(envelope.data.unwrap_or(Value::Null))?)
+}
+
+impl HttpClient {
+    /// Start a GraphQL operation against the endpoint at `url`
+    pub fn graphql(&self, url: &str) -> GraphqlRequest<'_> {
+        GraphqlRequest {
+            client: self,
+            url: url.to_string(),
+            query: String::new(),
+            variables: Ok(None),
+            operation_name: None,
+            persisted: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    
+    #[derive(Debug, Deserialize)]
+    struct UserData {
+        user: User,
+    }
+    
+    #[derive(Debug, Deserialize)]
+    struct User {
+        name: String,
+    }
+    
+    const QUERY: &str = "query User($id: ID!) { user(id: $id) { name } }";
+    
+    #[tokio::test]
+    async fn test_graphql_query_and_errors() {
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::post("/graphql")
+                .with_json_body(&json!({"query": QUERY, "variables": {"id": "1"}}))
+                .unwrap()
+                .respond_with(MockResponse::json(StatusCode::OK, &json!({"data": {"user": {"name": "ada"}}})).unwrap()),
+        );
+        transport.expect(
+            Mock::post("/graphql").respond_with(
+                MockResponse::json(
+                    StatusCode::OK,
+                    &json!({"data": null, "errors": [{"message": "not found", "path": ["user"]}]}),
+                )
+                .unwrap(),
+            ),
+        );
+        let client = transport.client();
+        
+        let data: UserData = client
+            .graphql("/graphql")
+            .query(QUERY)
+            .variables(&json!({"id": "1"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(data.user.name, "ada");
+        
+        let missing = client.graphql("/graphql").query(QUERY).send::<UserData>().await;
+        match missing {
+            Err(HttpError::GraphqlErrors(errors)) => assert_eq!(errors[0].to_string(), "not found (at user)"),
+            other => panic!("expected GraphQL errors, got {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_persisted_query_falls_back_to_document() {
+        let transport = MockTransport::new();
+        let full = transport.expect(
+            Mock::post("/graphql")
+                .with_json_body(&json!({
+                    "query": QUERY,
+                    "extensions": {"persistedQuery": {
+                        "version": 1,
+                        "sha256Hash": "8a8cd68d922ca386a0506a461b8fb5080cbd1676f42d11a93cc454dbfcd767b4"
+                    }}
+                }))
+                .unwrap()
+                .respond_with(MockResponse::json(StatusCode::OK, &json!({"data": {"user": {"name": "ada"}}})).unwrap()),
+        );
+        let hashed = transport.expect(
+            Mock::post("/graphql").respond_with(
+                MockResponse::json(
+                    StatusCode::OK,
+                    &json!({"errors": [{"message": "PersistedQueryNotFound"}]}),
+                )
+                .unwrap(),
+            ),
+        );
+        let client = transport.client();
+        
+        let data: UserData = client.graphql("/graphql").query(QUERY).persisted().send().await.unwrap();
+        assert_eq!(data.user.name, "ada");
+        assert_eq!(hashed.calls(), 1);
+        assert_eq!(full.calls(), 1);
+        assert!(transport.requests()[0].json().unwrap().get("query").is_none());
+    }
+}
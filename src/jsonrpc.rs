@@ -0,0 +1,307 @@
+// src/jsonrpc.rs
+// JSON-RPC 2.0 calls over HttpClient
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use crate::response::parse_json;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// The `error` object of a failed JSON-RPC call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+}
+
+impl fmt::Display for JsonRpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    #[serde(default)]
+    id: Value,
+    result: Option<Value>,
+    error: Option<JsonRpcError>,
+}
+
+impl RpcResponse {
+    fn into_result(self) -> Result<Value> {
+        match self.error {
+            Some(error) => Err(HttpError::JsonRpcError(error)),
+            None => Ok(self.result.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+/// A JSON-RPC 2.0 endpoint reached through an `HttpClient`
+///
+/// Calls go through `HttpClient::execute`, so middleware, retries and
+/// rate limits apply. Request ids are allocated from a counter shared by
+/// clones of this client.
+#[derive(Debug, Clone)]
+pub struct JsonRpcClient {
+    client: HttpClient,
+    url: String,
+    next_id: Arc<AtomicU64>,
+}
+
+impl JsonRpcClient {
+    /// Send calls to the endpoint at `url`
+    pub fn new(client: HttpClient, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+    
+    /// Call `method` and deserialize its result
+    ///
+    /// Pass `()` for a method without params. An error object in the
+    /// response fails with `HttpError::JsonRpcError`.
+    pub async fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: &P) -> Result<R> {
+        let id = self.next_id();
+        let response = self.post(&request(method, params, Some(id))?).await?;
+        let response: RpcResponse = serde_json::from_value(response)?;
+        if response.id != json!(id) && response.error.is_none() {
+            return Err(HttpError::JsonError(format!(
+                "JSON-RPC response id {} does not match request id {}",
+                response.id, id
+            )));
+        }
+        Ok(serde_json::from_value(response.into_result()?)?)
+    }
+    
+    /// Send a notification, which the server doesn't answer
+    pub async fn notify<P: Serialize>(&self, method: &str, params: &P) -> Result<()> {
+        self.post(&request(method, params, None)?).await?;
+        Ok(())
+    }
+    
+    /// Start a batch of calls and notifications sent in one request
+    pub fn batch(&self) -> JsonRpcBatch<'_> {
+        JsonRpcBatch {
+            rpc: self,
+            requests: Ok(Vec::new()),
+            ids: Vec::new(),
+        }
+    }
+    
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+    
+    async fn post(&self, body: &Value) -> Result<Value> {
+        let request = self.client.request(Method::POST, &self.url)?.json(body).build()?;
+        let response = self.client.execute(request).await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        
+        if !status.is_success() {
+            let body = String::from_utf8_lossy(&body).into_owned();
+            return Err(HttpError::ResponseError { status, body });
+        }
+        if body.trim_ascii().is_empty() {
+            return Ok(Value::Null);
+        }
+        parse_json(&body)
+    }
+}
+
+/// Several JSON-RPC requests sent together; see `JsonRpcClient::batch`
+#[derive(Debug)]
+pub struct JsonRpcBatch<'a> {
+    rpc: &'a JsonRpcClient,
+    requests: Result<Vec<Value>>,
+    ids: Vec<u64>,
+}
+
+impl<'a> JsonRpcBatch<'a> {
+    /// Add a call whose result is returned by `send`
+    pub fn call<P: Serialize>(mut self, method: &str, params: &P) -> Self {
+        let id = self.rpc.next_id();
+        self.push(request(method, params, Some(id)));
+        self.ids.push(id);
+        self
+    }
+    
+    /// Add a notification
+    pub fn notify<P: Serialize>(mut self, method: &str, params: &P) -> Self {
+        self.push(request(method, params, None));
+        self
+    }
+    
+    fn push(&mut self, request: Result<Value>) {
+        if let Ok(requests) = &mut self.requests {
+            match request {
+                Ok(request) => requests.push(request),
+                Err(e) => self.requests = Err(e),
+            }
+        }
+    }
+    
+    /// Send the batch
+    ///
+    /// Responses are matched to calls by id and returned in the order the
+    /// calls were added; notifications have no entry. A call the server
+    /// didn't answer gets a `JsonError`.
+    pub async fn send(self) -> Result<Vec<Result<Value>>> {
+        let requests = self.requests?;
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let responses = match self.rpc.post(&Value::Array(requests)).await? {
+            Value::Null => Vec::new(),
+            Value::Array(responses) => responses,
+            // servers answer a batch they can't parse with a single error
+            single => vec![single],
+        };
+        let mut responses: Vec<RpcResponse> = responses
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<std::result::Result<_, _>>()?;
+        
+        if let [response] = responses.as_slice() {
+            if response.id.is_null() && response.error.is_some() {
+                return Err(responses.remove(0).into_result().unwrap_err());
+            }
+        }
+        
+        Ok(self
+            .ids
+            .iter()
+            .map(|id| {
+                let position = responses.iter().position(|response| response.id == json!(id));
+                match position {
+                    Some(position) => responses.swap_remove(position).into_result(),
+                    None => Err(HttpError::JsonError(format!("No JSON-RPC response for request id {}", id))),
+                }
+            })
+            .collect())
+    }
+}
+
+fn request<P: Serialize>(method: &str, params: &P, id: Option<u64>) -> Result<Value> {
+    let mut request = json!({"jsonrpc": "2.0", "method": method});
+    match serde_json::to_value(params)? {
+        Value::Null => {}
+        params @ (Value::Array(_) | Value::Object(_)) => request["params"] = params,
+        _ => {
+            return Err(HttpError::SerializationError(format!(
+                "JSON-RPC params for '{}' must be an array or an object",
+                method
+            )))
+        }
+    }
+    if let Some(id) = id {
+        request["id"] = id.into();
+    }
+    Ok(request)
+}
+
+impl HttpClient {
+    /// Make JSON-RPC calls to the endpoint at `url`
+    pub fn jsonrpc(&self, url: &str) -> JsonRpcClient {
+        JsonRpcClient::new(self.clone(), url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    
+    #[tokio::test]
+    async fn test_call_and_notify() {
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::post("/rpc")
+                .with_json_body(&json!({"jsonrpc": "2.0", "method": "add", "params": [2, 3], "id": 1}))
+                .unwrap()
+                .respond_with(MockResponse::json(StatusCode::OK, &json!({"jsonrpc": "2.0", "result": 5, "id": 1})).unwrap()),
+        );
+        transport.expect(
+            Mock::post("/rpc")
+                .with_json_body(&json!({"jsonrpc": "2.0", "method": "log", "params": {"line": "hi"}}))
+                .unwrap()
+                .respond_with(MockResponse::new(StatusCode::NO_CONTENT)),
+        );
+        transport.expect(
+            Mock::post("/rpc").respond_with(
+                MockResponse::json(
+                    StatusCode::OK,
+                    &json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 2}),
+                )
+                .unwrap(),
+            ),
+        );
+        let client = transport.client();
+        let rpc = client.jsonrpc("/rpc");
+        
+        let sum: i64 = rpc.call("add", &[2, 3]).await.unwrap();
+        assert_eq!(sum, 5);
+        rpc.notify("log", &json!({"line": "hi"})).await.unwrap();
+        
+        match rpc.call::<_, Value>("missing", &()).await {
+            Err(HttpError::JsonRpcError(error)) => assert_eq!(error.code, JsonRpcError::METHOD_NOT_FOUND),
+            other => panic!("expected a JSON-RPC error, got {:?}", other),
+        }
+        assert!(rpc.call::<_, Value>("add", &1).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_batch_correlates_ids() {
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::post("/rpc").respond_with(
+                MockResponse::json(
+                    StatusCode::OK,
+                    &json!([
+                        {"jsonrpc": "2.0", "error": {"code": -32602, "message": "Invalid params"}, "id": 2},
+                        {"jsonrpc": "2.0", "result": "pong", "id": 1}
+                    ]),
+                )
+                .unwrap(),
+            ),
+        );
+        let client = transport.client();
+        
+        let results = client
+            .jsonrpc("/rpc")
+            .batch()
+            .call("ping", &())
+            .notify("log", &["batch"])
+            .call("add", &["a"])
+            .call("lost", &())
+            .send()
+            .await
+            .unwrap();
+        
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), "pong");
+        assert!(matches!(&results[1], Err(HttpError::JsonRpcError(error)) if error.code == -32602));
+        assert!(matches!(&results[2], Err(HttpError::JsonError(_))));
+        assert_eq!(transport.requests()[0].json().unwrap().as_array().unwrap().len(), 4);
+    }
+}
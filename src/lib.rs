@@ -1,31 +1,142 @@
 // src/lib.rs
 
 
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+compile_error!("at least one TLS backend feature must be enabled: `native-tls` or `rustls`");
+
 // Re-export essential types from reqwest for convenience
 pub use reqwest::{Method, StatusCode, Url};
 
 // Public modules
+pub mod api;
+pub mod background;
+pub mod batch;
+pub mod budget;
+pub mod bulkhead;
+pub mod clock;
+pub mod codec;
+pub mod coalesce;
+pub mod compression;
 pub mod client;
+pub mod content_type;
+pub mod cookies;
+pub mod dns;
+pub mod endpoints;
 pub mod error;
+pub mod fixtures;
+pub mod global;
+pub mod graphql;
+pub mod jsonrpc;
 pub mod middleware;
+pub mod openapi;
+pub mod pagination;
+pub mod poll;
+pub mod profile;
+pub mod proxy;
+pub mod rate_limit;
+pub mod response;
+pub mod retry;
+pub mod session;
+pub mod tls;
+pub mod transport;
+pub mod upload;
+pub mod url_template;
+pub mod vcr;
 
 // Optional blocking client
 #[cfg(feature = "blocking")]
 pub mod blocking;
 
+// Optional JSON Schema response validation
+#[cfg(feature = "json-schema")]
+pub mod schema;
+
+// Optional XML request and response helpers
+#[cfg(feature = "xml")]
+pub mod xml;
+
+// Optional XML-RPC client, built on the XML support
+#[cfg(feature = "xml")]
+pub mod xmlrpc;
+
+// Optional YAML request and response helpers
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+// Optional `http_api!` client generator
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod macros;
+
+// Stub server for integration tests
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
 // Utility functions and builders
 pub mod utils;
 
 // Public exports
-pub use client::{ClientConfig, HttpClient, RequestBuilderExt};
-pub use error::{HttpError, Result};
+pub use api::HttpClientApi;
+pub use background::{BackgroundQueue, BackgroundSender, CompletionCallback};
+pub use batch::{BatchFailure, BatchReport, BatchSuccess, ChunkFailure, ChunkedPost, Pipeline};
+pub use budget::Budget;
+pub use bulkhead::Bulkhead;
+pub use clock::{Clock, FakeClock, SystemClock};
+pub use codec::{BodyCodec, CodecRegistry};
+pub use coalesce::{Coalescer, Coalescing};
+pub use compression::{BodyEncoder, RequestCompression};
+pub use client::{
+    ClientConfig, ConnectionHealth, CrossHostPolicy, Decompression, Http2Settings, HttpClient, HttpVersionPref,
+    PathEncoding, PoolOverflow, PoolStats, RequestBuilderExt, TrailingSlash,
+};
+pub use content_type::{guess_content_type, ContentType};
+pub use cookies::{Cookie, CookieStore, JsonFileCookieStore, MemoryCookieStore};
+pub use dns::{is_internal_address, AddressFamilyPolicy, DnsCache, DnsCacheStats, DnsResolver, SystemResolver};
+pub use endpoints::{
+    Balancer, Endpoint, EndpointHealth, HealthCheck, HealthCheckHandle, LeastInFlightBalancer, PriorityBalancer,
+    RoundRobinBalancer, WeightedBalancer,
+};
+pub use error::{HttpError, Result, XmlRpcFault};
+pub use fixtures::Fixtures;
+pub use graphql::{GraphqlError, GraphqlLocation, GraphqlRequest};
+pub use jsonrpc::{JsonRpcBatch, JsonRpcClient, JsonRpcError};
+pub use global::{default_client, get, get_json, init_default_client, post_json};
 pub use middleware::{
-    AuthMiddleware, AuthType, HeaderMiddleware, LoggingMiddleware, 
-    Middleware, RetryMiddleware
+    AffinityMiddleware, AffinitySource, AuthMiddleware, AuthType, HeaderMiddleware, 
+    LoggingMiddleware, Middleware, RetryMiddleware, SpyMiddleware
+};
+pub use openapi::{OpenApi, Operation, OperationRequest, Parameter, ParameterLocation};
+pub use pagination::{
+    collect_pages, paginate, paginate_pages, CollectLimits, Collected, CursorPaginator, Link,
+    OffsetPaginator, Page, PageField, PageMapping, Paginator, StopReason, DEFAULT_MAX_PAGES,
 };
+pub use poll::Poller;
+pub use profile::HostProfile;
+pub use proxy::{EnvProxy, NoProxy, ProxyAuth, ProxyConfig, ProxyRoute, ProxyRoutes, ProxyScope};
+pub use rate_limit::{AdaptiveThrottle, RateBudget, RateLimit, RateLimiter, RoutePattern};
+pub use retry::RetryPolicy;
+pub use response::{hal_link, HttpVersion, JsonBody, ResponseExt};
+pub use session::{CsrfSource, LoginRequest, Session};
+pub use tls::{ClientIdentity, RootCertificate, SpkiPin, TlsBackend, TlsVersion};
+pub use transport::{Mock, MockHandle, MockResponse, MockTransport, RecordedRequest, Transport};
+pub use upload::ChunkedFile;
+pub use url_template::UrlTemplate;
+pub use vcr::{CassetteBody, CassetteRequest, CassetteResponse, Interaction, VcrMode, VcrTransport};
+
+#[cfg(feature = "json-schema")]
+pub use schema::{SchemaMode, SchemaValidation};
+
+#[cfg(feature = "xml")]
+pub use xmlrpc::{XmlRpcClient, XmlRpcValue};
+
+#[cfg(feature = "gzip")]
+pub use compression::Gzip;
+
+#[cfg(feature = "zstd")]
+pub use compression::Zstd;
 
 #[cfg(feature = "blocking")]
-pub use blocking::{BlockingClientConfig, BlockingHttpClient, BlockingRequestBuilderExt};
+pub use blocking::{BlockingClientConfig, BlockingHttpClient, BlockingMultipartBuilder, BlockingRequestBuilderExt};
 
 // Re-export common serialization traits
 pub use serde::{Deserialize, Serialize};
@@ -33,13 +144,19 @@ pub use serde::{Deserialize, Serialize};
 // Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// User-Agent sent when none is configured
+pub const DEFAULT_USER_AGENT: &str = concat!("rusty-http-client/", env!("CARGO_PKG_VERSION"));
+
+/// Environment variable that puts new clients in offline mode when set to `1` or `true`
+pub const OFFLINE_ENV_VAR: &str = "RUSTY_HTTP_OFFLINE";
+
 /// Create a new HTTP client with default settings
 pub fn new_client() -> HttpClient {
     HttpClient::new()
 }
 
 /// Create a new HTTP client with a base URL
-pub fn client_with_base_url<S: Into<String>>(base_url: S) -> HttpClient {
+pub fn client_with_base_url<S: AsRef<str>>(base_url: S) -> Result<HttpClient> {
     HttpClient::with_base_url(base_url)
 }
 
@@ -60,10 +177,13 @@ pub mod prelude {
     pub use crate::client::{ClientConfig, HttpClient, RequestBuilderExt};
     pub use crate::error::{HttpError, Result};
     pub use crate::middleware::{AuthMiddleware, AuthType, Middleware};
+    pub use crate::response::ResponseExt;
     pub use crate::{new_client, client_with_base_url};
     
     #[cfg(feature = "blocking")]
-    pub use crate::blocking::{BlockingClientConfig, BlockingHttpClient, BlockingRequestBuilderExt};
+    pub use crate::blocking::{
+        BlockingClientConfig, BlockingHttpClient, BlockingMultipartBuilder, BlockingRequestBuilderExt,
+    };
     #[cfg(feature = "blocking")]
     pub use crate::{new_blocking_client, blocking_client_with_base_url};
     
@@ -80,6 +200,11 @@ mod tests {
         assert!(!VERSION.is_empty());
     }
     
+    #[test]
+    fn test_default_user_agent() {
+        assert_eq!(DEFAULT_USER_AGENT, format!("rusty-http-client/{}", VERSION));
+    }
+    
     #[test]
     fn test_client_creation() {
         let client = new_client();
@@ -88,11 +213,12 @@ mod tests {
     
     #[test]
     fn test_client_with_base_url() {
-        let client = client_with_base_url("https://api.example.com");
+        let client = client_with_base_url("https://api.example.com").unwrap();
         assert_eq!(
-            client.config().base_url,
-            Some("https://api.example.com".to_string())
+            client.config().base_url.as_ref().map(|url| url.as_str()),
+            Some("https://api.example.com/")
         );
+        assert!(client_with_base_url("api.example.com").is_err());
     }
     
     #[cfg(feature = "blocking")]
@@ -0,0 +1,220 @@
+// src/macros.rs
+// Declarative API clients generated by `http_api!`
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use crate::url_template::UrlTemplate;
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Define a typed API client backed by `HttpClient`
+///
+/// Each method is declared with its HTTP method and a `UrlTemplate` path.
+/// Template variables (`{name}`, `{?name}`, ...) are filled from the
+/// arguments of the same name, percent-encoded; the remaining arguments
+/// are sent as query parameters, except one marked `#[body]`, which is
+/// sent as JSON. Responses are deserialized as JSON, with an empty body
+/// read as `null` so `()` works.
+/// Doc comments and other attributes go after the HTTP method attribute.
+///
+/// This is a declarative `macro_rules!` macro, not a derive or attribute
+/// macro: it only matches attribute-like syntax, so it needs no proc-macro
+/// crate, but errors in a declaration point at the whole macro call.
+///
+/// The struct gets `new(HttpClient)` and `client()`, plus one async method
+/// per declaration, e.g. `#[get("/users/{id}")] fn get_user(id: u64) -> User;`
+/// becomes `async fn get_user(&self, id: u64) -> Result<User>`.
+#[macro_export]
+macro_rules! http_api {
+    (
+        $(#[$struct_attr:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                #[$method:ident($path:literal)]
+                $(#[$fn_attr:meta])*
+                fn $fn_name:ident($($(#[$arg_attr:ident])? $arg:ident : $arg_ty:ty),* $(,)?) -> $ret:ty;
+            )*
+        }
+    ) => {
+        $(#[$struct_attr])*
+        #[derive(Debug, Clone)]
+        $vis struct $name {
+            client: $crate::HttpClient,
+        }
+        
+        impl $name {
+            /// Send requests through `client`
+            pub fn new(client: $crate::HttpClient) -> Self {
+                Self { client }
+            }
+            
+            /// The underlying client
+            pub fn client(&self) -> &$crate::HttpClient {
+                &self.client
+            }
+            
+            $(
+                $(#[$fn_attr])*
+                pub async fn $fn_name(&self, $($arg: $arg_ty),*) -> $crate::Result<$ret> {
+                    #[allow(unused_mut)]
+                    let mut call = $crate::macros::EndpointCall::new($crate::http_api!(@method $method), $path);
+                    $($crate::http_api!(@arg call, $(#[$arg_attr])? $arg);)*
+                    call.send(&self.client).await
+                }
+            )*
+        }
+    };
+    
+    (@method get) => { $crate::Method::GET };
+    (@method post) => { $crate::Method::POST };
+    (@method put) => { $crate::Method::PUT };
+    (@method patch) => { $crate::Method::PATCH };
+    (@method delete) => { $crate::Method::DELETE };
+    (@method head) => { $crate::Method::HEAD };
+    
+    (@arg $call:ident, #[body] $arg:ident) => { $call.body(&$arg) };
+    (@arg $call:ident, $arg:ident) => { $call.param(stringify!($arg), &$arg) };
+}
+
+/// One request being assembled by a method generated with `http_api!`
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct EndpointCall {
+    method: Method,
+    path: &'static str,
+    params: Vec<(&'static str, String)>,
+    body: Option<Result<Value>>,
+}
+
+impl EndpointCall {
+    pub fn new(method: Method, path: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            params: Vec::new(),
+            body: None,
+        }
+    }
+    
+    pub fn param<V: ToString + ?Sized>(&mut self, name: &'static str, value: &V) {
+        self.params.push((name, value.to_string()));
+    }
+    
+    pub fn body<T: Serialize + ?Sized>(&mut self, value: &T) {
+        self.body = Some(serde_json::to_value(value).map_err(HttpError::from));
+    }
+    
+    /// Expand the path as a `UrlTemplate`, leaving unused params for the query string
+    fn render(&mut self) -> Result<String> {
+        let template = UrlTemplate::parse(self.path)?;
+        let missing = template
+            .path_variables()
+            .into_iter()
+            .find(|name| !self.params.iter().any(|(param, _)| param == name));
+        if let Some(name) = missing {
+            return Err(HttpError::ConfigError(format!("No argument for placeholder '{{{}}}' in '{}'", name, self.path)));
+        }
+        let variables = template.variables();
+        let (used, rest) = std::mem::take(&mut self.params)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(param, _)| variables.contains(param));
+        self.params = rest;
+        let vars: Map<String, Value> = used
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), Value::String(value)))
+            .collect();
+        template.expand(&vars)
+    }
+    
+    pub async fn send<R: DeserializeOwned>(mut self, client: &HttpClient) -> Result<R> {
+        let path = self.render()?;
+        let mut builder = client.request(self.method.clone(), &path)?;
+        if !self.params.is_empty() {
+            builder = builder.query(&self.params);
+        }
+        if let Some(body) = self.body {
+            builder = builder.json(&body?);
+        }
+        
+        let response = client.execute(builder.build()?).await?;
+        let status = response.status();
+        if status.is_success() && (status == StatusCode::NO_CONTENT || response.content_length() == Some(0)) {
+            return Ok(serde_json::from_value(Value::Null)?);
+        }
+        client.process_json_response(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        id: u64,
+        name: String,
+    }
+    
+    crate::http_api! {
+        /// Users endpoints
+        struct UsersApi {
+            #[get("/users/{id}")]
+            fn get_user(id: u64) -> User;
+            
+            #[get("/orgs/{org}/users")]
+            /// Users of an org, paged
+            fn list_users(org: &str, page: u32) -> Vec<User>;
+            
+            #[post("/users")]
+            fn create_user(#[body] user: &User) -> User;
+            
+            #[delete("/users/{id}")]
+            fn delete_user(id: u64) -> ();
+            
+            #[get("/files/{+path}{?rev}")]
+            fn get_file(path: &str, rev: u32) -> User;
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_generated_client() {
+        let transport = MockTransport::new();
+        let ada = || json!({"id": 7, "name": "ada"});
+        transport.expect(Mock::get("/users/7").respond_with(MockResponse::json(StatusCode::OK, &ada()).unwrap()));
+        transport.expect(
+            Mock::get("/orgs/a%20b/users")
+                .with_query("page=2")
+                .respond_with(MockResponse::json(StatusCode::OK, &json!([ada()])).unwrap()),
+        );
+        transport.expect(
+            Mock::post("/users")
+                .with_json_body(&ada())
+                .unwrap()
+                .respond_with(MockResponse::json(StatusCode::CREATED, &ada()).unwrap()),
+        );
+        transport.expect(Mock::delete("/users/7").respond_with(MockResponse::new(StatusCode::NO_CONTENT)));
+        transport.expect(Mock::get("/users/8").respond_with(MockResponse::new(StatusCode::NOT_FOUND)));
+        transport.expect(Mock::delete("/users/8").respond_with(MockResponse::new(StatusCode::NOT_FOUND)));
+        transport.expect(
+            Mock::get("/files/docs/a%20b.md")
+                .with_query("rev=3")
+                .respond_with(MockResponse::json(StatusCode::OK, &ada()).unwrap()),
+        );
+        
+        let api = UsersApi::new(transport.client());
+        let user = User { id: 7, name: "ada".to_string() };
+        assert_eq!(api.get_user(7).await.unwrap(), user);
+        assert_eq!(api.list_users("a b", 2).await.unwrap(), [User { id: 7, name: "ada".to_string() }]);
+        assert_eq!(api.create_user(&user).await.unwrap(), user);
+        api.delete_user(7).await.unwrap();
+        assert!(api.get_user(8).await.is_err());
+        assert!(matches!(api.delete_user(8).await, Err(crate::HttpError::ResponseError { .. })));
+        assert_eq!(api.get_file("docs/a b.md", 3).await.unwrap(), user);
+        assert_eq!(api.client().middleware_count(), 0);
+    }
+}
@@ -1,7 +1,11 @@
 // src/middleware.rs
 use crate::error::{HttpError, Result};
+use crate::transport::RecordedRequest;
+use reqwest::header::{HeaderName, HeaderValue, COOKIE, SET_COOKIE};
 use reqwest::{Request, Response};
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
 
 /// Trait for implementing request/response middleware
 #[async_trait::async_trait]
@@ -266,6 +270,201 @@ impl Middleware for RetryMiddleware {
     }
 }
 
+/// Where a sticky-session load balancer identifies the backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AffinitySource {
+    /// A response header such as `X-Server-Id`
+    Header(HeaderName),
+    /// A routing cookie such as `AWSALB`
+    Cookie(String),
+}
+
+/// Middleware that pins requests to the backend that served the first response
+///
+/// The first response from each host carrying the affinity header or cookie
+/// is remembered and replayed on every later request to that host. Header
+/// affinity is replayed in the same header unless `with_request_header`
+/// says otherwise; cookie affinity is added to the `Cookie` header. Clones
+/// share the captured values.
+#[derive(Debug, Clone)]
+pub struct AffinityMiddleware {
+    pub source: AffinitySource,
+    pub request_header: Option<HeaderName>,
+    captured: Arc<RwLock<HashMap<String, HeaderValue>>>,
+}
+
+impl AffinityMiddleware {
+    /// Capture and replay the response header `name`
+    pub fn header(name: HeaderName) -> Self {
+        Self {
+            source: AffinitySource::Header(name),
+            request_header: None,
+            captured: Arc::default(),
+        }
+    }
+    
+    /// Capture and replay the routing cookie `name`
+    pub fn cookie(name: impl Into<String>) -> Self {
+        Self {
+            source: AffinitySource::Cookie(name.into()),
+            request_header: None,
+            captured: Arc::default(),
+        }
+    }
+    
+    /// Replay header affinity in a different request header
+    pub fn with_request_header(mut self, name: HeaderName) -> Self {
+        self.request_header = Some(name);
+        self
+    }
+    
+    /// Get the affinity value captured for `host`
+    pub fn value_for(&self, host: &str) -> Option<HeaderValue> {
+        self.captured.read().unwrap().get(&host.to_ascii_lowercase()).cloned()
+    }
+    
+    /// Forget all captured values, e.g. after a backend failure
+    pub fn reset(&self) {
+        self.captured.write().unwrap().clear();
+    }
+    
+    /// Extract the affinity value from a response
+    fn extract(&self, response: &Response) -> Option<HeaderValue> {
+        match &self.source {
+            AffinitySource::Header(name) => response.headers().get(name).cloned(),
+            AffinitySource::Cookie(cookie_name) => response
+                .headers()
+                .get_all(SET_COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .filter_map(|value| cookie_store::RawCookie::parse(value).ok())
+                .find(|cookie| cookie.name() == cookie_name)
+                .and_then(|cookie| {
+                    HeaderValue::from_str(&format!("{}={}", cookie.name(), cookie.value())).ok()
+                }),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for AffinityMiddleware {
+    async fn process_request(&self, request: &mut Request) -> Result<()> {
+        let value = match request.url().host_str().and_then(|host| self.value_for(host)) {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        
+        match &self.source {
+            AffinitySource::Header(name) => {
+                let name = self.request_header.as_ref().unwrap_or(name);
+                request.headers_mut().insert(name.clone(), value);
+            }
+            AffinitySource::Cookie(_) => {
+                let cookie = match request.headers().get(COOKIE) {
+                    Some(existing) => {
+                        let mut merged = existing.as_bytes().to_vec();
+                        merged.extend_from_slice(b"; ");
+                        merged.extend_from_slice(value.as_bytes());
+                        HeaderValue::from_bytes(&merged)
+                            .map_err(|e| HttpError::MiddlewareError(e.to_string()))?
+                    }
+                    None => value,
+                };
+                request.headers_mut().insert(COOKIE, cookie);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn process_response(&self, response: &mut Response) -> Result<()> {
+        let host = match response.url().host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return Ok(()),
+        };
+        
+        if self.captured.read().unwrap().contains_key(&host) {
+            return Ok(());
+        }
+        
+        if let Some(value) = self.extract(response) {
+            self.captured.write().unwrap().entry(host).or_insert(value);
+        }
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &'static str {
+        "AffinityMiddleware"
+    }
+}
+
+/// Middleware that records every outgoing request for test assertions
+///
+/// Requests are captured after earlier middleware has run, so register the
+/// spy last to see exactly what goes on the wire. Bodies are only captured
+/// when they are held in memory. Clones share the same log.
+#[derive(Debug, Clone, Default)]
+pub struct SpyMiddleware {
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl SpyMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Every request captured so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+    
+    /// Requests sent to `path`
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.url.path() == path)
+            .cloned()
+            .collect()
+    }
+    
+    /// The most recent request
+    pub fn last_request(&self) -> Option<RecordedRequest> {
+        self.requests.lock().unwrap().last().cloned()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Forget all captured requests
+    pub fn clear(&self) {
+        self.requests.lock().unwrap().clear();
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for SpyMiddleware {
+    async fn process_request(&self, request: &mut Request) -> Result<()> {
+        self.requests.lock().unwrap().push(RecordedRequest::from_request(request));
+        Ok(())
+    }
+    
+    async fn process_response(&self, _response: &mut Response) -> Result<()> {
+        Ok(())
+    }
+    
+    fn name(&self) -> &'static str {
+        "SpyMiddleware"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -286,4 +485,80 @@ mod tests {
         assert_eq!(middleware.headers.len(), 2);
         assert_eq!(middleware.headers.get("X-Custom"), Some(&"value".to_string()));
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_affinity_middleware_replays_first_value() {
+        use crate::client::HttpClient;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for server_id in ["node-1", "node-2", "node-3"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase());
+                let response = format!(
+                    "HTTP/1.1 204 No Content\r\nx-server-id: {}\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+                    server_id
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        
+        let affinity = AffinityMiddleware::header(HeaderName::from_static("x-server-id"));
+        let client = HttpClient::new().with_middleware(affinity.clone());
+        let url = format!("http://127.0.0.1:{}/", port);
+        for _ in 0..3 {
+            client.get(&url).await.unwrap();
+        }
+        
+        let requests = server.await.unwrap();
+        assert!(!requests[0].contains("x-server-id"));
+        assert!(requests[1].contains("x-server-id: node-1"));
+        assert!(requests[2].contains("x-server-id: node-1"));
+        assert_eq!(affinity.value_for("127.0.0.1").unwrap(), "node-1");
+        
+        affinity.reset();
+        assert!(affinity.value_for("127.0.0.1").is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_spy_middleware_captures_requests() {
+        use crate::client::HttpClient;
+        use crate::transport::{Mock, MockTransport};
+        
+        let transport = MockTransport::new();
+        transport.expect(Mock::any("/users"));
+        transport.expect(Mock::any("/health"));
+        
+        let spy = SpyMiddleware::new();
+        let client = HttpClient::with_base_url("http://api.test")
+            .unwrap()
+            .with_middleware(HeaderMiddleware::new().with_header("x-tenant", "acme"))
+            .with_middleware(spy.clone())
+            .with_transport(transport);
+        
+        client.get("/health").await.unwrap();
+        let request = client
+            .request(reqwest::Method::POST, "/users")
+            .unwrap()
+            .json(&serde_json::json!({"name": "ada"}))
+            .build()
+            .unwrap();
+        client.execute(request).await.unwrap();
+        
+        assert_eq!(spy.len(), 2);
+        let created = &spy.requests_to("/users")[0];
+        assert_eq!(created.method, reqwest::Method::POST);
+        assert_eq!(created.headers["x-tenant"], "acme");
+        assert_eq!(created.json().unwrap()["name"], "ada");
+        assert_eq!(spy.last_request().unwrap().url.path(), "/users");
+        
+        spy.clear();
+        assert!(spy.is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+// src/openapi.rs
+// Operations from an OpenAPI document, looked up by operationId
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Method, Request, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Where an operation parameter is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterLocation {
+    Path,
+    Query,
+    Header,
+    Cookie,
+}
+
+/// A parameter declared by an operation or its path item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub location: ParameterLocation,
+    pub required: bool,
+}
+
+/// One operation of an OpenAPI document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    pub id: String,
+    pub method: Method,
+    /// Path template, e.g. `/users/{id}`
+    pub path: String,
+    pub parameters: Vec<Parameter>,
+    pub body_required: bool,
+}
+
+/// The operations of an OpenAPI 3 document, keyed by `operationId`
+///
+/// Only what's needed to build requests is kept: methods, path templates,
+/// parameters and whether a request body is required. Local `$ref`s to
+/// parameters and request bodies are resolved; operations without an
+/// `operationId` are skipped. Paths are resolved against the client's base
+/// URL, not the document's `servers`.
+#[derive(Debug, Clone, Default)]
+pub struct OpenApi {
+    operations: HashMap<String, Operation>,
+}
+
+impl OpenApi {
+    /// Read operations from a parsed document
+    pub fn from_value(document: &Value) -> Result<Self> {
+        let paths = document
+            .get("paths")
+            .and_then(Value::as_object)
+            .ok_or_else(|| HttpError::ConfigError("OpenAPI document has no 'paths' object".to_string()))?;
+        
+        let mut operations = HashMap::new();
+        for (path, item) in paths {
+            let item = resolve(document, item)?;
+            let shared = parameters(document, item.get("parameters"))?;
+            
+            for method in ["get", "put", "post", "delete", "options", "head", "patch", "trace"] {
+                let operation = match item.get(method) {
+                    Some(operation) => operation,
+                    None => continue,
+                };
+                let id = match operation.get("operationId").and_then(Value::as_str) {
+                    Some(id) => id.to_string(),
+                    None => continue,
+                };
+                
+                // operation-level parameters override path-level ones
+                let mut params = parameters(document, operation.get("parameters"))?;
+                for param in &shared {
+                    if !params.iter().any(|p| p.name == param.name && p.location == param.location) {
+                        params.push(param.clone());
+                    }
+                }
+                let body_required = match operation.get("requestBody") {
+                    Some(body) => resolve(document, body)?.get("required").and_then(Value::as_bool).unwrap_or(false),
+                    None => false,
+                };
+                
+                let operation = Operation {
+                    id: id.clone(),
+                    method: Method::from_bytes(method.to_ascii_uppercase().as_bytes()).expect("valid method"),
+                    path: path.clone(),
+                    parameters: params,
+                    body_required,
+                };
+                if operations.insert(id.clone(), operation).is_some() {
+                    return Err(HttpError::ConfigError(format!("Duplicate operationId '{}'", id)));
+                }
+            }
+        }
+        
+        Ok(Self { operations })
+    }
+    
+    /// Parse a JSON document
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::from_value(&serde_json::from_str(json)?)
+    }
+    
+    /// Parse a YAML document
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        Self::from_value(&crate::yaml::from_yaml::<Value>(yaml)?)
+    }
+    
+    /// Load a document from a file
+    ///
+    /// `.yaml` and `.yml` files need the `yaml` feature; anything else is
+    /// read as JSON.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| HttpError::IoError(format!("Failed to read {}: {}", path.display(), e)))?;
+        
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Self::from_yaml(&text),
+            #[cfg(not(feature = "yaml"))]
+            Some("yaml" | "yml") => Err(HttpError::ConfigError(
+                "Loading a YAML OpenAPI document requires the `yaml` feature".to_string(),
+            )),
+            _ => Self::from_json(&text),
+        }
+    }
+    
+    /// Look up an operation by its `operationId`
+    pub fn operation(&self, id: &str) -> Option<&Operation> {
+        self.operations.get(id)
+    }
+    
+    /// All `operationId`s in the document
+    pub fn operation_ids(&self) -> impl Iterator<Item = &str> {
+        self.operations.keys().map(String::as_str)
+    }
+}
+
+/// Follow a local `$ref` such as `#/components/parameters/Id`
+fn resolve<'a>(document: &'a Value, value: &'a Value) -> Result<&'a Value> {
+    match value.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference
+            .strip_prefix('#')
+            .and_then(|pointer| document.pointer(pointer))
+            .ok_or_else(|| HttpError::ConfigError(format!("Unresolvable OpenAPI reference '{}'", reference))),
+        None => Ok(value),
+    }
+}
+
+fn parameters(document: &Value, list: Option<&Value>) -> Result<Vec<Parameter>> {
+    let list = match list.and_then(Value::as_array) {
+        Some(list) => list,
+        None => return Ok(Vec::new()),
+    };
+    
+    list.iter()
+        .map(|param| {
+            let param = resolve(document, param)?;
+            let name = param.get("name").and_then(Value::as_str).unwrap_or_default();
+            let location = match param.get("in").and_then(Value::as_str) {
+                Some("path") => ParameterLocation::Path,
+                Some("query") => ParameterLocation::Query,
+                Some("header") => ParameterLocation::Header,
+                Some("cookie") => ParameterLocation::Cookie,
+                other => {
+                    return Err(HttpError::ConfigError(format!(
+                        "Parameter '{}' has unknown location {:?}",
+                        name, other
+                    )))
+                }
+            };
+            Ok(Parameter {
+                name: name.to_string(),
+                location,
+                required: location == ParameterLocation::Path
+                    || param.get("required").and_then(Value::as_bool).unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// A request for an OpenAPI operation; see `HttpClient::op`
+///
+/// Required parameters and bodies are checked before anything is sent.
+#[derive(Debug)]
+pub struct OperationRequest<'a> {
+    client: &'a HttpClient,
+    operation: Result<&'a Operation>,
+    path_params: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Option<Result<Value>>,
+}
+
+impl<'a> OperationRequest<'a> {
+    pub(crate) fn new(client: &'a HttpClient, spec: Option<&'a OpenApi>, id: &str) -> Self {
+        let operation = match spec {
+            Some(spec) => spec
+                .operation(id)
+                .ok_or_else(|| HttpError::ConfigError(format!("Unknown operationId '{}'", id))),
+            None => Err(HttpError::ConfigError("No OpenAPI document configured".to_string())),
+        };
+        Self {
+            client,
+            operation,
+            path_params: Vec::new(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+    
+    /// Fill the `{name}` path placeholder
+    pub fn path_param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.path_params.push((name.into(), value.to_string()));
+        self
+    }
+    
+    /// Add a query parameter
+    pub fn query_param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.query.push((name.into(), value.to_string()));
+        self
+    }
+    
+    /// Add a header parameter
+    pub fn header(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.headers.push((name.into(), value.to_string()));
+        self
+    }
+    
+    /// Send `body` as JSON
+    pub fn json<T: Serialize>(mut self, body: &T) -> Self {
+        self.body = Some(serde_json::to_value(body).map_err(HttpError::from));
+        self
+    }
+    
+    /// Validate the parameters and build the request
+    pub fn build(self) -> Result<Request> {
+        let operation = self.operation?;
+        let missing = |location: ParameterLocation, supplied: &[(String, String)]| {
+            operation
+                .parameters
+                .iter()
+                .filter(|param| param.location == location && param.required)
+                .find(|param| !supplied.iter().any(|(name, _)| name.eq_ignore_ascii_case(&param.name)))
+                .map(|param| {
+                    HttpError::ConfigError(format!(
+                        "Operation '{}' is missing required {:?} parameter '{}'",
+                        operation.id, location, param.name
+                    ))
+                })
+        };
+        for (location, supplied) in [
+            (ParameterLocation::Path, &self.path_params),
+            (ParameterLocation::Query, &self.query),
+            (ParameterLocation::Header, &self.headers),
+        ] {
+            if let Some(error) = missing(location, supplied) {
+                return Err(error);
+            }
+        }
+        if operation.body_required && self.body.is_none() {
+            return Err(HttpError::ConfigError(format!(
+                "Operation '{}' requires a request body",
+                operation.id
+            )));
+        }
+        
+        let mut path = operation.path.clone();
+        for (name, value) in &self.path_params {
+            let placeholder = format!("{{{}}}", name);
+            if !path.contains(&placeholder) {
+                return Err(HttpError::ConfigError(format!(
+                    "Operation '{}' has no path parameter '{}'",
+                    operation.id, name
+                )));
+            }
+            path = path.replace(&placeholder, &urlencoding::encode(value));
+        }
+        
+        let mut builder = self.client.request(operation.method.clone(), &path)?;
+        if !self.query.is_empty() {
+            builder = builder.query(&self.query);
+        }
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| HttpError::HeaderError(format!("Invalid header name: {}", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| HttpError::HeaderError(format!("Invalid header value: {}", value)))?;
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = self.body {
+            builder = builder.json(&body?);
+        }
+        Ok(builder.build()?)
+    }
+    
+    /// Send the request
+    pub async fn send(self) -> Result<Response> {
+        let client = self.client;
+        client.execute(self.build()?).await
+    }
+    
+    /// Send the request and deserialize the JSON response
+    pub async fn send_json<T: DeserializeOwned>(self) -> Result<T> {
+        let client = self.client;
+        let response = client.execute(self.build()?).await?;
+        client.process_json_response(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    
+    fn spec() -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "paths": {
+                "/users/{id}": {
+                    "parameters": [{"$ref": "#/components/parameters/Id"}],
+                    "get": {
+                        "operationId": "getUser",
+                        "parameters": [{"name": "fields", "in": "query"}]
+                    }
+                },
+                "/users": {
+                    "get": {
+                        "operationId": "listUsers",
+                        "parameters": [{"name": "page", "in": "query", "required": true}]
+                    },
+                    "post": {
+                        "operationId": "createUser",
+                        "requestBody": {"required": true, "content": {"application/json": {}}}
+                    }
+                }
+            },
+            "components": {"parameters": {"Id": {"name": "id", "in": "path", "required": true}}}
+        })
+    }
+    
+    #[test]
+    fn test_operations_from_document() {
+        let api = OpenApi::from_value(&spec()).unwrap();
+        let get_user = api.operation("getUser").unwrap();
+        assert_eq!(get_user.method, Method::GET);
+        assert_eq!(get_user.path, "/users/{id}");
+        assert_eq!(get_user.parameters.len(), 2);
+        assert!(api.operation("createUser").unwrap().body_required);
+        assert_eq!(api.operation_ids().count(), 3);
+        
+        assert!(OpenApi::from_json("{}").is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_op_validates_before_sending() {
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::get("/users/7")
+                .with_query("fields=name")
+                .respond_with(MockResponse::json(StatusCode::OK, &json!({"name": "ada"})).unwrap()),
+        );
+        let client = HttpClient::with_base_url("http://api.test")
+            .unwrap()
+            .with_openapi(OpenApi::from_value(&spec()).unwrap())
+            .with_transport(transport.clone());
+        
+        let user: Value = client
+            .op("getUser")
+            .path_param("id", 7)
+            .query_param("fields", "name")
+            .send_json()
+            .await
+            .unwrap();
+        assert_eq!(user["name"], "ada");
+        
+        let errors = [
+            client.op("getUser").send().await,
+            client.op("listUsers").send().await,
+            client.op("createUser").send().await,
+            client.op("getUser").path_param("id", 1).path_param("org", 2).send().await,
+            client.op("deleteUser").send().await,
+        ];
+        for error in errors {
+            assert!(matches!(error, Err(HttpError::ConfigError(_))));
+        }
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
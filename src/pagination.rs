@@ -0,0 +1,908 @@
+// src/pagination.rs
+// Following paginated API responses
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use crate::response::{parse_json, ResponseExt};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use reqwest::header::HeaderName;
+use reqwest::{Method, Response, Url};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default cap on the number of pages `get_paginated` fetches
+pub const DEFAULT_MAX_PAGES: usize = 100;
+
+/// A link from an RFC 8288 `Link` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub url: String,
+    /// Link parameters with lowercased names and unquoted values
+    pub params: Vec<(String, String)>,
+}
+
+impl Link {
+    /// Get a parameter by (lowercase) name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+    
+    /// Check whether the `rel` parameter contains `rel`
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.param("rel")
+            .is_some_and(|rels| rels.split_whitespace().any(|r| r.eq_ignore_ascii_case(rel)))
+    }
+}
+
+/// Parse a `Link` header value
+///
+/// Malformed entries are skipped rather than failing the whole header.
+pub fn parse_link_header(value: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    let mut rest = value;
+    
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let url = rest[start + 1..end].trim().to_string();
+        rest = &rest[end + 1..];
+        
+        // parameters run until the next comma outside a quoted string
+        let mut in_quotes = false;
+        let params_end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                c == ',' && !in_quotes
+            })
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        
+        let params = rest[..params_end]
+            .split(';')
+            .filter_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                Some((
+                    key.trim().to_ascii_lowercase(),
+                    value.trim().trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+        
+        links.push(Link { url, params });
+        rest = &rest[params_end..];
+    }
+    
+    links
+}
+
+/// Get the `rel="next"` link of a response, resolved against its URL
+pub fn next_link(response: &Response) -> Option<Url> {
+    response.link("next")
+}
+
+impl HttpClient {
+    /// Stream pages by following `rel="next"` links from `url`
+    ///
+    /// Each page body is deserialized as `P`. Stops when a page has no next
+    /// link or after `max_pages` pages, whichever comes first.
+    pub fn get_pages<P>(&self, url: &str, max_pages: usize) -> impl Stream<Item = Result<P>>
+    where
+        P: DeserializeOwned,
+    {
+        let state = (self.clone(), Some(self.build_url(url)), 0usize);
+        
+        stream::try_unfold(state, move |(client, next, fetched)| async move {
+            let url = match next {
+                Some(url) => url?,
+                None => return Ok(None),
+            };
+            
+            if fetched >= max_pages {
+                log::warn!("Stopped pagination after {} pages; next page was {}", fetched, url);
+                return Ok(None);
+            }
+            
+            let request = client.request(Method::GET, &url)?.build()?;
+            let response = client.execute(request).await?;
+            let next = next_link(&response).map(|url| Ok(url.to_string()));
+            let page = client.process_json_response::<P>(response).await?;
+            
+            Ok(Some((page, (client, next, fetched + 1))))
+        })
+    }
+    
+    /// Stream the items of a paginated JSON array endpoint
+    ///
+    /// Follows `rel="next"` links (GitHub style), fetching at most
+    /// `DEFAULT_MAX_PAGES` pages. Use `get_pages` for a different limit or
+    /// for pages that aren't plain arrays.
+    pub fn get_paginated<T>(&self, url: &str) -> impl Stream<Item = Result<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.get_pages::<Vec<T>>(url, DEFAULT_MAX_PAGES)
+            .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+}
+
+/// A pagination scheme: how to request a page and find the next one
+///
+/// Implement this for cursor, offset or token based APIs, then drive it
+/// with `paginate` (items) or `paginate_pages` (whole pages).
+pub trait Paginator: Send + Sync + 'static {
+    /// Response body of one page
+    type Page: DeserializeOwned + Send;
+    /// Items yielded from each page
+    type Item: Send;
+    /// Position of a page, e.g. a cursor token or an offset
+    type Cursor: Send;
+    
+    /// Build the request for the page at `cursor` (`None` for the first page)
+    fn request(&self, client: &HttpClient, cursor: Option<&Self::Cursor>) -> Result<reqwest::Request>;
+    
+    /// Split the page at `cursor` into its items and the next page's cursor, if any
+    fn parse(&self, page: Self::Page, cursor: Option<&Self::Cursor>) -> (Vec<Self::Item>, Option<Self::Cursor>);
+    
+    /// Maximum number of pages to fetch
+    fn max_pages(&self) -> usize {
+        DEFAULT_MAX_PAGES
+    }
+}
+
+/// Stream the pages of `paginator`, as item lists
+pub fn paginate_pages<P: Paginator>(client: &HttpClient, paginator: P) -> impl Stream<Item = Result<Vec<P::Item>>> {
+    let paginator = Arc::new(paginator);
+    let state = (client.clone(), paginator, Some(None), 0usize);
+    
+    stream::try_unfold(state, |(client, paginator, cursor, fetched)| async move {
+        let cursor: Option<P::Cursor> = match cursor {
+            Some(cursor) => cursor,
+            None => return Ok(None),
+        };
+        
+        if fetched >= paginator.max_pages() {
+            log::warn!("Stopped pagination after {} pages", fetched);
+            return Ok(None);
+        }
+        
+        let request = paginator.request(&client, cursor.as_ref())?;
+        let response = client.execute(request).await?;
+        let page = client.process_json_response::<P::Page>(response).await?;
+        let (items, next) = paginator.parse(page, cursor.as_ref());
+        
+        Ok(Some((items, (client, paginator, next.map(Some), fetched + 1))))
+    })
+}
+
+/// Stream every item of `paginator`, fetching pages as needed
+pub fn paginate<P: Paginator>(client: &HttpClient, paginator: P) -> impl Stream<Item = Result<P::Item>> {
+    paginate_pages(client, paginator)
+        .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+        .try_flatten()
+}
+
+/// Pulls the items and next cursor out of a page
+type CursorExtractor<Page, Item> = Box<dyn Fn(Page) -> (Vec<Item>, Option<String>) + Send + Sync>;
+
+/// Paginator for APIs that return an opaque cursor in each page
+///
+/// The cursor is sent back in the `param` query parameter; `extract` pulls
+/// the items and next cursor out of a page.
+pub struct CursorPaginator<Page, Item> {
+    url: String,
+    param: String,
+    extract: CursorExtractor<Page, Item>,
+    max_pages: usize,
+}
+
+impl<Page, Item> fmt::Debug for CursorPaginator<Page, Item> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CursorPaginator")
+            .field("url", &self.url)
+            .field("param", &self.param)
+            .field("max_pages", &self.max_pages)
+            .finish()
+    }
+}
+
+impl<Page, Item> CursorPaginator<Page, Item> {
+    pub fn new<F>(url: impl Into<String>, param: impl Into<String>, extract: F) -> Self
+    where
+        F: Fn(Page) -> (Vec<Item>, Option<String>) + Send + Sync + 'static,
+    {
+        Self {
+            url: url.into(),
+            param: param.into(),
+            extract: Box::new(extract),
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+    
+    /// Set the maximum number of pages to fetch
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+}
+
+impl<Page, Item> Paginator for CursorPaginator<Page, Item>
+where
+    Page: DeserializeOwned + Send + 'static,
+    Item: Send + 'static,
+{
+    type Page = Page;
+    type Item = Item;
+    type Cursor = String;
+    
+    fn request(&self, client: &HttpClient, cursor: Option<&String>) -> Result<reqwest::Request> {
+        let mut builder = client.request(Method::GET, &self.url)?;
+        if let Some(cursor) = cursor {
+            builder = builder.query(&[(self.param.as_str(), cursor.as_str())]);
+        }
+        Ok(builder.build()?)
+    }
+    
+    fn parse(&self, page: Page, _cursor: Option<&String>) -> (Vec<Item>, Option<String>) {
+        let (items, next) = (self.extract)(page);
+        // an empty cursor means "no more pages" for many APIs
+        (items, next.filter(|cursor| !cursor.is_empty()))
+    }
+    
+    fn max_pages(&self) -> usize {
+        self.max_pages
+    }
+}
+
+/// Paginator for `offset`/`limit` APIs whose pages are JSON arrays
+///
+/// Stops at the first page shorter than the page size.
+pub struct OffsetPaginator<Item> {
+    url: String,
+    offset_param: String,
+    limit_param: String,
+    page_size: usize,
+    max_pages: usize,
+    _item: PhantomData<fn() -> Item>,
+}
+
+impl<Item> fmt::Debug for OffsetPaginator<Item> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OffsetPaginator")
+            .field("url", &self.url)
+            .field("page_size", &self.page_size)
+            .field("max_pages", &self.max_pages)
+            .finish()
+    }
+}
+
+impl<Item> OffsetPaginator<Item> {
+    /// Page through `url` using `offset` and `limit` query parameters
+    pub fn new(url: impl Into<String>, page_size: usize) -> Self {
+        Self {
+            url: url.into(),
+            offset_param: "offset".to_string(),
+            limit_param: "limit".to_string(),
+            page_size,
+            max_pages: DEFAULT_MAX_PAGES,
+            _item: PhantomData,
+        }
+    }
+    
+    /// Use different names for the offset and limit query parameters
+    pub fn with_params(mut self, offset_param: impl Into<String>, limit_param: impl Into<String>) -> Self {
+        self.offset_param = offset_param.into();
+        self.limit_param = limit_param.into();
+        self
+    }
+    
+    /// Set the maximum number of pages to fetch
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+}
+
+impl<Item> Paginator for OffsetPaginator<Item>
+where
+    Item: DeserializeOwned + Send + 'static,
+{
+    type Page = Vec<Item>;
+    type Item = Item;
+    type Cursor = usize;
+    
+    fn request(&self, client: &HttpClient, cursor: Option<&usize>) -> Result<reqwest::Request> {
+        let offset = cursor.copied().unwrap_or(0);
+        let request = client
+            .request(Method::GET, &self.url)?
+            .query(&[(self.offset_param.as_str(), offset), (self.limit_param.as_str(), self.page_size)])
+            .build()?;
+        Ok(request)
+    }
+    
+    fn parse(&self, page: Vec<Item>, cursor: Option<&usize>) -> (Vec<Item>, Option<usize>) {
+        let offset = cursor.copied().unwrap_or(0);
+        let next = (page.len() >= self.page_size && self.page_size > 0).then(|| offset + page.len());
+        (page, next)
+    }
+    
+    fn max_pages(&self) -> usize {
+        self.max_pages
+    }
+}
+
+/// Where a piece of page metadata is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageField {
+    /// A response header
+    Header(HeaderName),
+    /// A field of the JSON body, as a JSON pointer (`/meta/total`)
+    JsonPointer(String),
+}
+
+impl PageField {
+    /// Read a `u64` from the response headers or body
+    fn read_u64(&self, headers: &reqwest::header::HeaderMap, body: &serde_json::Value) -> Option<u64> {
+        match self {
+            PageField::Header(name) => headers.get(name)?.to_str().ok()?.trim().parse().ok(),
+            PageField::JsonPointer(pointer) => {
+                let value = body.pointer(pointer)?;
+                value.as_u64().or_else(|| value.as_str()?.trim().parse().ok())
+            }
+        }
+    }
+}
+
+/// How to find a page's items and metadata in a response
+///
+/// Defaults to a JSON array body with `X-Total-Count`, `X-Page` and
+/// `X-Per-Page` headers and the `Link` header's `rel="next"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageMapping {
+    /// JSON pointer to the items array; `None` when the body is the array
+    pub items: Option<String>,
+    pub total: Option<PageField>,
+    pub page: Option<PageField>,
+    pub per_page: Option<PageField>,
+    /// JSON pointer to the next page URL; `None` to use the `Link` header
+    pub next: Option<String>,
+}
+
+impl Default for PageMapping {
+    fn default() -> Self {
+        Self {
+            items: None,
+            total: Some(PageField::Header(HeaderName::from_static("x-total-count"))),
+            page: Some(PageField::Header(HeaderName::from_static("x-page"))),
+            per_page: Some(PageField::Header(HeaderName::from_static("x-per-page"))),
+            next: None,
+        }
+    }
+}
+
+impl PageMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Read the items from the array at `pointer` in the body
+    pub fn with_items(mut self, pointer: impl Into<String>) -> Self {
+        self.items = Some(pointer.into());
+        self
+    }
+    
+    /// Set where the total item count is read from
+    pub fn with_total(mut self, field: PageField) -> Self {
+        self.total = Some(field);
+        self
+    }
+    
+    /// Set where the current page number is read from
+    pub fn with_page(mut self, field: PageField) -> Self {
+        self.page = Some(field);
+        self
+    }
+    
+    /// Set where the page size is read from
+    pub fn with_per_page(mut self, field: PageField) -> Self {
+        self.per_page = Some(field);
+        self
+    }
+    
+    /// Read the next page URL from the body field at `pointer`
+    pub fn with_next(mut self, pointer: impl Into<String>) -> Self {
+        self.next = Some(pointer.into());
+        self
+    }
+}
+
+/// One page of results with its pagination metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: Option<u64>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    pub next: Option<Url>,
+}
+
+impl<T: DeserializeOwned> Page<T> {
+    /// Read a page from a response using `mapping`
+    pub async fn from_response(response: Response, mapping: &PageMapping) -> Result<Self> {
+        let headers = response.headers().clone();
+        let url = response.url().clone();
+        let link_next = next_link(&response);
+        
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(HttpError::ResponseError {
+                status,
+                body: String::from_utf8_lossy(&body).to_string(),
+            });
+        }
+        let mut body: serde_json::Value = parse_json(&body)?;
+        
+        let read = |field: &Option<PageField>| field.as_ref().and_then(|f| f.read_u64(&headers, &body));
+        let total = read(&mapping.total);
+        let page = read(&mapping.page);
+        let per_page = read(&mapping.per_page);
+        
+        let next = match &mapping.next {
+            Some(pointer) => body
+                .pointer(pointer)
+                .and_then(|value| value.as_str())
+                .filter(|next| !next.is_empty())
+                .and_then(|next| url.join(next).ok()),
+            None => link_next,
+        };
+        
+        let items = match &mapping.items {
+            Some(pointer) => body
+                .pointer_mut(pointer)
+                .map(serde_json::Value::take)
+                .ok_or_else(|| HttpError::JsonError(format!("No items at '{}' in page", pointer)))?,
+            None => body,
+        };
+        let items = serde_json::from_value(items).map_err(|e| {
+            HttpError::SerializationError(format!("Failed to deserialize page items: {}", e))
+        })?;
+        
+        Ok(Self { items, total, page, per_page, next })
+    }
+}
+
+impl<T> Page<T> {
+    /// Check whether there is a next page
+    pub fn has_next(&self) -> bool {
+        self.next.is_some()
+    }
+    
+    /// Compute the number of pages from the total and page size, if known
+    pub fn total_pages(&self) -> Option<u64> {
+        match (self.total, self.per_page) {
+            (Some(total), Some(per_page)) if per_page > 0 => Some(total.div_ceil(per_page)),
+            _ => None,
+        }
+    }
+}
+
+impl HttpClient {
+    /// Fetch a single page and its metadata
+    pub async fn get_page<T>(&self, url: &str, mapping: &PageMapping) -> Result<Page<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let request = self.request(Method::GET, url)?.build()?;
+        let response = self.execute(request).await?;
+        Page::from_response(response, mapping).await
+    }
+    
+    /// Stream typed pages from `url`, following each page's `next` URL
+    ///
+    /// Fetches at most `max_pages` pages.
+    pub fn get_typed_pages<T>(
+        &self,
+        url: &str,
+        mapping: PageMapping,
+        max_pages: usize,
+    ) -> impl Stream<Item = Result<Page<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let state = (self.clone(), Arc::new(mapping), Some(self.build_url(url)), 0usize);
+        
+        stream::try_unfold(state, move |(client, mapping, next, fetched)| async move {
+            let url = match next {
+                Some(url) => url?,
+                None => return Ok(None),
+            };
+            
+            if fetched >= max_pages {
+                log::warn!("Stopped pagination after {} pages; next page was {}", fetched, url);
+                return Ok(None);
+            }
+            
+            let page = client.get_page::<T>(&url, &mapping).await?;
+            let next = page.next.as_ref().map(|url| Ok(url.to_string()));
+            
+            Ok(Some((page, (client, mapping, next, fetched + 1))))
+        })
+    }
+}
+
+/// Guards that stop `collect_pages` before a crawl runs away
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectLimits {
+    pub max_items: Option<usize>,
+    pub max_pages: Option<usize>,
+    pub max_duration: Option<Duration>,
+}
+
+impl CollectLimits {
+    /// No limits; only use with streams that are known to end
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Stop once this many items have been collected
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+    
+    /// Stop after fetching this many pages
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+    
+    /// Stop when this much time has passed, abandoning any page in flight
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+}
+
+/// Why `collect_pages` stopped
+#[derive(Debug)]
+pub enum StopReason {
+    /// There were no more pages
+    Exhausted,
+    /// `max_items` was reached; later items were dropped
+    MaxItems,
+    /// `max_pages` pages were fetched
+    MaxPages,
+    /// `max_duration` elapsed
+    MaxDuration,
+    /// Fetching a page failed
+    Error(HttpError),
+}
+
+/// Items collected from a paginated stream, with a report of how it ended
+#[derive(Debug)]
+pub struct Collected<T> {
+    pub items: Vec<T>,
+    pub pages: usize,
+    pub elapsed: Duration,
+    pub stop_reason: StopReason,
+}
+
+impl<T> Collected<T> {
+    /// Check whether every page was collected
+    pub fn is_complete(&self) -> bool {
+        matches!(self.stop_reason, StopReason::Exhausted)
+    }
+    
+    /// Get the items if every page was collected, or the reason it stopped
+    pub fn into_complete(self) -> std::result::Result<Vec<T>, StopReason> {
+        match self.stop_reason {
+            StopReason::Exhausted => Ok(self.items),
+            reason => Err(reason),
+        }
+    }
+}
+
+/// Collect a stream of pages into a single list, within `limits`
+///
+/// Never fails: an error ends collection with `StopReason::Error` and the
+/// items gathered so far. Note that page streams from `paginate_pages` and
+/// `get_pages` have their own page cap, which ends them as `Exhausted`.
+pub async fn collect_pages<S, T>(pages: S, limits: CollectLimits) -> Collected<T>
+where
+    S: Stream<Item = Result<Vec<T>>>,
+{
+    let started = Instant::now();
+    let mut pages = std::pin::pin!(pages);
+    let mut items = Vec::new();
+    let mut fetched = 0;
+    
+    let stop_reason = loop {
+        if limits.max_pages.is_some_and(|max| fetched >= max) {
+            break StopReason::MaxPages;
+        }
+        
+        let next = match limits.max_duration {
+            Some(max) => {
+                let remaining = max.saturating_sub(started.elapsed());
+                match tokio::time::timeout(remaining, pages.next()).await {
+                    Ok(next) => next,
+                    Err(_) => break StopReason::MaxDuration,
+                }
+            }
+            None => pages.next().await,
+        };
+        
+        let page = match next {
+            Some(Ok(page)) => page,
+            Some(Err(e)) => break StopReason::Error(e),
+            None => break StopReason::Exhausted,
+        };
+        fetched += 1;
+        items.extend(page);
+        
+        if let Some(max) = limits.max_items {
+            if items.len() >= max {
+                items.truncate(max);
+                break StopReason::MaxItems;
+            }
+        }
+    };
+    
+    if !matches!(stop_reason, StopReason::Exhausted) {
+        log::warn!("Stopped collecting pages after {} pages: {:?}", fetched, stop_reason);
+    }
+    
+    Collected {
+        items,
+        pages: fetched,
+        elapsed: started.elapsed(),
+        stop_reason,
+    }
+}
+
+impl HttpClient {
+    /// Collect every item of a paginated JSON array endpoint, within `limits`
+    ///
+    /// Like `get_paginated`, but only `limits` bound the crawl and the
+    /// result reports whether it stopped early.
+    pub async fn paginate_all<T>(&self, url: &str, limits: CollectLimits) -> Collected<T>
+    where
+        T: DeserializeOwned,
+    {
+        collect_pages(self.get_pages::<Vec<T>>(url, usize::MAX), limits).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    
+    /// Serve `pages(port)` on consecutive connections
+    async fn serve_pages<F>(pages: F) -> u16
+    where
+        F: FnOnce(u16) -> Vec<String>,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let responses = pages(port);
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+    
+    fn page(body: &str, link: Option<String>) -> String {
+        let link = link.map(|l| format!("link: {}\r\n", l)).unwrap_or_default();
+        format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n{}connection: close\r\ncontent-length: {}\r\n\r\n{}",
+            link,
+            body.len(),
+            body
+        )
+    }
+    
+    #[test]
+    fn test_parse_link_header() {
+        let links = parse_link_header(
+            r#"<https://api.example.com/items?page=2&a=1,2>; rel="next", <https://api.example.com/items?page=5>; rel="last"; title="a, b""#,
+        );
+        
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].url, "https://api.example.com/items?page=2&a=1,2");
+        assert!(links[0].has_rel("next"));
+        assert_eq!(links[1].param("title"), Some("a, b"));
+        assert!(links[1].has_rel("last"));
+        assert!(parse_link_header("garbage").is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_get_paginated_follows_next_links() {
+        let port = serve_pages(|port| {
+            vec![
+                page("[1,2]", Some(format!("<http://127.0.0.1:{}/items?page=2>; rel=\"next\"", port))),
+                page("[3]", Some("</items?page=3>; rel=\"next\"".to_string())),
+                page("[4,5]", None),
+            ]
+        })
+        .await;
+        
+        let client = HttpClient::new();
+        let items: Vec<u32> = client
+            .get_paginated::<u32>(&format!("http://127.0.0.1:{}/items", port))
+            .try_collect()
+            .await
+            .unwrap();
+        
+        assert_eq!(items, vec![1, 2, 3, 4, 5]);
+    }
+    
+    #[tokio::test]
+    async fn test_get_pages_respects_max_pages() {
+        let port = serve_pages(|_| {
+            vec![
+                page("[1]", Some("</items?page=2>; rel=\"next\"".to_string())),
+                page("[2]", Some("</items?page=3>; rel=\"next\"".to_string())),
+            ]
+        })
+        .await;
+        
+        let client = HttpClient::new();
+        let pages: Vec<Result<Vec<u32>>> = client
+            .get_pages(&format!("http://127.0.0.1:{}/items", port), 2)
+            .collect()
+            .await;
+        
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].as_ref().unwrap(), &vec![2]);
+    }
+    
+    #[tokio::test]
+    async fn test_cursor_paginator() {
+        #[derive(serde::Deserialize)]
+        struct Page {
+            data: Vec<String>,
+            next_cursor: Option<String>,
+        }
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut request_lines = Vec::new();
+            for body in [r#"{"data":["a","b"],"next_cursor":"c2"}"#, r#"{"data":["c"],"next_cursor":""}"#] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                request_lines.push(request.lines().next().unwrap().to_string());
+                let _ = socket.write_all(page(body, None).as_bytes()).await;
+            }
+            request_lines
+        });
+        
+        let paginator = CursorPaginator::new(
+            format!("http://127.0.0.1:{}/events", port),
+            "cursor",
+            |page: Page| (page.data, page.next_cursor),
+        );
+        let items: Vec<String> = paginate(&HttpClient::new(), paginator).try_collect().await.unwrap();
+        
+        assert_eq!(items, vec!["a", "b", "c"]);
+        let request_lines = server.await.unwrap();
+        assert_eq!(request_lines[0], "GET /events HTTP/1.1");
+        assert_eq!(request_lines[1], "GET /events?cursor=c2 HTTP/1.1");
+    }
+    
+    #[tokio::test]
+    async fn test_offset_paginator_stops_on_short_page() {
+        let port = serve_pages(|_| vec![page("[1,2]", None), page("[3,4]", None), page("[5]", None)]).await;
+        
+        let paginator = OffsetPaginator::<u32>::new(format!("http://127.0.0.1:{}/items", port), 2);
+        let pages: Vec<Vec<u32>> = paginate_pages(&HttpClient::new(), paginator).try_collect().await.unwrap();
+        
+        assert_eq!(pages, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    }
+    
+    #[tokio::test]
+    async fn test_collect_pages_limits() {
+        let pages = || stream::iter(vec![Ok(vec![1, 2]), Ok(vec![3, 4]), Ok(vec![5])]);
+        
+        let all = collect_pages(pages(), CollectLimits::new()).await;
+        assert!(all.is_complete());
+        assert_eq!(all.items, vec![1, 2, 3, 4, 5]);
+        assert_eq!(all.pages, 3);
+        
+        let items = collect_pages(pages(), CollectLimits::new().with_max_items(3)).await;
+        assert!(matches!(items.stop_reason, StopReason::MaxItems));
+        assert_eq!(items.items, vec![1, 2, 3]);
+        
+        let first = collect_pages(pages(), CollectLimits::new().with_max_pages(1)).await;
+        assert!(matches!(first.stop_reason, StopReason::MaxPages));
+        assert!(matches!(first.into_complete(), Err(StopReason::MaxPages)));
+        
+        let failing = stream::iter(vec![Ok(vec![1]), Err(HttpError::TimeoutError), Ok(vec![2])]);
+        let partial = collect_pages(failing, CollectLimits::new()).await;
+        assert!(matches!(partial.stop_reason, StopReason::Error(HttpError::TimeoutError)));
+        assert_eq!(partial.items, vec![1]);
+    }
+    
+    #[tokio::test]
+    async fn test_collect_pages_max_duration() {
+        let slow = stream::iter(vec![Ok(vec![1]), Ok(vec![2])]).then(|page| async move {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            page
+        });
+        let never = slow.chain(stream::pending());
+        
+        let collected = collect_pages(never, CollectLimits::new().with_max_duration(Duration::from_millis(200))).await;
+        assert!(matches!(collected.stop_reason, StopReason::MaxDuration));
+        assert_eq!(collected.items, vec![1, 2]);
+    }
+    
+    #[tokio::test]
+    async fn test_typed_pages_from_headers() {
+        let port = serve_pages(|_| {
+            let mut first = page("[1,2]", Some("</items?page=2>; rel=\"next\"".to_string()));
+            first = first.replace("connection: close", "x-total-count: 3\r\nx-page: 1\r\nx-per-page: 2\r\nconnection: close");
+            vec![first, page("[3]", None)]
+        })
+        .await;
+        
+        let client = HttpClient::new();
+        let pages: Vec<Page<u32>> = client
+            .get_typed_pages(&format!("http://127.0.0.1:{}/items", port), PageMapping::new(), 10)
+            .try_collect()
+            .await
+            .unwrap();
+        
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].items, vec![1, 2]);
+        assert_eq!(pages[0].total, Some(3));
+        assert_eq!(pages[0].page, Some(1));
+        assert_eq!(pages[0].total_pages(), Some(2));
+        assert_eq!(pages[0].next.as_ref().unwrap().path(), "/items");
+        assert!(!pages[1].has_next());
+        assert_eq!(pages[1].total, None);
+    }
+    
+    #[tokio::test]
+    async fn test_page_from_body_fields() {
+        let body = r#"{"data":[{"id":7}],"meta":{"total":"41","page":3,"size":20},"links":{"next":"/items?page=4"}}"#;
+        let port = serve_pages(|_| vec![page(body, None)]).await;
+        
+        let mapping = PageMapping::new()
+            .with_items("/data")
+            .with_total(PageField::JsonPointer("/meta/total".to_string()))
+            .with_page(PageField::JsonPointer("/meta/page".to_string()))
+            .with_per_page(PageField::JsonPointer("/meta/size".to_string()))
+            .with_next("/links/next");
+        
+        let page: Page<serde_json::Value> = HttpClient::new()
+            .get_page(&format!("http://127.0.0.1:{}/items?page=3", port), &mapping)
+            .await
+            .unwrap();
+        
+        assert_eq!(page.items, vec![serde_json::json!({"id": 7})]);
+        assert_eq!((page.total, page.page, page.per_page), (Some(41), Some(3), Some(20)));
+        assert_eq!(page.total_pages(), Some(3));
+        assert_eq!(page.next.unwrap().query(), Some("page=4"));
+    }
+}
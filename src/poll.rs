@@ -0,0 +1,264 @@
+// src/poll.rs
+// Polling a URL at a fixed interval
+
+use crate::client::HttpClient;
+use crate::error::Result;
+use futures::Stream;
+use reqwest::header::{HeaderValue, ETAG, IF_NONE_MATCH};
+use reqwest::{Method, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+type StopCondition<'a> = Box<dyn Fn(&Response) -> bool + Send + Sync + 'a>;
+
+/// A stream of polls of one URL; see `HttpClient::poll_every`
+///
+/// The first request is sent at once and each following one `interval`
+/// (plus jitter) after the previous finished. Waits go through the
+/// client's clock.
+pub struct Poller<'a> {
+    client: &'a HttpClient,
+    url: String,
+    interval: Duration,
+    jitter: Duration,
+    changes_only: bool,
+    max_polls: Option<usize>,
+    stop_on_error: bool,
+    until: Option<StopCondition<'a>>,
+}
+
+impl<'a> Poller<'a> {
+    /// Wait up to `jitter` longer than the interval, picked at random each time
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+    
+    /// Only yield responses whose `ETag` changed
+    ///
+    /// The last `ETag` is sent as `If-None-Match`, and `304 Not Modified`
+    /// responses, or 2xx responses with the same `ETag`, are skipped.
+    pub fn changes_only(mut self) -> Self {
+        self.changes_only = true;
+        self
+    }
+    
+    /// Stop after sending `polls` requests, skipped ones included
+    pub fn with_max_polls(mut self, polls: usize) -> Self {
+        self.max_polls = Some(polls);
+        self
+    }
+    
+    /// End the stream after the first error instead of polling on
+    pub fn stop_on_error(mut self) -> Self {
+        self.stop_on_error = true;
+        self
+    }
+    
+    /// End the stream after the first response matching `condition`
+    pub fn until<F>(mut self, condition: F) -> Self
+    where
+        F: Fn(&Response) -> bool + Send + Sync + 'a,
+    {
+        self.until = Some(Box::new(condition));
+        self
+    }
+    
+    /// Poll, yielding each response
+    pub fn responses(self) -> impl Stream<Item = Result<Response>> + 'a {
+        let state = PollState { poller: self, etag: None, polls: 0, done: false };
+        futures::stream::unfold(state, |mut state| async move {
+            let item = state.next().await?;
+            Some((item, state))
+        })
+    }
+    
+    /// Poll, decoding each response like `HttpClient::decode`
+    pub fn json<T: DeserializeOwned + 'a>(self) -> impl Stream<Item = Result<T>> + 'a {
+        let client = self.client;
+        futures::StreamExt::then(self.responses(), move |result| async move {
+            match result {
+                Ok(response) => client.decode(response).await,
+                Err(e) => Err(e),
+            }
+        })
+    }
+    
+    fn delay(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.interval;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        let fraction = (random >> 11) as f64 / (1u64 << 53) as f64;
+        self.interval + self.jitter.mul_f64(fraction)
+    }
+}
+
+impl fmt::Debug for Poller<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Poller")
+            .field("url", &self.url)
+            .field("interval", &self.interval)
+            .field("jitter", &self.jitter)
+            .field("changes_only", &self.changes_only)
+            .field("max_polls", &self.max_polls)
+            .field("stop_on_error", &self.stop_on_error)
+            .field("until", &self.until.is_some())
+            .finish()
+    }
+}
+
+struct PollState<'a> {
+    poller: Poller<'a>,
+    etag: Option<HeaderValue>,
+    polls: usize,
+    done: bool,
+}
+
+impl PollState<'_> {
+    async fn next(&mut self) -> Option<Result<Response>> {
+        loop {
+            if self.done || self.poller.max_polls.is_some_and(|max| self.polls >= max) {
+                return None;
+            }
+            if self.polls > 0 {
+                self.poller.client.config().clock.sleep(self.poller.delay()).await;
+            }
+            self.polls += 1;
+            
+            let response = match self.poll().await {
+                Ok(response) => response,
+                Err(e) => {
+                    self.done = self.poller.stop_on_error;
+                    return Some(Err(e));
+                }
+            };
+            if self.poller.changes_only {
+                let etag = response.headers().get(ETAG).cloned();
+                let unchanged = response.status() == StatusCode::NOT_MODIFIED
+                    || (response.status().is_success() && etag.is_some() && etag == self.etag);
+                if unchanged {
+                    continue;
+                }
+                if etag.is_some() {
+                    self.etag = etag;
+                }
+            }
+            
+            self.done = self.poller.until.as_ref().is_some_and(|until| until(&response));
+            return Some(Ok(response));
+        }
+    }
+    
+    async fn poll(&self) -> Result<Response> {
+        let mut builder = self.poller.client.request(Method::GET, &self.poller.url)?;
+        if let Some(etag) = self.etag.as_ref().filter(|_| self.poller.changes_only) {
+            builder = builder.header(IF_NONE_MATCH, etag.clone());
+        }
+        self.poller.client.execute(builder.build()?).await
+    }
+}
+
+impl HttpClient {
+    /// Poll `url` with a GET every `interval`
+    ///
+    /// Without a stop condition the stream never ends; drop it to stop.
+    pub fn poll_every(&self, interval: Duration, url: &str) -> Poller<'_> {
+        Poller {
+            client: self,
+            url: url.to_string(),
+            interval,
+            jitter: Duration::ZERO,
+            changes_only: false,
+            max_polls: None,
+            stop_on_error: false,
+            until: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::client::ClientConfig;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_poll_changes_only() {
+        let transport = MockTransport::new();
+        let tagged = |etag: &'static str, state: &str| {
+            MockResponse::json(StatusCode::OK, &json!({ "state": state }))
+                .unwrap()
+                .with_header(ETAG, HeaderValue::from_static(etag))
+        };
+        transport.expect(
+            Mock::get("/job")
+                .with_header(IF_NONE_MATCH, HeaderValue::from_static("\"v2\""))
+                .respond_with(MockResponse::new(StatusCode::NOT_MODIFIED)),
+        );
+        transport.expect(
+            Mock::get("/job")
+                .with_header(IF_NONE_MATCH, HeaderValue::from_static("\"v1\""))
+                .respond_with(tagged("\"v2\"", "done")),
+        );
+        transport.expect(Mock::get("/job").respond_with(tagged("\"v1\"", "running")));
+        
+        let clock = FakeClock::new();
+        let config = ClientConfig::new()
+            .with_base_url("http://api.test")
+            .unwrap()
+            .with_clock(Arc::new(clock.clone()));
+        let client = HttpClient::with_config(config).unwrap().with_transport(transport.clone());
+        
+        let states: Vec<_> = client
+            .poll_every(Duration::from_secs(5), "/job")
+            .changes_only()
+            .with_max_polls(4)
+            .json::<Value>()
+            .map(|state| state.unwrap()["state"].clone())
+            .collect()
+            .await;
+        assert_eq!(states, ["running", "done"]);
+        assert_eq!(transport.requests().len(), 4);
+        assert_eq!(clock.sleeps(), [Duration::from_secs(5); 3]);
+    }
+    
+    #[tokio::test]
+    async fn test_poll_until_and_jitter() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::get("/status").respond_with(MockResponse::new(StatusCode::ACCEPTED)));
+        let clock = FakeClock::new();
+        let config = ClientConfig::new()
+            .with_base_url("http://api.test")
+            .unwrap()
+            .with_clock(Arc::new(clock.clone()));
+        let client = HttpClient::with_config(config).unwrap().with_transport(transport.clone());
+        
+        let polls = std::sync::atomic::AtomicUsize::new(0);
+        let responses: Vec<_> = client
+            .poll_every(Duration::from_secs(1), "/status")
+            .with_jitter(Duration::from_millis(500))
+            .until(|_| polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 2)
+            .responses()
+            .collect()
+            .await;
+        assert_eq!(responses.len(), 3);
+        let jittered = |sleep: &Duration| *sleep >= Duration::from_secs(1) && *sleep < Duration::from_millis(1500);
+        assert!(clock.sleeps().iter().all(jittered));
+        
+        let errors: Vec<_> = client
+            .poll_every(Duration::from_secs(1), "/missing")
+            .stop_on_error()
+            .responses()
+            .collect()
+            .await;
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,92 @@
+// src/profile.rs
+// Per-host configuration overrides
+
+use crate::error::{HttpError, Result};
+use crate::middleware::AuthMiddleware;
+use crate::rate_limit::RateLimit;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::{fmt, time::Duration};
+
+/// Settings applied to requests whose URL host matches the profile's host
+///
+/// Profile headers are only added when the request doesn't already carry
+/// them, and the profile timeout only applies when the request has no
+/// timeout of its own.
+#[derive(Debug, Clone, Default)]
+pub struct HostProfile {
+    pub timeout: Option<Duration>,
+    pub default_headers: HeaderMap,
+    pub auth: Option<AuthMiddleware>,
+    pub rate_limit: Option<RateLimit>,
+    pub max_in_flight: Option<usize>,
+}
+
+impl HostProfile {
+    /// Create an empty host profile
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set the request timeout for this host
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    
+    /// Add a default header for this host
+    pub fn with_default_header<K, V>(mut self, key: K, value: V) -> Result<Self>
+    where
+        K: TryInto<HeaderName>,
+        K::Error: fmt::Display,
+        V: TryInto<HeaderValue>,
+        V::Error: fmt::Display,
+    {
+        let header_name = key.try_into()
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        
+        let header_value = value.try_into()
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        
+        self.default_headers.insert(header_name, header_value);
+        Ok(self)
+    }
+    
+    /// Set authentication for this host
+    pub fn with_auth(mut self, auth: AuthMiddleware) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+    
+    /// Limit the request rate to this host
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+    
+    /// Limit the number of concurrent requests to this host
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight.max(1));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_host_profile_builder() {
+        let profile = HostProfile::new()
+            .with_timeout(Duration::from_secs(120))
+            .with_default_header("X-Team", "payments").unwrap()
+            .with_auth(AuthMiddleware::bearer("token"))
+            .with_rate_limit(RateLimit::per_second(10))
+            .with_max_in_flight(4);
+        
+        assert_eq!(profile.timeout, Some(Duration::from_secs(120)));
+        assert_eq!(profile.default_headers.get("x-team").unwrap(), "payments");
+        assert!(profile.auth.is_some());
+        assert_eq!(profile.rate_limit, Some(RateLimit::per_second(10)));
+        assert_eq!(profile.max_in_flight, Some(4));
+    }
+}
@@ -0,0 +1,666 @@
+// src/proxy.rs
+// Proxy configuration
+
+use crate::error::{HttpError, Result};
+use reqwest::header::{HeaderName, HeaderValue, PROXY_AUTHORIZATION};
+use reqwest::Url;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Extra per-request check deciding whether a proxy may handle a URL
+type ProxyGate = Arc<dyn Fn(&Url) -> bool + Send + Sync>;
+
+/// Which request schemes are sent through a proxy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProxyScope {
+    /// Both `http` and `https` requests
+    #[default]
+    All,
+    /// Only `http` requests
+    Http,
+    /// Only `https` requests (tunneled with CONNECT)
+    Https,
+}
+
+/// Credentials sent to a proxy in the `Proxy-Authorization` header
+#[derive(Clone, PartialEq, Eq)]
+pub enum ProxyAuth {
+    /// Basic auth with a username and password
+    Basic { username: String, password: String },
+    /// A raw `Proxy-Authorization` header value (e.g. a proxy token)
+    Custom(HeaderValue),
+}
+
+impl fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyAuth::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+            ProxyAuth::Custom(_) => f.debug_tuple("Custom").field(&"<redacted>").finish(),
+        }
+    }
+}
+
+/// Configuration for a single proxy
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    pub url: Url,
+    pub scope: ProxyScope,
+    pub auth: Option<ProxyAuth>,
+    pub auth_connect_only: bool,
+    pub connect_headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl ProxyConfig {
+    /// Proxy both `http` and `https` requests through `url`
+    pub fn new(url: &str) -> Result<Self> {
+        let url = Url::parse(url)
+            .map_err(|e| HttpError::ConfigError(format!("Invalid proxy URL '{}': {}", url, e)))?;
+        
+        Ok(Self {
+            url,
+            scope: ProxyScope::All,
+            auth: None,
+            auth_connect_only: false,
+            connect_headers: Vec::new(),
+        })
+    }
+    
+    /// Proxy only `http` requests through `url`
+    pub fn http(url: &str) -> Result<Self> {
+        Ok(Self::new(url)?.with_scope(ProxyScope::Http))
+    }
+    
+    /// Proxy only `https` requests through `url`
+    pub fn https(url: &str) -> Result<Self> {
+        Ok(Self::new(url)?.with_scope(ProxyScope::Https))
+    }
+    
+    /// Set which request schemes use this proxy
+    pub fn with_scope(mut self, scope: ProxyScope) -> Self {
+        self.scope = scope;
+        self
+    }
+    
+    /// Authenticate to the proxy with basic auth
+    pub fn with_basic_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth = Some(ProxyAuth::Basic {
+            username: username.into(),
+            password: password.into(),
+        });
+        self
+    }
+    
+    /// Authenticate to the proxy with a raw `Proxy-Authorization` value
+    pub fn with_custom_auth<V>(mut self, value: V) -> Result<Self>
+    where
+        V: TryInto<HeaderValue>,
+        V::Error: fmt::Display,
+    {
+        let mut value = value.try_into()
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        value.set_sensitive(true);
+        self.auth = Some(ProxyAuth::Custom(value));
+        Ok(self)
+    }
+    
+    /// Only send credentials on CONNECT tunnels (`https` requests)
+    ///
+    /// Plain `http` requests still go through the proxy but without a
+    /// `Proxy-Authorization` header, for proxies that only challenge CONNECT.
+    pub fn auth_connect_only(mut self, connect_only: bool) -> Self {
+        self.auth_connect_only = connect_only;
+        self
+    }
+    
+    /// Add a header to the CONNECT request that opens `https` tunnels
+    ///
+    /// The reqwest backend only lets `Proxy-Authorization` through to CONNECT,
+    /// so other names fail with `ConfigError`. The value is sent on CONNECT
+    /// only, in place of `with_basic_auth`/`with_custom_auth` credentials;
+    /// plain `http` requests keep those. Building a client with connect
+    /// headers on an `http`-only proxy fails, since it never opens a tunnel.
+    pub fn with_connect_header<K, V>(mut self, key: K, value: V) -> Result<Self>
+    where
+        K: TryInto<HeaderName>,
+        K::Error: fmt::Display,
+        V: TryInto<HeaderValue>,
+        V::Error: fmt::Display,
+    {
+        let key = key.try_into()
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        let mut value = value.try_into()
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        if key != PROXY_AUTHORIZATION {
+            return Err(HttpError::ConfigError(format!(
+                "CONNECT header '{}' isn't supported, only Proxy-Authorization reaches CONNECT requests",
+                key
+            )));
+        }
+        value.set_sensitive(true);
+        self.connect_headers.retain(|(name, _)| *name != key);
+        self.connect_headers.push((key, value));
+        Ok(self)
+    }
+    
+    /// Check whether this proxy handles requests with `scheme`
+    pub fn handles_scheme(&self, scheme: &str) -> bool {
+        match self.scope {
+            ProxyScope::All => true,
+            ProxyScope::Http => scheme == "http",
+            ProxyScope::Https => scheme == "https",
+        }
+    }
+    
+    /// The `Proxy-Authorization` value for CONNECT requests, if any
+    ///
+    /// Fails when the proxy never opens a tunnel or a header can't be sent.
+    fn connect_auth(&self) -> Result<Option<HeaderValue>> {
+        if self.connect_headers.is_empty() {
+            return Ok(None);
+        }
+        if self.scope == ProxyScope::Http {
+            return Err(HttpError::ConfigError(format!(
+                "Proxy '{}' has CONNECT headers but only handles http requests, which don't use CONNECT",
+                self.url
+            )));
+        }
+        let mut auth = None;
+        for (name, value) in &self.connect_headers {
+            if *name != PROXY_AUTHORIZATION {
+                return Err(HttpError::ConfigError(format!(
+                    "Proxy '{}' has CONNECT header '{}', only Proxy-Authorization reaches CONNECT requests",
+                    self.url, name
+                )));
+            }
+            auth = Some(value.clone());
+        }
+        Ok(auth)
+    }
+    
+    /// Convert to reqwest proxies
+    pub(crate) fn to_reqwest(&self) -> Result<Vec<reqwest::Proxy>> {
+        self.to_reqwest_gated(None)
+    }
+    
+    /// Convert to reqwest proxies that only handle URLs accepted by `gate`
+    fn to_reqwest_gated(&self, gate: Option<ProxyGate>) -> Result<Vec<reqwest::Proxy>> {
+        let tunnel_auth = self.connect_auth()?.map(ProxyAuth::Custom);
+        
+        let build = |scope: ProxyScope, auth: Option<&ProxyAuth>| -> Result<reqwest::Proxy> {
+            let url = self.url.clone();
+            let proxy = match (&gate, scope) {
+                (Some(gate), scope) => {
+                    let gate = gate.clone();
+                    let scoped = Self { scope, ..self.clone() };
+                    Ok(reqwest::Proxy::custom(move |target| {
+                        (scoped.handles_scheme(target.scheme()) && gate(target)).then(|| url.clone())
+                    }))
+                }
+                (None, ProxyScope::All) => reqwest::Proxy::all(url),
+                (None, ProxyScope::Http) => reqwest::Proxy::http(url),
+                (None, ProxyScope::Https) => reqwest::Proxy::https(url),
+            }
+            .map_err(|e| HttpError::ConfigError(format!("Invalid proxy '{}': {}", self.url, e)))?;
+            
+            Ok(match auth {
+                Some(ProxyAuth::Basic { username, password }) => proxy.basic_auth(username, password),
+                Some(ProxyAuth::Custom(value)) => proxy.custom_http_auth(value.clone()),
+                None => proxy,
+            })
+        };
+        
+        let auth = self.auth.as_ref();
+        if tunnel_auth.is_none() && (auth.is_none() || !self.auth_connect_only) {
+            return Ok(vec![build(self.scope, auth)?]);
+        }
+        
+        // CONNECT and plain http get different credentials, so each needs a proxy of its own
+        let connect_auth = tunnel_auth.as_ref().or(auth);
+        let http_auth = auth.filter(|_| !self.auth_connect_only);
+        
+        match self.scope {
+            ProxyScope::All => Ok(vec![
+                build(ProxyScope::Https, connect_auth)?,
+                build(ProxyScope::Http, http_auth)?,
+            ]),
+            ProxyScope::Http => Ok(vec![build(ProxyScope::Http, http_auth)?]),
+            ProxyScope::Https => Ok(vec![build(ProxyScope::Https, connect_auth)?]),
+        }
+    }
+}
+
+/// Where requests matching a routing rule are sent
+#[derive(Debug, Clone)]
+pub enum ProxyRoute {
+    /// Connect directly, bypassing every proxy
+    Direct,
+    /// Send through this proxy
+    Proxy(ProxyConfig),
+}
+
+/// Ordered host-based proxy routing rules
+///
+/// Patterns use the `NO_PROXY` syntax (domain suffixes, IPs, CIDR blocks
+/// and `*`). The first rule matching a request's host wins; requests no
+/// rule matches fall back to `with_proxy` and `with_env_proxy` settings.
+/// A proxy route whose scope doesn't cover the request's scheme is skipped.
+#[derive(Debug, Clone, Default)]
+pub struct ProxyRoutes {
+    rules: Vec<(NoProxy, ProxyRoute)>,
+}
+
+impl ProxyRoutes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add a rule sending hosts matching `pattern` to `route`
+    pub fn route(mut self, pattern: &str, route: ProxyRoute) -> Self {
+        self.rules.push((NoProxy::parse(pattern), route));
+        self
+    }
+    
+    /// Connect directly to hosts matching `pattern`
+    pub fn direct(self, pattern: &str) -> Self {
+        self.route(pattern, ProxyRoute::Direct)
+    }
+    
+    /// Send requests to hosts matching `pattern` through `proxy`
+    pub fn via(self, pattern: &str, proxy: ProxyConfig) -> Self {
+        self.route(pattern, ProxyRoute::Proxy(proxy))
+    }
+    
+    /// Check whether any rules are configured
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+    
+    /// Index of the first rule that applies to `url`
+    fn rule_for(&self, url: &Url) -> Option<usize> {
+        let host = url.host_str()?;
+        
+        self.rules.iter().position(|(pattern, route)| {
+            pattern.matches(host)
+                && match route {
+                    ProxyRoute::Direct => true,
+                    ProxyRoute::Proxy(proxy) => proxy.handles_scheme(url.scheme()),
+                }
+        })
+    }
+    
+    /// Get the route for `url`, or `None` if no rule applies
+    pub fn route_for(&self, url: &Url) -> Option<&ProxyRoute> {
+        self.rule_for(url).map(|index| &self.rules[index].1)
+    }
+}
+
+/// Build the reqwest proxies for a client's routing rules, explicit proxies
+/// and environment settings, in that order of precedence
+pub(crate) fn build_proxies(
+    routes: &ProxyRoutes,
+    proxies: &[ProxyConfig],
+    env_proxy: Option<&EnvProxy>,
+) -> Result<Vec<reqwest::Proxy>> {
+    let mut built = Vec::new();
+    
+    if routes.is_empty() {
+        for proxy in proxies {
+            built.extend(proxy.to_reqwest()?);
+        }
+        built.extend(env_proxy.map(EnvProxy::to_reqwest));
+        return Ok(built);
+    }
+    
+    let routes = Arc::new(routes.clone());
+    
+    for (index, (_, route)) in routes.rules.iter().enumerate() {
+        if let ProxyRoute::Proxy(proxy) = route {
+            let routes = routes.clone();
+            let gate: ProxyGate = Arc::new(move |url| routes.rule_for(url) == Some(index));
+            built.extend(proxy.to_reqwest_gated(Some(gate))?);
+        }
+    }
+    
+    let unrouted: ProxyGate = {
+        let routes = routes.clone();
+        Arc::new(move |url| routes.rule_for(url).is_none())
+    };
+    
+    for proxy in proxies {
+        built.extend(proxy.to_reqwest_gated(Some(unrouted.clone()))?);
+    }
+    
+    if let Some(env_proxy) = env_proxy {
+        let env = env_proxy.clone();
+        built.push(reqwest::Proxy::custom(move |url| {
+            if unrouted(url) { env.proxy_for(url).cloned() } else { None }
+        }));
+    }
+    
+    Ok(built)
+}
+
+/// A single `NO_PROXY` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NoProxyRule {
+    /// An IP address or CIDR block
+    Network(IpAddr, u8),
+    /// A domain, matching itself and all subdomains
+    Domain(String),
+}
+
+/// Parsed `NO_PROXY` list
+///
+/// Entries are comma or whitespace separated. `*` disables proxying
+/// entirely, IPs and CIDR blocks (`10.0.0.0/8`) match addresses, and any
+/// other entry matches that domain and its subdomains (a leading `.` is
+/// optional). Ports on entries are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoProxy {
+    match_all: bool,
+    rules: Vec<NoProxyRule>,
+}
+
+impl NoProxy {
+    /// Parse a `NO_PROXY` value
+    pub fn parse(list: &str) -> Self {
+        let mut no_proxy = Self::default();
+        
+        for entry in list.split(|c: char| c == ',' || c.is_whitespace()) {
+            let entry = entry.trim().to_ascii_lowercase();
+            if entry.is_empty() {
+                continue;
+            }
+            if entry == "*" {
+                no_proxy.match_all = true;
+                continue;
+            }
+            
+            if let Some(rule) = Self::parse_network(&entry) {
+                no_proxy.rules.push(rule);
+                continue;
+            }
+            
+            let domain = match entry.rsplit_once(':') {
+                Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+                _ => entry.as_str(),
+            };
+            let domain = domain.trim_start_matches("*.").trim_start_matches('.');
+            if !domain.is_empty() {
+                no_proxy.rules.push(NoProxyRule::Domain(domain.to_string()));
+            }
+        }
+        
+        no_proxy
+    }
+    
+    fn parse_network(entry: &str) -> Option<NoProxyRule> {
+        let (addr, prefix) = match entry.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+            None => (entry, None),
+        };
+        let addr = addr.trim_start_matches('[').trim_end_matches(']');
+        let ip = addr.parse::<IpAddr>().ok()?;
+        let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max_prefix);
+        
+        (prefix <= max_prefix).then_some(NoProxyRule::Network(ip, prefix))
+    }
+    
+    /// Check whether requests to `host` should bypass the proxy
+    pub fn matches(&self, host: &str) -> bool {
+        if self.match_all {
+            return true;
+        }
+        
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+        let host_ip = host.parse::<IpAddr>().ok();
+        
+        self.rules.iter().any(|rule| match (rule, host_ip) {
+            (NoProxyRule::Network(network, prefix), Some(ip)) => in_network(ip, *network, *prefix),
+            (NoProxyRule::Domain(domain), None) => {
+                host == *domain
+                    || (host.ends_with(domain.as_str())
+                        && host.as_bytes()[host.len() - domain.len() - 1] == b'.')
+            }
+            _ => false,
+        })
+    }
+}
+
+/// Check whether `ip` lies within `network/prefix`
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Proxy settings read from the environment, curl style
+///
+/// `http` requests use `http_proxy` (lowercase only, as an uppercase
+/// `HTTP_PROXY` can be injected by CGI request headers), `https` requests
+/// use `https_proxy`/`HTTPS_PROXY`, and both fall back to
+/// `all_proxy`/`ALL_PROXY`. `no_proxy`/`NO_PROXY` lists hosts that bypass
+/// the proxy.
+#[derive(Debug, Clone, Default)]
+pub struct EnvProxy {
+    pub http: Option<Url>,
+    pub https: Option<Url>,
+    pub all: Option<Url>,
+    pub no_proxy: NoProxy,
+}
+
+impl EnvProxy {
+    /// Read proxy settings from the process environment
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+    
+    /// Read proxy settings using a custom variable lookup
+    pub fn from_lookup<F>(lookup: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| lookup(name))
+                .find(|value| !value.trim().is_empty())
+        };
+        
+        Ok(Self {
+            http: var(&["http_proxy"]).map(|url| parse_env_proxy_url(&url)).transpose()?,
+            https: var(&["https_proxy", "HTTPS_PROXY"])
+                .map(|url| parse_env_proxy_url(&url))
+                .transpose()?,
+            all: var(&["all_proxy", "ALL_PROXY"])
+                .map(|url| parse_env_proxy_url(&url))
+                .transpose()?,
+            no_proxy: var(&["no_proxy", "NO_PROXY"])
+                .map(|list| NoProxy::parse(&list))
+                .unwrap_or_default(),
+        })
+    }
+    
+    /// Get the proxy to use for `url`, if any
+    pub fn proxy_for(&self, url: &Url) -> Option<&Url> {
+        if url.host_str().is_some_and(|host| self.no_proxy.matches(host)) {
+            return None;
+        }
+        
+        match url.scheme() {
+            "http" => self.http.as_ref().or(self.all.as_ref()),
+            "https" => self.https.as_ref().or(self.all.as_ref()),
+            _ => self.all.as_ref(),
+        }
+    }
+    
+    /// Check whether any proxy variable was set
+    pub fn is_empty(&self) -> bool {
+        self.http.is_none() && self.https.is_none() && self.all.is_none()
+    }
+    
+    /// Convert to a reqwest proxy that consults these settings per request
+    pub(crate) fn to_reqwest(&self) -> reqwest::Proxy {
+        let env = self.clone();
+        reqwest::Proxy::custom(move |url| env.proxy_for(url).cloned())
+    }
+}
+
+/// Parse a proxy URL from the environment, defaulting to `http://`
+fn parse_env_proxy_url(value: &str) -> Result<Url> {
+    let value = value.trim();
+    let with_scheme = if value.contains("://") {
+        value.to_string()
+    } else {
+        format!("http://{}", value)
+    };
+    
+    Url::parse(&with_scheme)
+        .map_err(|e| HttpError::ConfigError(format!("Invalid proxy URL '{}': {}", value, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_proxy_config_builder() {
+        let proxy = ProxyConfig::https("http://proxy.internal:3128")
+            .unwrap()
+            .with_basic_auth("user", "hunter2");
+        
+        assert_eq!(proxy.scope, ProxyScope::Https);
+        assert_eq!(proxy.url.as_str(), "http://proxy.internal:3128/");
+        assert!(matches!(proxy.auth, Some(ProxyAuth::Basic { .. })));
+        assert!(ProxyConfig::new("not a url").is_err());
+    }
+    
+    #[test]
+    fn test_proxy_auth_debug_redacts_secrets() {
+        let proxy = ProxyConfig::new("http://proxy.internal:3128")
+            .unwrap()
+            .with_basic_auth("user", "hunter2");
+        let debug = format!("{:?}", proxy);
+        
+        assert!(debug.contains("user"));
+        assert!(!debug.contains("hunter2"));
+    }
+    
+    #[test]
+    fn test_no_proxy_matching() {
+        let no_proxy = NoProxy::parse(".internal.example.com, localhost:8080 10.0.0.0/8,::1,192.168.1.7");
+        
+        assert!(no_proxy.matches("internal.example.com"));
+        assert!(no_proxy.matches("api.internal.example.com"));
+        assert!(!no_proxy.matches("notinternal.example.com"));
+        assert!(no_proxy.matches("localhost"));
+        assert!(no_proxy.matches("10.20.30.40"));
+        assert!(!no_proxy.matches("11.0.0.1"));
+        assert!(no_proxy.matches("[::1]"));
+        assert!(no_proxy.matches("192.168.1.7"));
+        assert!(!no_proxy.matches("192.168.1.8"));
+        assert!(!no_proxy.matches("example.com"));
+        
+        assert!(NoProxy::parse("*").matches("anything.example.com"));
+        assert!(!NoProxy::parse("").matches("example.com"));
+    }
+    
+    #[test]
+    fn test_env_proxy_selection() {
+        let vars = |name: &str| match name {
+            "http_proxy" => Some("proxy.corp:3128".to_string()),
+            "HTTPS_PROXY" => Some("http://secure-proxy.corp:3129".to_string()),
+            "NO_PROXY" => Some(".corp,10.0.0.0/8".to_string()),
+            _ => None,
+        };
+        let env = EnvProxy::from_lookup(vars).unwrap();
+        
+        let proxy_for = |url: &str| env.proxy_for(&Url::parse(url).unwrap()).map(|u| u.to_string());
+        assert_eq!(proxy_for("http://example.com/"), Some("http://proxy.corp:3128/".to_string()));
+        assert_eq!(proxy_for("https://example.com/"), Some("http://secure-proxy.corp:3129/".to_string()));
+        assert_eq!(proxy_for("https://git.corp/"), None);
+        assert_eq!(proxy_for("http://10.1.2.3/"), None);
+    }
+    
+    #[test]
+    fn test_env_proxy_ignores_uppercase_http_proxy() {
+        let env = EnvProxy::from_lookup(|name| {
+            (name == "HTTP_PROXY").then(|| "http://injected:8080".to_string())
+        })
+        .unwrap();
+        
+        assert!(env.is_empty());
+    }
+    
+    #[test]
+    fn test_proxy_routes_first_match_wins() {
+        let partner = ProxyConfig::https("http://partner-proxy:3128").unwrap();
+        let routes = ProxyRoutes::new()
+            .direct("internal.corp, 10.0.0.0/8")
+            .via("partner.example.com", partner)
+            .via("*", ProxyConfig::new("http://egress:3128").unwrap());
+        
+        let route = |url: &str| match routes.route_for(&Url::parse(url).unwrap()) {
+            Some(ProxyRoute::Direct) => "direct".to_string(),
+            Some(ProxyRoute::Proxy(proxy)) => proxy.url.host_str().unwrap().to_string(),
+            None => "none".to_string(),
+        };
+        
+        assert_eq!(route("https://git.internal.corp/"), "direct");
+        assert_eq!(route("http://10.1.2.3/"), "direct");
+        assert_eq!(route("https://api.partner.example.com/"), "partner-proxy");
+        // the partner proxy only handles https, so plain http falls through
+        assert_eq!(route("http://partner.example.com/"), "egress");
+        assert_eq!(route("https://example.org/"), "egress");
+        assert!(ProxyRoutes::new().route_for(&Url::parse("https://example.org/").unwrap()).is_none());
+    }
+    
+    #[test]
+    fn test_build_proxies_with_routes() {
+        let routes = ProxyRoutes::new()
+            .direct("internal.corp")
+            .via("example.com", ProxyConfig::new("http://a:1").unwrap());
+        let proxies = vec![ProxyConfig::new("http://b:2").unwrap()];
+        
+        assert_eq!(build_proxies(&routes, &proxies, None).unwrap().len(), 2);
+        assert_eq!(build_proxies(&ProxyRoutes::new(), &proxies, None).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_connect_only_auth_splits_proxies() {
+        let proxy = ProxyConfig::new("http://proxy.internal:3128")
+            .unwrap()
+            .with_custom_auth("Bearer proxy-token")
+            .unwrap()
+            .auth_connect_only(true);
+        
+        assert_eq!(proxy.to_reqwest().unwrap().len(), 2);
+        assert_eq!(proxy.clone().auth_connect_only(false).to_reqwest().unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_connect_header_splits_proxies() {
+        let proxy = ProxyConfig::new("http://proxy.internal:3128")
+            .unwrap()
+            .with_connect_header("proxy-authorization", "Bearer tunnel-token")
+            .unwrap();
+        
+        assert_eq!(proxy.to_reqwest().unwrap().len(), 2);
+        assert_eq!(proxy.clone().with_scope(ProxyScope::Https).to_reqwest().unwrap().len(), 1);
+        assert!(proxy.with_scope(ProxyScope::Http).to_reqwest().is_err());
+    }
+}
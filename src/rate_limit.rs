@@ -0,0 +1,492 @@
+// src/rate_limit.rs
+// Token bucket rate limiting for outgoing requests
+
+use crate::clock::{system_clock, Clock};
+use crate::error::{HttpError, Result};
+use crate::middleware::Middleware;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Request, Response, StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A rate limit expressed as a number of requests per period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Create a rate limit of `requests` per `per`
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self { requests, per }
+    }
+    
+    /// Create a rate limit of `requests` per second
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+    
+    /// Create a rate limit of `requests` per minute
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+    
+    fn tokens_per_sec(&self) -> f64 {
+        self.requests as f64 / self.per.as_secs_f64()
+    }
+}
+
+/// Token bucket limiter enforcing a `RateLimit`
+///
+/// The bucket starts full, so up to `requests` calls may burst before
+/// pacing kicks in.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    clock: Arc<dyn Clock>,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Create a new limiter with a full bucket
+    pub fn new(limit: RateLimit) -> Self {
+        Self::with_clock(limit, system_clock())
+    }
+    
+    /// Create a new limiter that reads time from `clock`
+    pub fn with_clock(limit: RateLimit, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(BucketState {
+                tokens: limit.requests as f64,
+                last_refill: clock.now(),
+            }),
+            clock,
+        }
+    }
+    
+    /// Get the configured limit
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+    
+    /// Reserve a token, returning how long the caller must wait before sending
+    pub fn reserve(&self) -> Duration {
+        let rate = self.limit.tokens_per_sec();
+        if rate <= 0.0 || !rate.is_finite() {
+            return Duration::ZERO;
+        }
+        
+        let mut state = self.state.lock().unwrap();
+        let now = self.clock.now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * rate).min(self.limit.requests as f64);
+        state.last_refill = now;
+        state.tokens -= 1.0;
+        
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / rate)
+        }
+    }
+    
+    /// Wait until a request may be sent
+    pub async fn acquire(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            self.clock.sleep(wait).await;
+        }
+    }
+    
+    /// Block the current thread until a request may be sent
+    pub fn acquire_blocking(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            self.clock.sleep_blocking(wait);
+        }
+    }
+}
+
+/// Hosts and paths a rate limit applies to
+///
+/// Patterns are a host with an optional path prefix: `api.github.com`,
+/// `api.example.com/v1/search`, `*.internal.example` for any subdomain, or
+/// `*` for every host. Ports are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutePattern {
+    host: String,
+    path_prefix: Option<String>,
+}
+
+impl RoutePattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let (host, path) = match pattern.find('/') {
+            Some(i) => (&pattern[..i], Some(&pattern[i..])),
+            None => (pattern, None),
+        };
+        let host = host.trim().to_ascii_lowercase();
+        
+        let valid = !host.is_empty() && (host == "*" || !host.trim_start_matches("*.").contains('*'));
+        if !valid {
+            return Err(HttpError::ConfigError(format!("Invalid route pattern '{}'", pattern)));
+        }
+        
+        Ok(Self {
+            host,
+            path_prefix: path.map(|path| path.trim_end_matches('/').to_string()).filter(|path| !path.is_empty()),
+        })
+    }
+    
+    /// Check whether `url` falls under this pattern
+    pub fn matches(&self, url: &Url) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false,
+        };
+        
+        let host_matches = match self.host.strip_prefix("*.") {
+            _ if self.host == "*" => true,
+            Some(domain) => host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.') && sub.len() > 1),
+            None => host == self.host,
+        };
+        
+        host_matches
+            && self.path_prefix.as_ref().is_none_or(|prefix| {
+                url.path()
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+    }
+}
+
+/// A server's advertised request quota for one host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RateBudget {
+    /// Requests allowed per window, if advertised
+    pub limit: Option<u64>,
+    /// Requests left in the current window (counting requests in flight)
+    pub remaining: Option<u64>,
+    /// Time until the window resets
+    pub reset_in: Option<Duration>,
+    /// Time until a `Retry-After` back-off ends
+    pub retry_after: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct HostBudget {
+    limit: Option<u64>,
+    remaining: Option<u64>,
+    reset_at: Option<Instant>,
+    blocked_until: Option<Instant>,
+    next_send: Option<Instant>,
+}
+
+/// Middleware that paces requests to stay within server-advertised quotas
+///
+/// Reads `X-RateLimit-Limit/Remaining/Reset` (or the unprefixed
+/// `RateLimit-*` draft headers) from every response, and `Retry-After` from
+/// 429 and 503 responses. Requests to a host are then spread evenly over
+/// what's left of the window, and held back entirely while the quota is
+/// spent or a `Retry-After` is pending. Waits are capped at `max_wait`.
+/// Clones share state.
+#[derive(Debug, Clone)]
+pub struct AdaptiveThrottle {
+    hosts: Arc<Mutex<HashMap<String, HostBudget>>>,
+    max_wait: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for AdaptiveThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveThrottle {
+    pub fn new() -> Self {
+        Self {
+            hosts: Arc::default(),
+            max_wait: Duration::from_secs(60),
+            clock: system_clock(),
+        }
+    }
+    
+    /// Set the longest a single request will be held back
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+    
+    /// Read time and wait through `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Get the current budget for `host`, if the server advertised one
+    pub fn budget(&self, host: &str) -> Option<RateBudget> {
+        let hosts = self.hosts.lock().unwrap();
+        let budget = hosts.get(&host.to_ascii_lowercase())?;
+        let now = self.clock.now();
+        let until = |at: Option<Instant>| at.map(|at| at.saturating_duration_since(now));
+        
+        Some(RateBudget {
+            limit: budget.limit,
+            remaining: budget.remaining,
+            reset_in: until(budget.reset_at),
+            retry_after: until(budget.blocked_until).filter(|d| !d.is_zero()),
+        })
+    }
+    
+    /// Update the budget for `host` from a response's status and headers
+    pub fn observe(&self, host: &str, status: StatusCode, headers: &HeaderMap) {
+        let header = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| headers.get(*name))
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+        };
+        let number = |names: &[&str]| header(names).and_then(|value| value.parse::<u64>().ok());
+        
+        let limit = number(&["x-ratelimit-limit", "ratelimit-limit"]);
+        let remaining = number(&["x-ratelimit-remaining", "ratelimit-remaining"]);
+        let reset = number(&["x-ratelimit-reset", "ratelimit-reset"]).map(reset_delay);
+        let retry_after = match status {
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+                header(&[RETRY_AFTER.as_str()]).and_then(parse_retry_after)
+            }
+            _ => None,
+        };
+        
+        if limit.is_none() && remaining.is_none() && reset.is_none() && retry_after.is_none() {
+            return;
+        }
+        
+        let now = self.clock.now();
+        let mut hosts = self.hosts.lock().unwrap();
+        let budget = hosts.entry(host.to_ascii_lowercase()).or_default();
+        
+        if limit.is_some() {
+            budget.limit = limit;
+        }
+        if remaining.is_some() {
+            budget.remaining = remaining;
+        }
+        if let Some(reset) = reset {
+            budget.reset_at = Some(now + reset);
+        }
+        if let Some(retry_after) = retry_after {
+            budget.blocked_until = Some(now + retry_after);
+        }
+    }
+    
+    /// Reserve a slot for a request to `host`, returning how long to wait
+    pub fn reserve(&self, host: &str) -> Duration {
+        let mut hosts = self.hosts.lock().unwrap();
+        let budget = match hosts.get_mut(&host.to_ascii_lowercase()) {
+            Some(budget) => budget,
+            None => return Duration::ZERO,
+        };
+        
+        let now = self.clock.now();
+        
+        // a finished window restores the full quota until the server says otherwise
+        if budget.reset_at.is_some_and(|reset_at| reset_at <= now) {
+            budget.reset_at = None;
+            budget.remaining = budget.limit;
+        }
+        
+        let mut send_at = now;
+        if let Some(blocked_until) = budget.blocked_until {
+            send_at = send_at.max(blocked_until);
+        }
+        
+        if let (Some(remaining), Some(reset_at)) = (budget.remaining, budget.reset_at) {
+            if remaining == 0 {
+                send_at = send_at.max(reset_at);
+            } else {
+                // spread what's left evenly over the rest of the window
+                let interval = reset_at.saturating_duration_since(now) / (remaining as u32).max(1);
+                if let Some(next_send) = budget.next_send {
+                    send_at = send_at.max(next_send);
+                }
+                budget.next_send = Some(send_at + interval);
+            }
+        }
+        
+        budget.remaining = budget.remaining.map(|remaining| remaining.saturating_sub(1));
+        send_at.saturating_duration_since(now).min(self.max_wait)
+    }
+    
+    /// Block the current thread until a request to `host` may be sent
+    pub fn wait_blocking(&self, host: &str) {
+        let wait = self.reserve(host);
+        if !wait.is_zero() {
+            log::debug!("Throttling request to {} for {:?}", host, wait);
+            self.clock.sleep_blocking(wait);
+        }
+    }
+}
+
+/// Interpret a reset header as a delay; large values are Unix timestamps
+fn reset_delay(value: u64) -> Duration {
+    const EPOCH_THRESHOLD: u64 = 1_000_000_000;
+    
+    if value >= EPOCH_THRESHOLD {
+        let reset = SystemTime::UNIX_EPOCH + Duration::from_secs(value);
+        reset.duration_since(SystemTime::now()).unwrap_or_default()
+    } else {
+        Duration::from_secs(value)
+    }
+}
+
+/// Parse a `Retry-After` value given in seconds or as an HTTP date
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[async_trait::async_trait]
+impl Middleware for AdaptiveThrottle {
+    async fn process_request(&self, request: &mut Request) -> Result<()> {
+        if let Some(host) = request.url().host_str() {
+            let wait = self.reserve(host);
+            if !wait.is_zero() {
+                log::debug!("Throttling request to {} for {:?}", host, wait);
+                self.clock.sleep(wait).await;
+            }
+        }
+        Ok(())
+    }
+    
+    async fn process_response(&self, response: &mut Response) -> Result<()> {
+        if let Some(host) = response.url().host_str() {
+            self.observe(host, response.status(), response.headers());
+        }
+        Ok(())
+    }
+    
+    fn name(&self) -> &'static str {
+        "AdaptiveThrottle"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_rate_limit_constructors() {
+        assert_eq!(RateLimit::per_second(5).per, Duration::from_secs(1));
+        assert_eq!(RateLimit::per_minute(5).per, Duration::from_secs(60));
+    }
+    
+    #[test]
+    fn test_route_patterns() {
+        let url = |url: &str| Url::parse(url).unwrap();
+        
+        let exact = RoutePattern::parse("API.github.com").unwrap();
+        assert!(exact.matches(&url("https://api.github.com:443/repos")));
+        assert!(!exact.matches(&url("https://github.com/")));
+        
+        let wildcard = RoutePattern::parse("*.internal.example").unwrap();
+        assert!(wildcard.matches(&url("http://billing.internal.example/")));
+        assert!(!wildcard.matches(&url("http://internal.example/")));
+        assert!(!wildcard.matches(&url("http://badinternal.example/")));
+        
+        let route = RoutePattern::parse("api.example.com/v1/search/").unwrap();
+        assert!(route.matches(&url("https://api.example.com/v1/search?q=x")));
+        assert!(route.matches(&url("https://api.example.com/v1/search/users")));
+        assert!(!route.matches(&url("https://api.example.com/v1/searches")));
+        
+        assert!(RoutePattern::parse("*").unwrap().matches(&url("http://anything/")));
+        assert!(RoutePattern::parse("api.*.com").is_err());
+        assert!(RoutePattern::parse("").is_err());
+    }
+    
+    #[test]
+    fn test_limiter_allows_burst_then_paces() {
+        let limiter = RateLimiter::new(RateLimit::per_second(2));
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        
+        let wait = limiter.reserve();
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+    }
+    
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+    
+    #[test]
+    fn test_adaptive_throttle_paces_remaining_quota() {
+        let throttle = AdaptiveThrottle::new();
+        assert_eq!(throttle.reserve("api.example.com"), Duration::ZERO);
+        
+        throttle.observe("API.example.com", StatusCode::OK, &headers(&[
+            ("x-ratelimit-limit", "100"),
+            ("x-ratelimit-remaining", "4"),
+            ("x-ratelimit-reset", "8"),
+        ]));
+        
+        let budget = throttle.budget("api.example.com").unwrap();
+        assert_eq!(budget.limit, Some(100));
+        assert_eq!(budget.remaining, Some(4));
+        assert!(budget.reset_in.unwrap() > Duration::from_secs(7));
+        
+        // four requests left over ~8s: roughly one every two seconds
+        assert_eq!(throttle.reserve("api.example.com"), Duration::ZERO);
+        let second = throttle.reserve("api.example.com");
+        assert!(second > Duration::from_millis(1900) && second <= Duration::from_secs(2));
+        assert_eq!(throttle.budget("api.example.com").unwrap().remaining, Some(2));
+    }
+    
+    #[test]
+    fn test_adaptive_throttle_waits_for_reset_and_retry_after() {
+        let throttle = AdaptiveThrottle::new();
+        throttle.observe("a.example", StatusCode::OK, &headers(&[
+            ("ratelimit-remaining", "0"),
+            ("ratelimit-reset", "5"),
+        ]));
+        let wait = throttle.reserve("a.example");
+        assert!(wait > Duration::from_millis(4900) && wait <= Duration::from_secs(5));
+        
+        throttle.observe("b.example", StatusCode::TOO_MANY_REQUESTS, &headers(&[("retry-after", "3")]));
+        assert!(throttle.budget("b.example").unwrap().retry_after.is_some());
+        let wait = throttle.reserve("b.example");
+        assert!(wait > Duration::from_millis(2900) && wait <= Duration::from_secs(3));
+        
+        // Retry-After on a success response is not a back-off
+        throttle.observe("c.example", StatusCode::OK, &headers(&[("retry-after", "3")]));
+        assert!(throttle.budget("c.example").is_none());
+        
+        let capped = AdaptiveThrottle::new().with_max_wait(Duration::from_secs(1));
+        capped.observe("d.example", StatusCode::SERVICE_UNAVAILABLE, &headers(&[("retry-after", "120")]));
+        assert_eq!(capped.reserve("d.example"), Duration::from_secs(1));
+    }
+    
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
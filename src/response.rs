@@ -0,0 +1,386 @@
+// src/response.rs
+// Extensions for inspecting responses
+
+use crate::client::HttpClient;
+use crate::codec::decode_body;
+use crate::error::{HttpError, Result};
+use crate::pagination::parse_link_header;
+use hyper::body::Bytes;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE, LINK};
+use reqwest::{Response, Url};
+use serde::de::{Deserialize, DeserializeOwned};
+use serde_json::Value;
+use std::fmt;
+
+/// HTTP protocol version of a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HttpVersion {
+    Http09,
+    Http10,
+    Http11,
+    Http2,
+    Http3,
+}
+
+impl HttpVersion {
+    /// Convert from the reqwest version type
+    pub fn from_reqwest(version: reqwest::Version) -> Option<Self> {
+        match version {
+            reqwest::Version::HTTP_09 => Some(HttpVersion::Http09),
+            reqwest::Version::HTTP_10 => Some(HttpVersion::Http10),
+            reqwest::Version::HTTP_11 => Some(HttpVersion::Http11),
+            reqwest::Version::HTTP_2 => Some(HttpVersion::Http2),
+            reqwest::Version::HTTP_3 => Some(HttpVersion::Http3),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HttpVersion::Http09 => "HTTP/0.9",
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
+            HttpVersion::Http3 => "HTTP/3",
+        })
+    }
+}
+
+/// A JSON response body read once into memory
+///
+/// `parse` deserializes straight from the buffer, so `&str` fields can
+/// borrow from it instead of being copied, and the raw bytes stay around
+/// for error reporting or for parsing into a second type.
+#[derive(Debug, Clone)]
+pub struct JsonBody {
+    bytes: Bytes,
+}
+
+impl JsonBody {
+    pub fn new(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+    
+    /// Deserialize the body, borrowing from the buffer where `T` allows
+    ///
+    /// Always uses serde_json, since simd-json can't borrow from a shared buffer.
+    pub fn parse<'de, T: Deserialize<'de>>(&'de self) -> Result<T> {
+        parse_json_borrowed(&self.bytes)
+    }
+    
+    /// The raw body
+    pub fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+    
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+}
+
+/// Deserialize a JSON body, quoting its start in the error if it doesn't fit `T`
+///
+/// Every owned response JSON helper decodes through here. With the
+/// `simd-json` feature the body is parsed by simd-json, from a copy since
+/// it parses in place.
+pub(crate) fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    #[cfg(feature = "simd-json")]
+    {
+        let mut copy = body.to_vec();
+        simd_json::serde::from_slice(&mut copy).map_err(|e| json_error(e, body))
+    }
+    #[cfg(not(feature = "simd-json"))]
+    parse_json_borrowed(body)
+}
+
+/// Deserialize a JSON body with serde_json, letting `T` borrow from it
+pub(crate) fn parse_json_borrowed<'de, T: Deserialize<'de>>(body: &'de [u8]) -> Result<T> {
+    serde_json::from_slice(body).map_err(|e| json_error(e, body))
+}
+
+fn json_error(error: impl fmt::Display, body: &[u8]) -> HttpError {
+    const SNIPPET: usize = 200;
+    
+    let text = String::from_utf8_lossy(&body[..body.len().min(SNIPPET)]);
+    let more = if body.len() > SNIPPET { "..." } else { "" };
+    HttpError::SerializationError(format!("Failed to deserialize response: {} (body: {}{})", error, text, more))
+}
+
+/// Extension trait for Response to expose protocol details and decoding
+#[async_trait::async_trait]
+pub trait ResponseExt {
+    /// The HTTP version negotiated for this response
+    fn http_version(&self) -> Option<HttpVersion>;
+    
+    /// Deserialize the body in the format named by its `Content-Type`
+    ///
+    /// See `codec::decode_body` for the supported formats. Non-success
+    /// responses fail with `ResponseError`.
+    async fn decode<T: DeserializeOwned>(self) -> Result<T>;
+    
+    /// The `Content-Encoding` the body still has, if it wasn't decompressed
+    ///
+    /// `None` means the body is plain: either the server didn't compress
+    /// it or the client decoded it, see `was_decompressed`.
+    fn content_encoding(&self) -> Option<String>;
+    
+    /// Whether the client decompressed the body (see `Decompression`)
+    fn was_decompressed(&self) -> bool;
+    
+    /// Read the body once for JSON decoding with `JsonBody::parse`
+    ///
+    /// Non-success responses fail with `ResponseError`.
+    async fn json_body(self) -> Result<JsonBody>;
+    
+    /// The `Link` header target with relation `rel`, resolved against the response URL
+    fn link(&self, rel: &str) -> Option<Url>;
+    
+    /// Request the resource linked with relation `rel` through `client`
+    ///
+    /// The link is taken from the `Link` header, or else from HAL `_links`
+    /// in a JSON body; it is resolved against the final URL of this
+    /// response and fetched with a GET.
+    async fn follow(self, client: &HttpClient, rel: &str) -> Result<Response>;
+}
+
+#[async_trait::async_trait]
+impl ResponseExt for Response {
+    fn http_version(&self) -> Option<HttpVersion> {
+        HttpVersion::from_reqwest(self.version())
+    }
+    
+    async fn decode<T: DeserializeOwned>(self) -> Result<T> {
+        let status = self.status();
+        let content_type = self
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = self.bytes().await?;
+        
+        if !status.is_success() {
+            let body = String::from_utf8_lossy(&body).into_owned();
+            return Err(HttpError::ResponseError { status, body });
+        }
+        decode_body(content_type.as_deref(), &body)
+    }
+    
+    fn content_encoding(&self) -> Option<String> {
+        let encodings: Vec<&str> = self
+            .headers()
+            .get_all(CONTENT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|encoding| !encoding.is_empty() && !encoding.eq_ignore_ascii_case("identity"))
+            .collect();
+        (!encodings.is_empty()).then(|| encodings.join(", "))
+    }
+    
+    fn was_decompressed(&self) -> bool {
+        self.extensions().get::<crate::compression::Decompressed>().is_some()
+    }
+    
+    async fn json_body(self) -> Result<JsonBody> {
+        let status = self.status();
+        let body = self.bytes().await?;
+        
+        if !status.is_success() {
+            let body = String::from_utf8_lossy(&body).into_owned();
+            return Err(HttpError::ResponseError { status, body });
+        }
+        Ok(JsonBody::new(body))
+    }
+    
+    fn link(&self, rel: &str) -> Option<Url> {
+        self.headers()
+            .get_all(LINK)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(parse_link_header)
+            .find(|link| link.has_rel(rel))
+            .and_then(|link| self.url().join(&link.url).ok())
+    }
+    
+    async fn follow(self, client: &HttpClient, rel: &str) -> Result<Response> {
+        let target = match self.link(rel) {
+            Some(url) => url,
+            None => {
+                let url = self.url().clone();
+                let body: Value = serde_json::from_slice(&self.bytes().await?).unwrap_or(Value::Null);
+                hal_link(&body, rel)
+                    .and_then(|href| url.join(&href).ok())
+                    .ok_or_else(|| HttpError::UrlError(format!("No '{}' link in response from {}", rel, url)))?
+            }
+        };
+        client.get(target.as_str()).await
+    }
+}
+
+/// Get the `href` of a HAL `_links` entry
+///
+/// Arrays of links yield their first entry. URI template expressions of
+/// templated links (`{?page}`) are dropped.
+pub fn hal_link(body: &Value, rel: &str) -> Option<String> {
+    let link = match body.get("_links")?.get(rel)? {
+        Value::Array(links) => links.first()?,
+        link => link,
+    };
+    let href = link.get("href")?.as_str()?;
+    
+    if link.get("templated").and_then(Value::as_bool) != Some(true) {
+        return Some(href.to_string());
+    }
+    let mut expanded = String::with_capacity(href.len());
+    let mut rest = href;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = rest[start..].find('}').map_or("", |end| &rest[start + end + 1..]);
+    }
+    expanded.push_str(rest);
+    Some(expanded)
+}
+
+/// Read the whole body, leaving an equivalent in-memory response behind
+///
+/// Status, headers, version and URL are kept; other extensions are lost.
+#[cfg(feature = "json-schema")]
+pub(crate) async fn buffer_body(response: &mut Response) -> Result<Vec<u8>> {
+    use reqwest::ResponseBuilderExt;
+    
+    let mut builder = hyper::Response::builder()
+        .status(response.status())
+        .version(response.version())
+        .url(response.url().clone());
+    if let Some(headers) = builder.headers_mut() {
+        *headers = response.headers().clone();
+    }
+    
+    let placeholder = Response::from(hyper::Response::new(Vec::<u8>::new()));
+    let body = std::mem::replace(response, placeholder).bytes().await?.to_vec();
+    *response = Response::from(builder.body(body.clone()).expect("valid response parts"));
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_http_version_conversion() {
+        assert_eq!(
+            HttpVersion::from_reqwest(reqwest::Version::HTTP_2),
+            Some(HttpVersion::Http2)
+        );
+        assert_eq!(HttpVersion::Http11.to_string(), "HTTP/1.1");
+        assert!(HttpVersion::Http2 > HttpVersion::Http11);
+    }
+    
+    #[tokio::test]
+    async fn test_decode_follows_content_type() {
+        use crate::client::RequestBuilderExt;
+        use crate::transport::{Mock, MockResponse, MockTransport};
+        use reqwest::header::{HeaderValue, ACCEPT};
+        use reqwest::{Method, StatusCode};
+        
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::get("/greeting")
+                .with_header(ACCEPT, HeaderValue::from_static("text/plain, application/json"))
+                .respond_with(
+                    MockResponse::new(StatusCode::OK)
+                        .with_header(CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"))
+                        .with_body("hello"),
+                ),
+        );
+        transport.expect(Mock::get("/count").respond_with(MockResponse::json(StatusCode::OK, &3).unwrap()));
+        transport.expect(Mock::get("/gone").respond_with(MockResponse::new(StatusCode::GONE)));
+        let client = transport.client();
+        
+        let request = client
+            .request(Method::GET, "/greeting")
+            .unwrap()
+            .accept(&["text/plain", "application/json"])
+            .build()
+            .unwrap();
+        let greeting: String = client.execute(request).await.unwrap().decode().await.unwrap();
+        assert_eq!(greeting, "hello");
+        
+        let count: u32 = client.get("/count").await.unwrap().decode().await.unwrap();
+        assert_eq!(count, 3);
+        
+        let gone = client.get("/gone").await.unwrap().decode::<String>().await;
+        assert!(matches!(gone, Err(HttpError::ResponseError { status: StatusCode::GONE, .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_json_body_borrows_and_reports() {
+        use crate::transport::{Mock, MockResponse, MockTransport};
+        use reqwest::StatusCode;
+        use serde::Deserialize;
+        
+        #[derive(Deserialize)]
+        struct User<'a> {
+            name: &'a str,
+        }
+        
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::get("/user").respond_with(MockResponse::new(StatusCode::OK).with_body(r#"{"name": "ada", "id": 7}"#)),
+        );
+        let client = transport.client();
+        
+        let body = client.get("/user").await.unwrap().json_body().await.unwrap();
+        let user: User = body.parse().unwrap();
+        assert_eq!(user.name, "ada");
+        let id: Value = body.parse().unwrap();
+        assert_eq!(id["id"], 7);
+        
+        match body.parse::<Vec<u8>>() {
+            Err(HttpError::SerializationError(message)) => assert!(message.ends_with(r#"(body: {"name": "ada", "id": 7})"#)),
+            other => panic!("expected a serialization error, got {:?}", other.map(|_| ())),
+        }
+        let failed: Result<Vec<u8>> = client.get_json("/user").await;
+        assert!(matches!(failed, Err(HttpError::SerializationError(message)) if message.contains("\"ada\"")));
+    }
+    
+    #[tokio::test]
+    async fn test_follow_link_header_and_hal() {
+        use crate::transport::{Mock, MockResponse, MockTransport};
+        use reqwest::header::HeaderValue;
+        use reqwest::StatusCode;
+        use serde_json::json;
+        
+        let transport = MockTransport::new();
+        transport.expect(
+            Mock::get("/orders/1/").respond_with(
+                MockResponse::new(StatusCode::OK)
+                    .with_header(LINK, HeaderValue::from_static("<items>; rel=\"items\", </customers/9>; rel=\"customer\"")),
+            ),
+        );
+        transport.expect(
+            Mock::get("/orders/1/items").respond_with(
+                MockResponse::json(
+                    StatusCode::OK,
+                    &json!({"_links": {"next": {"href": "/orders/1/items/2{?size}", "templated": true}}}),
+                )
+                .unwrap(),
+            ),
+        );
+        transport.expect(Mock::get("/orders/1/items/2").respond_with(MockResponse::json(StatusCode::OK, &json!({})).unwrap()));
+        let client = transport.client();
+        
+        let order = client.get("/orders/1/").await.unwrap();
+        assert_eq!(order.link("customer").unwrap().as_str(), "http://api.test/customers/9");
+        let items = order.follow(&client, "items").await.unwrap();
+        assert_eq!(items.url().path(), "/orders/1/items");
+        
+        let next = items.follow(&client, "next").await.unwrap();
+        assert_eq!(next.url().path(), "/orders/1/items/2");
+        let missing = next.follow(&client, "next").await;
+        assert!(matches!(missing, Err(HttpError::UrlError(_))));
+    }
+}
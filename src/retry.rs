@@ -0,0 +1,187 @@
+// src/retry.rs
+// Retry rules shared by the async and blocking clients
+
+use crate::error::HttpError;
+use crate::rate_limit::parse_retry_after;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::io::ErrorKind;
+use std::time::Duration;
+
+/// Header that marks a non-idempotent request as safe to retry
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// When and how failed requests are retried
+///
+/// Connection failures, timeouts and 429/502/503/504 responses are retried
+/// with a backoff that doubles after each attempt. A `Retry-After` header
+/// on the response replaces the backoff, unless it asks for longer than
+/// `max_retry_after`, in which case the response is returned as is.
+///
+/// Only idempotent requests are retried after they may have reached the
+/// server: GET, HEAD, PUT, DELETE, OPTIONS and TRACE, plus requests
+/// carrying an `Idempotency-Key` header. Connection failures are retried
+/// for every method, since nothing was sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff: Duration,
+    pub max_retry_after: Duration,
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            backoff: Duration::from_millis(100),
+            max_retry_after: Duration::from_secs(60),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+/// What an attempt produced, as far as retrying is concerned
+pub(crate) enum Outcome<'a> {
+    Response(StatusCode, &'a HeaderMap),
+    Error(&'a HttpError),
+}
+
+impl RetryPolicy {
+    /// Retry up to `max_retries` times, starting with `backoff`
+    pub fn new(max_retries: u32, backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            backoff,
+            ..Self::default()
+        }
+    }
+
+    /// Give up instead of waiting when `Retry-After` asks for more than `max`
+    pub fn with_max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+
+    /// Also retry POST, PATCH and other non-idempotent requests
+    pub fn with_non_idempotent_retries(mut self, enabled: bool) -> Self {
+        self.retry_non_idempotent = enabled;
+        self
+    }
+
+    /// Whether a request may be sent again once it has reached the server
+    pub fn is_idempotent(&self, method: &Method, headers: &HeaderMap) -> bool {
+        self.retry_non_idempotent
+            || matches!(
+                *method,
+                Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+            )
+            || headers.contains_key(IDEMPOTENCY_KEY)
+    }
+
+    /// How long to wait before the next attempt, or `None` to stop
+    ///
+    /// `retries` is the number of retries already made.
+    pub(crate) fn delay(&self, retries: u32, idempotent: bool, outcome: Outcome<'_>) -> Option<Duration> {
+        if retries >= self.max_retries {
+            return None;
+        }
+        let backoff = self.backoff.saturating_mul(1 << retries.min(16));
+
+        match outcome {
+            Outcome::Response(status, headers) if idempotent && is_retryable_status(status) => {
+                let retry_after = headers
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| parse_retry_after(value.trim()));
+                match retry_after {
+                    Some(wait) if wait > self.max_retry_after => None,
+                    Some(wait) => Some(wait),
+                    None => Some(backoff),
+                }
+            }
+            Outcome::Error(HttpError::RequestError(e)) if e.is_connect() => Some(backoff),
+            Outcome::Error(HttpError::RequestError(e)) if idempotent && e.is_timeout() => Some(backoff),
+            _ => None,
+        }
+    }
+}
+
+/// Statuses worth retrying
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Whether a request failed because its pooled connection was already dead
+///
+/// The server or a middlebox closed the connection while it sat idle, so
+/// the request never got an answer and can go out again on a fresh one.
+pub(crate) fn is_stale_connection(error: &HttpError) -> bool {
+    let HttpError::RequestError(e) = error else {
+        return false;
+    };
+    let mut source = std::error::Error::source(e);
+    while let Some(cause) = source {
+        if let Some(e) = cause.downcast_ref::<hyper::Error>() {
+            if e.is_incomplete_message() || e.is_closed() {
+                return true;
+            }
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            if matches!(
+                e.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+            ) {
+                return true;
+            }
+        }
+        source = cause.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_retry_delays() {
+        let policy = RetryPolicy::new(3, Duration::from_secs(1)).with_max_retry_after(Duration::from_secs(30));
+        let empty = HeaderMap::new();
+        let unavailable = Outcome::Response(StatusCode::SERVICE_UNAVAILABLE, &empty);
+        assert_eq!(policy.delay(0, true, unavailable), Some(Duration::from_secs(1)));
+        assert_eq!(
+            policy.delay(2, true, Outcome::Response(StatusCode::BAD_GATEWAY, &empty)),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(policy.delay(3, true, Outcome::Response(StatusCode::BAD_GATEWAY, &empty)), None);
+        assert_eq!(policy.delay(0, false, Outcome::Response(StatusCode::BAD_GATEWAY, &empty)), None);
+        assert_eq!(policy.delay(0, true, Outcome::Response(StatusCode::NOT_FOUND, &empty)), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        let limited = Outcome::Response(StatusCode::TOO_MANY_REQUESTS, &headers);
+        assert_eq!(policy.delay(0, true, limited), Some(Duration::from_secs(7)));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        let limited = Outcome::Response(StatusCode::TOO_MANY_REQUESTS, &headers);
+        assert_eq!(policy.delay(0, true, limited), None);
+    }
+
+    #[test]
+    fn test_idempotency_rules() {
+        let policy = RetryPolicy::default();
+        let mut headers = HeaderMap::new();
+        assert!(policy.is_idempotent(&Method::PUT, &headers));
+        assert!(!policy.is_idempotent(&Method::POST, &headers));
+        headers.insert(IDEMPOTENCY_KEY, HeaderValue::from_static("abc"));
+        assert!(policy.is_idempotent(&Method::POST, &headers));
+        assert!(policy.clone().with_non_idempotent_retries(true).is_idempotent(&Method::PATCH, &HeaderMap::new()));
+    }
+}
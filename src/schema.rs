@@ -0,0 +1,160 @@
+// src/schema.rs
+// JSON Schema validation of response bodies
+
+use crate::error::{HttpError, Result};
+use crate::middleware::Middleware;
+use crate::rate_limit::RoutePattern;
+use crate::response::buffer_body;
+use jsonschema::JSONSchema;
+use reqwest::{Request, Response};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+/// What `SchemaValidation` does when a response breaks its schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaMode {
+    /// Fail the request with a `MiddlewareError`
+    #[default]
+    Fail,
+    /// Log a warning and pass the response through
+    Warn,
+}
+
+struct RouteSchema {
+    pattern: RoutePattern,
+    schema: JSONSchema,
+}
+
+/// Middleware that checks successful response bodies against JSON Schemas
+///
+/// Schemas are registered per route pattern (see `RoutePattern`) and the
+/// first pattern matching the response URL is used. Only 2xx responses are
+/// checked; responses on routes without a schema pass through untouched.
+/// Checked bodies are buffered in memory. Clones share the schemas.
+#[derive(Clone, Default)]
+pub struct SchemaValidation {
+    routes: Vec<Arc<RouteSchema>>,
+    mode: SchemaMode,
+}
+
+impl fmt::Debug for SchemaValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchemaValidation")
+            .field("routes", &self.routes.iter().map(|route| &route.pattern).collect::<Vec<_>>())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl SchemaValidation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Check responses from routes matching `pattern` against `schema`
+    pub fn with_schema(mut self, pattern: &str, schema: &Value) -> Result<Self> {
+        let pattern = RoutePattern::parse(pattern)?;
+        let schema = JSONSchema::compile(schema)
+            .map_err(|e| HttpError::ConfigError(format!("Invalid JSON Schema: {}", e)))?;
+        self.routes.push(Arc::new(RouteSchema { pattern, schema }));
+        Ok(self)
+    }
+    
+    /// Choose between failing and warning on mismatches
+    pub fn with_mode(mut self, mode: SchemaMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    
+    /// Check `body` against the schema registered for `url`, if any
+    pub fn validate(&self, url: &reqwest::Url, body: &[u8]) -> Result<()> {
+        let route = match self.routes.iter().find(|route| route.pattern.matches(url)) {
+            Some(route) => route,
+            None => return Ok(()),
+        };
+        
+        let instance: Value = serde_json::from_slice(body)?;
+        if let Err(errors) = route.schema.validate(&instance) {
+            let errors: Vec<String> = errors
+                .map(|error| format!("{} at '{}'", error, error.instance_path))
+                .collect();
+            return Err(HttpError::MiddlewareError(format!(
+                "Response from {} does not match schema: {}",
+                url,
+                errors.join("; ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for SchemaValidation {
+    async fn process_request(&self, _request: &mut Request) -> Result<()> {
+        Ok(())
+    }
+    
+    async fn process_response(&self, response: &mut Response) -> Result<()> {
+        if !response.status().is_success() || !self.routes.iter().any(|route| route.pattern.matches(response.url())) {
+            return Ok(());
+        }
+        
+        let body = buffer_body(response).await?;
+        match self.validate(response.url(), &body) {
+            Err(e) if self.mode == SchemaMode::Warn => {
+                log::warn!("{}", e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+    
+    fn name(&self) -> &'static str {
+        "SchemaValidation"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::HttpClient;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    
+    #[tokio::test]
+    async fn test_schema_validation_fails_or_warns() {
+        let transport = MockTransport::new();
+        let user = |body: Value| MockResponse::json(StatusCode::OK, &body).unwrap();
+        transport.expect(Mock::get("/users/1").respond_with(user(json!({"id": 1, "name": "ada"}))));
+        transport.expect(Mock::get("/users/2").respond_with(user(json!({"id": "2"}))));
+        transport.expect(Mock::get("/other").respond_with(user(json!("anything"))));
+        
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {"id": {"type": "integer"}, "name": {"type": "string"}}
+        });
+        let validation = SchemaValidation::new().with_schema("api.test/users", &schema).unwrap();
+        let client = HttpClient::with_base_url("http://api.test")
+            .unwrap()
+            .with_middleware(validation.clone())
+            .with_transport(transport.clone());
+        
+        let ok: Value = client.get_json("/users/1").await.unwrap();
+        assert_eq!(ok["name"], "ada");
+        let drifted = client.get("/users/2").await.unwrap_err();
+        assert!(matches!(drifted, HttpError::MiddlewareError(message) if message.contains("/id")));
+        assert!(client.get("/other").await.is_ok());
+        
+        let client = HttpClient::with_base_url("http://api.test")
+            .unwrap()
+            .with_middleware(validation.with_mode(SchemaMode::Warn))
+            .with_transport(transport);
+        let drifted: Value = client.get_json("/users/2").await.unwrap();
+        assert_eq!(drifted["id"], "2");
+        
+        assert!(SchemaValidation::new().with_schema("*", &json!({"type": 12})).is_err());
+    }
+}
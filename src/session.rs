@@ -0,0 +1,345 @@
+// src/session.rs
+// Login sessions with cookie and CSRF token handling
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Method, Response, Url};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+use std::sync::RwLock;
+
+/// Where the CSRF token is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsrfSource {
+    /// A response header, refreshed from every response that carries it
+    Header(HeaderName),
+    /// A string field of the JSON login response, as a JSON pointer (`/csrf/token`)
+    JsonPointer(String),
+    /// A cookie set by the server (e.g. Django's `csrftoken`)
+    Cookie(String),
+}
+
+/// The request a `Session` sends to log in
+#[derive(Clone)]
+pub struct LoginRequest {
+    pub method: Method,
+    pub url: String,
+    body: Option<(&'static str, Vec<u8>)>,
+}
+
+impl fmt::Debug for LoginRequest {
+    // the body usually holds credentials
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("body", &self.body.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl LoginRequest {
+    /// POST `body` as JSON to `url`
+    pub fn json<T: Serialize>(url: impl Into<String>, body: &T) -> Result<Self> {
+        Ok(Self {
+            method: Method::POST,
+            url: url.into(),
+            body: Some(("application/json", serde_json::to_vec(body)?)),
+        })
+    }
+    
+    /// POST `body` as an urlencoded form to `url`
+    pub fn form<T: Serialize>(url: impl Into<String>, body: &T) -> Result<Self> {
+        let encoded = serde_urlencoded::to_string(body)
+            .map_err(|e| HttpError::SerializationError(e.to_string()))?;
+        
+        Ok(Self {
+            method: Method::POST,
+            url: url.into(),
+            body: Some(("application/x-www-form-urlencoded", encoded.into_bytes())),
+        })
+    }
+    
+    /// Use a different HTTP method for the login request
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+}
+
+/// A logged-in session layered over an `HttpClient`
+///
+/// `login` sends the login request, after which the client's cookie store
+/// holds the session cookies. If a CSRF source is configured, the token is
+/// captured and sent in the CSRF header on every request to the login
+/// URL's origin whose method isn't safe (anything but GET, HEAD, OPTIONS
+/// and TRACE). Header tokens are only taken from successful responses
+/// from that origin.
+#[derive(Debug)]
+pub struct Session {
+    client: HttpClient,
+    login: LoginRequest,
+    csrf_source: Option<CsrfSource>,
+    csrf_header: HeaderName,
+    csrf_token: RwLock<Option<HeaderValue>>,
+}
+
+impl Session {
+    /// Create a session; the client must have cookies enabled
+    pub fn new(client: HttpClient, login: LoginRequest) -> Result<Self> {
+        if client.cookie_store().is_none() {
+            return Err(HttpError::ConfigError(
+                "Session requires a client with cookies enabled".to_string(),
+            ));
+        }
+        
+        Ok(Self {
+            client,
+            login,
+            csrf_source: None,
+            csrf_header: HeaderName::from_static("x-csrf-token"),
+            csrf_token: RwLock::new(None),
+        })
+    }
+    
+    /// Capture the CSRF token from `source`
+    pub fn with_csrf_source(mut self, source: CsrfSource) -> Self {
+        self.csrf_source = Some(source);
+        self
+    }
+    
+    /// Set the header the CSRF token is sent in (default `X-CSRF-Token`)
+    pub fn with_csrf_header<K>(mut self, name: K) -> Result<Self>
+    where
+        K: TryInto<HeaderName>,
+        K::Error: fmt::Display,
+    {
+        self.csrf_header = name.try_into()
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        Ok(self)
+    }
+    
+    /// Get the underlying client
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+    
+    /// Get the current CSRF token, if one was captured
+    pub fn csrf_token(&self) -> Option<String> {
+        self.csrf_token
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|token| token.to_str().ok())
+            .map(str::to_string)
+    }
+    
+    /// Send the login request and capture the CSRF token
+    ///
+    /// Fails with `ResponseError` if the server rejects the login.
+    pub async fn login(&self) -> Result<()> {
+        let mut builder = self.client.request(self.login.method.clone(), &self.login.url)?;
+        if let Some((content_type, body)) = &self.login.body {
+            builder = builder.header(CONTENT_TYPE, *content_type).body(body.clone());
+        }
+        
+        let response = self.client.execute(builder.build()?).await?;
+        let status = response.status();
+        self.capture_header_token(&response);
+        let url = response.url().clone();
+        
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(HttpError::ResponseError {
+                status,
+                body: String::from_utf8_lossy(&body).to_string(),
+            });
+        }
+        
+        match &self.csrf_source {
+            Some(CsrfSource::JsonPointer(pointer)) => {
+                let json: serde_json::Value = serde_json::from_slice(&body)?;
+                let token = json.pointer(pointer).and_then(|value| value.as_str()).ok_or_else(|| {
+                    HttpError::JsonError(format!("No CSRF token at '{}' in login response", pointer))
+                })?;
+                self.set_token(token)?;
+            }
+            Some(CsrfSource::Cookie(name)) => {
+                let store = self.client.cookie_store();
+                let cookie = store
+                    .iter()
+                    .flat_map(|store| store.list(&url))
+                    .find(|cookie| cookie.name == *name)
+                    .ok_or_else(|| {
+                        HttpError::ConfigError(format!("No '{}' cookie after login", name))
+                    })?;
+                self.set_token(&cookie.value)?;
+            }
+            _ => {}
+        }
+        
+        Ok(())
+    }
+    
+    fn set_token(&self, token: &str) -> Result<()> {
+        let mut value = HeaderValue::from_str(token)
+            .map_err(|e| HttpError::HeaderError(e.to_string()))?;
+        value.set_sensitive(true);
+        *self.csrf_token.write().unwrap() = Some(value);
+        Ok(())
+    }
+    
+    /// Whether `url` has the same origin as the login URL
+    fn is_login_origin(&self, url: &Url) -> bool {
+        let login_url = self.client.build_url(&self.login.url).ok().and_then(|url| Url::parse(&url).ok());
+        login_url.is_some_and(|login_url| login_url.origin() == url.origin())
+    }
+    
+    fn capture_header_token(&self, response: &Response) {
+        if !response.status().is_success() || !self.is_login_origin(response.url()) {
+            return;
+        }
+        if let Some(CsrfSource::Header(name)) = &self.csrf_source {
+            if let Some(value) = response.headers().get(name) {
+                let mut value = value.clone();
+                value.set_sensitive(true);
+                *self.csrf_token.write().unwrap() = Some(value);
+            }
+        }
+    }
+    
+    /// Send a request, adding the CSRF token to mutating methods
+    pub async fn execute(&self, mut request: reqwest::Request) -> Result<Response> {
+        let safe = matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        );
+        
+        if !safe && !request.headers().contains_key(&self.csrf_header) && self.is_login_origin(request.url()) {
+            if let Some(token) = self.csrf_token.read().unwrap().clone() {
+                request.headers_mut().insert(self.csrf_header.clone(), token);
+            }
+        }
+        
+        let response = self.client.execute(request).await?;
+        self.capture_header_token(&response);
+        Ok(response)
+    }
+    
+    /// Send a GET request
+    pub async fn get(&self, url: &str) -> Result<Response> {
+        self.execute(self.client.request(Method::GET, url)?.build()?).await
+    }
+    
+    /// Send a GET request and deserialize the response as JSON
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.client.process_json_response(self.get(url).await?).await
+    }
+    
+    /// Send a POST request with a JSON body
+    pub async fn post_json<T: Serialize, R: DeserializeOwned>(&self, url: &str, body: &T) -> Result<R> {
+        let request = self.client.request(Method::POST, url)?.json(body).build()?;
+        self.client.process_json_response(self.execute(request).await?).await
+    }
+    
+    /// Send a PUT request with a JSON body
+    pub async fn put_json<T: Serialize, R: DeserializeOwned>(&self, url: &str, body: &T) -> Result<R> {
+        let request = self.client.request(Method::PUT, url)?.json(body).build()?;
+        self.client.process_json_response(self.execute(request).await?).await
+    }
+    
+    /// Send a DELETE request
+    pub async fn delete(&self, url: &str) -> Result<Response> {
+        self.execute(self.client.request(Method::DELETE, url)?.build()?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    
+    /// Answer one connection per response, returning the requests received
+    async fn serve(responses: Vec<&'static [u8]>) -> (u16, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase());
+                socket.write_all(response).await.unwrap();
+            }
+            requests
+        });
+        (port, server)
+    }
+    
+    #[tokio::test]
+    async fn test_login_captures_cookie_and_json_csrf_token() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nset-cookie: sid=s3cr3t; Path=/\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: 23\r\n\r\n{\"meta\":{\"csrf\":\"t0k\"}}",
+            b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: 11\r\n\r\n{\"id\":\"42\"}",
+        ])
+        .await;
+        
+        let config = ClientConfig::new()
+            .with_base_url(format!("http://127.0.0.1:{}", port)).unwrap()
+            .with_cookies(true);
+        let login = LoginRequest::form("/login", &[("user", "alice"), ("password", "pw")]).unwrap();
+        let session = Session::new(HttpClient::with_config(config).unwrap(), login)
+            .unwrap()
+            .with_csrf_source(CsrfSource::JsonPointer("/meta/csrf".to_string()));
+        
+        session.login().await.unwrap();
+        assert_eq!(session.csrf_token().as_deref(), Some("t0k"));
+        
+        let created: serde_json::Value = session.post_json("/items", &serde_json::json!({})).await.unwrap();
+        assert_eq!(created["id"], "42");
+        
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("post /login"));
+        assert!(requests[0].ends_with("user=alice&password=pw"));
+        assert!(requests[1].contains("cookie: sid=s3cr3t"));
+        assert!(requests[1].contains("x-csrf-token: t0k"));
+        
+        let (other_port, other) = serve(vec![
+            b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: 2\r\n\r\n{}",
+        ])
+        .await;
+        let other_url = format!("http://127.0.0.1:{}/items", other_port);
+        let _: serde_json::Value = session.post_json(&other_url, &serde_json::json!({})).await.unwrap();
+        assert!(!other.await.unwrap()[0].contains("x-csrf-token"));
+    }
+    
+    #[tokio::test]
+    async fn test_failed_login_and_header_token() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 401 Unauthorized\r\nx-csrf: nope\r\nconnection: close\r\ncontent-length: 3\r\n\r\nbad",
+        ])
+        .await;
+        
+        let client = HttpClient::with_config(ClientConfig::new().with_cookies(true)).unwrap();
+        let login = LoginRequest::json(format!("http://127.0.0.1:{}/login", port), &()).unwrap();
+        let session = Session::new(client, login)
+            .unwrap()
+            .with_csrf_source(CsrfSource::Header(HeaderName::from_static("x-csrf")));
+        
+        match session.login().await {
+            Err(HttpError::ResponseError { status, body }) => {
+                assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+                assert_eq!(body, "bad");
+            }
+            other => panic!("unexpected login result: {:?}", other),
+        }
+        assert_eq!(session.csrf_token(), None);
+        server.await.unwrap();
+        
+        let login = LoginRequest::json("/login", &()).unwrap();
+        assert!(Session::new(HttpClient::new(), login).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+// src/test_support.rs
+// Ephemeral HTTP server for integration tests
+
+use crate::error::{HttpError, Result};
+use crate::transport::{Mock, MockHandle, MockResponse, MockTransport, RecordedRequest, Transport};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use reqwest::{Method, StatusCode, Url};
+use std::convert::Infallible;
+use std::fmt;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+type Handler = Arc<dyn Fn(&RecordedRequest) -> MockResponse + Send + Sync>;
+
+#[derive(Default)]
+struct Routes {
+    handlers: Mutex<Vec<(Method, String, Handler)>>,
+    stubs: MockTransport,
+    requests: Mutex<Vec<RecordedRequest>>,
+    connections: AtomicUsize,
+}
+
+/// Local HTTP server answering from registered stubs and handlers
+///
+/// The server binds `127.0.0.1` on a random port and runs on the current
+/// tokio runtime until dropped. Handlers are tried first, then stubs in
+/// registration order; anything unmatched gets `404 Not Found`. Every
+/// request is recorded, matched or not.
+pub struct StubServer {
+    addr: SocketAddr,
+    routes: Arc<Routes>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl fmt::Debug for StubServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StubServer").field("addr", &self.addr).finish()
+    }
+}
+
+impl StubServer {
+    /// Start a server on a random local port
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| HttpError::IoError(format!("Failed to bind stub server: {}", e)))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| HttpError::IoError(format!("Failed to bind stub server: {}", e)))?;
+        
+        let routes = Arc::new(Routes::default());
+        let make_service = {
+            let routes = routes.clone();
+            make_service_fn(move |_| {
+                routes.connections.fetch_add(1, Ordering::SeqCst);
+                let routes = routes.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        let routes = routes.clone();
+                        async move { Ok::<_, Infallible>(routes.answer(addr, request).await) }
+                    }))
+                }
+            })
+        };
+        
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)
+            .map_err(|e| HttpError::IoError(format!("Failed to start stub server: {}", e)))?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+        tokio::spawn(server);
+        
+        Ok(Self {
+            addr,
+            routes,
+            shutdown: Some(shutdown),
+        })
+    }
+    
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+    
+    /// Base URL of the server, e.g. `http://127.0.0.1:49152`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+    
+    /// Absolute URL for `path` on the server
+    pub fn url_for(&self, path: &str) -> String {
+        format!("{}/{}", self.url(), path.trim_start_matches('/'))
+    }
+    
+    /// Answer requests matching `mock` with its canned response
+    pub fn stub(&self, mock: Mock) -> MockHandle {
+        self.routes.stubs.expect(mock)
+    }
+    
+    /// Answer `method` requests to `path` by calling `handler`
+    pub fn handle<F>(&self, method: Method, path: impl Into<String>, handler: F)
+    where
+        F: Fn(&RecordedRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        self.routes
+            .handlers
+            .lock()
+            .unwrap()
+            .push((method, path.into(), Arc::new(handler)));
+    }
+    
+    /// Every request received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.routes.requests.lock().unwrap().clone()
+    }
+    
+    /// Requests received for `path`
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.url.path() == path)
+            .collect()
+    }
+    
+    /// Number of connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.routes.connections.load(Ordering::SeqCst)
+    }
+    
+    /// Assert every registered stub was called as expected
+    pub fn verify(&self) {
+        self.routes.stubs.verify();
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl Routes {
+    async fn answer(&self, addr: SocketAddr, request: hyper::Request<Body>) -> hyper::Response<Body> {
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+        let path_and_query = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+        let url = Url::parse(&format!("http://{}{}", addr, path_and_query)).expect("valid request URI");
+        
+        let recorded = RecordedRequest {
+            method: parts.method,
+            url,
+            headers: parts.headers,
+            body: (!body.is_empty()).then(|| body.to_vec()),
+        };
+        self.requests.lock().unwrap().push(recorded.clone());
+        
+        let handler = self
+            .handlers
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(method, path, _)| *method == recorded.method && *path == recorded.url.path())
+            .map(|(_, _, handler)| handler.clone());
+        if let Some(handler) = handler {
+            return handler(&recorded).into_hyper();
+        }
+        
+        match self.stubs.send(recorded.to_request()).await {
+            Ok(response) => {
+                let mut answer = hyper::Response::builder().status(response.status());
+                for (name, value) in response.headers() {
+                    answer = answer.header(name, value);
+                }
+                let body = response.bytes().await.unwrap_or_default();
+                answer.body(Body::from(body)).expect("valid stub response")
+            }
+            Err(e) => MockResponse::new(StatusCode::NOT_FOUND)
+                .with_body(e.to_string())
+                .into_hyper(),
+        }
+    }
+}
+
+/// Raw TCP server for tests that need what `StubServer` can't produce
+///
+/// Each accepted connection is handed to the handler on a thread of its
+/// own, numbered from 0, so tests can drop connections, stall or send
+/// malformed responses. Runs on plain threads, so blocking tests can use
+/// it as well; the listener lives until the test process exits.
+#[cfg(test)]
+pub(crate) struct RawServer {
+    addr: SocketAddr,
+}
+
+#[cfg(test)]
+impl RawServer {
+    pub(crate) fn start<F>(handler: F) -> Self
+    where
+        F: Fn(usize, std::net::TcpStream) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handler = Arc::new(handler);
+        std::thread::spawn(move || {
+            for (n, stream) in listener.incoming().enumerate() {
+                let Ok(stream) = stream else { break };
+                let handler = handler.clone();
+                std::thread::spawn(move || handler(n, stream));
+            }
+        });
+        Self { addr }
+    }
+    
+    /// Answer connection `n` with `responses[n]` after reading its request
+    ///
+    /// Connections beyond the list are closed without an answer.
+    pub(crate) fn replying<R>(responses: &[R]) -> Self
+    where
+        R: AsRef<[u8]> + Clone + Send + Sync + 'static,
+    {
+        let responses = responses.to_vec();
+        Self::start(move |n, mut stream| {
+            use std::io::{Read, Write};
+            
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf);
+            if let Some(response) = responses.get(n) {
+                let _ = stream.write_all(response.as_ref());
+            }
+        })
+    }
+    
+    /// Accept connections and never answer them
+    pub(crate) fn silent() -> Self {
+        Self::start(|_, stream| {
+            use std::io::Read;
+            
+            let _ = (&stream).read_to_end(&mut Vec::new());
+        })
+    }
+    
+    pub(crate) fn port(&self) -> u16 {
+        self.addr.port()
+    }
+    
+    pub(crate) fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::HttpClient;
+    use serde_json::json;
+    
+    #[tokio::test]
+    async fn test_stub_server_serves_and_records() {
+        let server = StubServer::start().await.unwrap();
+        let users = server.stub(
+            Mock::get("/users")
+                .respond_with(MockResponse::json(StatusCode::OK, &json!([{"id": 1}])).unwrap())
+                .times(1),
+        );
+        server.handle(Method::POST, "/echo", |request| {
+            MockResponse::new(StatusCode::CREATED).with_body(request.body.clone().unwrap_or_default())
+        });
+        
+        let client = HttpClient::with_base_url(server.url()).unwrap();
+        let listed: serde_json::Value = client.get_json("/users").await.unwrap();
+        assert_eq!(listed, json!([{"id": 1}]));
+        
+        let echoed: serde_json::Value = client.post_json("/echo", &json!({"name": "ada"})).await.unwrap();
+        assert_eq!(echoed["name"], "ada");
+        
+        let missing = client.get("/missing").await.unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(server.requests_to("/echo")[0].json().unwrap()["name"], "ada");
+        users.assert();
+        server.verify();
+    }
+}
@@ -0,0 +1,591 @@
+// src/tls.rs
+// TLS trust and identity configuration
+
+use crate::error::{HttpError, Result};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// TLS implementation used by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// The platform TLS library (`native-tls` feature)
+    NativeTls,
+    /// rustls (`rustls` feature)
+    Rustls,
+}
+
+impl TlsBackend {
+    /// Check whether this backend was compiled into the crate
+    pub fn is_compiled(self) -> bool {
+        match self {
+            TlsBackend::NativeTls => cfg!(feature = "native-tls"),
+            TlsBackend::Rustls => cfg!(feature = "rustls"),
+        }
+    }
+    
+    /// The backend reqwest uses when none is selected explicitly
+    pub fn compiled_default() -> Self {
+        if cfg!(feature = "native-tls") {
+            TlsBackend::NativeTls
+        } else {
+            TlsBackend::Rustls
+        }
+    }
+    
+    /// Name of the crate feature that enables this backend
+    pub fn feature_name(self) -> &'static str {
+        match self {
+            TlsBackend::NativeTls => "native-tls",
+            TlsBackend::Rustls => "rustls",
+        }
+    }
+}
+
+/// TLS protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    Tls1_0,
+    Tls1_1,
+    Tls1_2,
+    Tls1_3,
+}
+
+impl TlsVersion {
+    /// Convert to the reqwest version type
+    pub fn to_reqwest(self) -> reqwest::tls::Version {
+        match self {
+            TlsVersion::Tls1_0 => reqwest::tls::Version::TLS_1_0,
+            TlsVersion::Tls1_1 => reqwest::tls::Version::TLS_1_1,
+            TlsVersion::Tls1_2 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::Tls1_3 => reqwest::tls::Version::TLS_1_3,
+        }
+    }
+}
+
+/// A certificate added to the client's set of trusted roots
+#[derive(Clone)]
+pub struct RootCertificate {
+    cert: reqwest::Certificate,
+    der: Vec<u8>,
+}
+
+impl RootCertificate {
+    /// Parse one or more certificates from PEM or DER bytes
+    ///
+    /// PEM input may contain a bundle of several certificates; DER input
+    /// always holds exactly one.
+    pub fn parse(pem_or_der: &[u8]) -> Result<Vec<Self>> {
+        let ders = if is_pem(pem_or_der) {
+            pem_certificates(pem_or_der)?
+        } else {
+            vec![pem_or_der.to_vec()]
+        };
+        
+        ders.into_iter().map(Self::from_der).collect()
+    }
+    
+    /// Parse a single DER-encoded certificate
+    pub fn from_der(der: Vec<u8>) -> Result<Self> {
+        // Every DER certificate is an ASN.1 SEQUENCE; rustls doesn't check
+        // this when parsing, so reject obvious garbage up front.
+        if der_element(&der).map(|element| element.tag) != Some(TAG_SEQUENCE) {
+            return Err(HttpError::ConfigError(
+                "Invalid DER certificate: expected an ASN.1 SEQUENCE".to_string(),
+            ));
+        }
+        
+        let cert = reqwest::Certificate::from_der(&der)
+            .map_err(|e| HttpError::ConfigError(format!("Invalid DER certificate: {}", e)))?;
+        
+        Ok(Self { cert, der })
+    }
+    
+    /// Get the DER encoding of the certificate
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+    
+    /// Get the underlying reqwest certificate
+    pub fn as_reqwest(&self) -> &reqwest::Certificate {
+        &self.cert
+    }
+}
+
+impl fmt::Debug for RootCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RootCertificate")
+    }
+}
+
+/// SHA-256 fingerprint of a certificate's SubjectPublicKeyInfo
+///
+/// This is the pin format used by HPKP and most mobile pinning libraries
+/// (`openssl x509 -pubkey | openssl pkey -pubin -outform der | openssl dgst -sha256`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpkiPin([u8; 32]);
+
+impl SpkiPin {
+    /// Parse a base64-encoded pin, optionally prefixed with `sha256/`
+    pub fn from_base64(pin: &str) -> Result<Self> {
+        let encoded = pin.trim();
+        let encoded = encoded.strip_prefix("sha256/").unwrap_or(encoded);
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| HttpError::ConfigError(format!("Invalid SPKI pin '{}': {}", pin, e)))?;
+        
+        let digest: [u8; 32] = bytes.try_into().map_err(|_| {
+            HttpError::ConfigError(format!("Invalid SPKI pin '{}': expected a SHA-256 digest", pin))
+        })?;
+        
+        Ok(Self(digest))
+    }
+    
+    /// Compute the pin of a DER-encoded certificate
+    pub fn from_certificate_der(der: &[u8]) -> Result<Self> {
+        let spki = subject_public_key_info(der).ok_or_else(|| {
+            HttpError::ConfigError("Could not locate SubjectPublicKeyInfo in certificate".to_string())
+        })?;
+        
+        Ok(Self(Sha256::digest(spki).into()))
+    }
+    
+    /// Encode the pin as base64
+    pub fn to_base64(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(self.0)
+    }
+}
+
+impl fmt::Display for SpkiPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sha256/{}", self.to_base64())
+    }
+}
+
+/// A client certificate and private key presented for mutual TLS
+///
+/// Identities are tied to the TLS backend that parsed them.
+#[derive(Clone)]
+pub struct ClientIdentity {
+    identity: reqwest::Identity,
+    backend: TlsBackend,
+}
+
+impl ClientIdentity {
+    /// Load an identity from a DER-encoded PKCS#12 archive
+    #[cfg(feature = "native-tls")]
+    pub fn from_pkcs12_der(der: &[u8], password: &str) -> Result<Self> {
+        reqwest::Identity::from_pkcs12_der(der, password)
+            .map(|identity| Self { identity, backend: TlsBackend::NativeTls })
+            .map_err(|e| HttpError::ConfigError(format!("Invalid PKCS#12 identity: {}", e)))
+    }
+    
+    /// Load an identity from a PEM certificate chain and a PEM PKCS#8 private key
+    #[cfg(feature = "native-tls")]
+    pub fn from_pkcs8_pem(cert_pem: &[u8], key_pem: &[u8]) -> Result<Self> {
+        reqwest::Identity::from_pkcs8_pem(cert_pem, key_pem)
+            .map(|identity| Self { identity, backend: TlsBackend::NativeTls })
+            .map_err(|e| HttpError::ConfigError(format!("Invalid PKCS#8 identity: {}", e)))
+    }
+    
+    /// Load an identity from a PEM buffer holding both the private key and certificate chain
+    #[cfg(feature = "rustls")]
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        reqwest::Identity::from_pem(pem)
+            .map(|identity| Self { identity, backend: TlsBackend::Rustls })
+            .map_err(|e| HttpError::ConfigError(format!("Invalid PEM identity: {}", e)))
+    }
+    
+    /// Get the TLS backend this identity can be used with
+    pub fn backend(&self) -> TlsBackend {
+        self.backend
+    }
+    
+    /// Get the underlying reqwest identity
+    pub fn as_reqwest(&self) -> &reqwest::Identity {
+        &self.identity
+    }
+}
+
+impl fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClientIdentity")
+    }
+}
+
+/// Check whether the bytes look like PEM rather than DER
+pub(crate) fn is_pem(bytes: &[u8]) -> bool {
+    bytes
+        .windows(b"-----BEGIN".len())
+        .any(|window| window == b"-----BEGIN")
+}
+
+/// Extract the DER bodies of all `CERTIFICATE` blocks in a PEM bundle
+fn pem_certificates(pem: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let text = std::str::from_utf8(pem)
+        .map_err(|_| HttpError::ConfigError("Invalid PEM certificate: not UTF-8".to_string()))?;
+    
+    let mut certs = Vec::new();
+    let mut body: Option<String> = None;
+    
+    for line in text.lines().map(str::trim) {
+        match (&mut body, line) {
+            (None, "-----BEGIN CERTIFICATE-----") => body = Some(String::new()),
+            (Some(encoded), "-----END CERTIFICATE-----") => {
+                let der = base64::engine::general_purpose::STANDARD
+                    .decode(encoded.as_bytes())
+                    .map_err(|e| HttpError::ConfigError(format!("Invalid PEM certificate: {}", e)))?;
+                if der.is_empty() {
+                    return Err(HttpError::ConfigError(
+                        "Invalid PEM certificate: empty body".to_string(),
+                    ));
+                }
+                certs.push(der);
+                body = None;
+            }
+            (Some(encoded), line) => encoded.push_str(line),
+            (None, _) => {}
+        }
+    }
+    
+    if certs.is_empty() {
+        return Err(HttpError::ConfigError(
+            "No certificates found in PEM input".to_string(),
+        ));
+    }
+    
+    Ok(certs)
+}
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_EXPLICIT_VERSION: u8 = 0xa0;
+
+/// A single DER type-length-value element
+struct DerElement<'a> {
+    tag: u8,
+    /// The complete encoding, including tag and length
+    raw: &'a [u8],
+    contents: &'a [u8],
+    rest: &'a [u8],
+}
+
+/// Parse the DER element at the start of `input`
+fn der_element(input: &[u8]) -> Option<DerElement<'_>> {
+    let (&tag, after_tag) = input.split_first()?;
+    let (&first_len, after_len) = after_tag.split_first()?;
+    
+    let (len, after_len) = if first_len & 0x80 == 0 {
+        (first_len as usize, after_len)
+    } else {
+        let num_bytes = (first_len & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > 4 || after_len.len() < num_bytes {
+            return None;
+        }
+        let len = after_len[..num_bytes]
+            .iter()
+            .fold(0usize, |acc, &byte| (acc << 8) | byte as usize);
+        (len, &after_len[num_bytes..])
+    };
+    
+    if after_len.len() < len {
+        return None;
+    }
+    
+    let header_len = input.len() - after_len.len();
+    Some(DerElement {
+        tag,
+        raw: &input[..header_len + len],
+        contents: &after_len[..len],
+        rest: &after_len[len..],
+    })
+}
+
+/// Locate the DER-encoded SubjectPublicKeyInfo of an X.509 certificate
+fn subject_public_key_info(cert_der: &[u8]) -> Option<&[u8]> {
+    let cert = der_element(cert_der).filter(|element| element.tag == TAG_SEQUENCE)?;
+    let tbs = der_element(cert.contents).filter(|element| element.tag == TAG_SEQUENCE)?;
+    
+    let mut fields = tbs.contents;
+    let first = der_element(fields)?;
+    if first.tag == TAG_EXPLICIT_VERSION {
+        fields = first.rest;
+    }
+    
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        fields = der_element(fields)?.rest;
+    }
+    
+    der_element(fields)
+        .filter(|element| element.tag == TAG_SEQUENCE)
+        .map(|element| element.raw)
+}
+
+/// rustls configuration enforcing SPKI pins on top of normal chain validation
+#[cfg(feature = "rustls")]
+pub(crate) mod pinning {
+    use super::{HttpError, Result, RootCertificate, SpkiPin, TlsVersion};
+    use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+    use rustls::{Certificate, ServerName};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::SystemTime;
+    
+    /// Verifier that requires the server's own certificate to carry a pinned key
+    ///
+    /// Only the end-entity certificate is checked: the rest of what the peer
+    /// sends isn't necessarily the chain webpki validated, so a pinned but
+    /// public intermediate could simply be appended to any other chain.
+    struct PinningVerifier {
+        inner: WebPkiVerifier,
+        accept_invalid_certs: bool,
+        pins: HashMap<String, Vec<SpkiPin>>,
+    }
+    
+    impl ServerCertVerifier for PinningVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &Certificate,
+            intermediates: &[Certificate],
+            server_name: &ServerName,
+            scts: &mut dyn Iterator<Item = &[u8]>,
+            ocsp_response: &[u8],
+            now: SystemTime,
+        ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+            if !self.accept_invalid_certs {
+                self.inner.verify_server_cert(
+                    end_entity,
+                    intermediates,
+                    server_name,
+                    scts,
+                    ocsp_response,
+                    now,
+                )?;
+            }
+            
+            let host = match server_name {
+                ServerName::DnsName(name) => name.as_ref().to_ascii_lowercase(),
+                ServerName::IpAddress(ip) => ip.to_string(),
+                _ => return Ok(ServerCertVerified::assertion()),
+            };
+            
+            if let Some(pins) = self.pins.get(&host) {
+                let matched = SpkiPin::from_certificate_der(&end_entity.0).is_ok_and(|pin| pins.contains(&pin));
+                if !matched {
+                    return Err(rustls::Error::General(format!(
+                        "certificate pin mismatch for {}",
+                        host
+                    )));
+                }
+            }
+            
+            Ok(ServerCertVerified::assertion())
+        }
+    }
+    
+    /// Build a rustls client config that enforces the given pins
+    pub(crate) fn client_config(
+        root_certificates: &[RootCertificate],
+        min_version: Option<TlsVersion>,
+        max_version: Option<TlsVersion>,
+        accept_invalid_certs: bool,
+        pins: &HashMap<String, Vec<SpkiPin>>,
+    ) -> Result<rustls::ClientConfig> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        }));
+        
+        for cert in root_certificates {
+            roots
+                .add(&Certificate(cert.der().to_vec()))
+                .map_err(|e| HttpError::ConfigError(format!("Invalid root certificate: {}", e)))?;
+        }
+        
+        let versions: Vec<_> = rustls::ALL_VERSIONS
+            .iter()
+            .copied()
+            .filter(|supported| {
+                let version = match supported.version {
+                    rustls::ProtocolVersion::TLSv1_2 => TlsVersion::Tls1_2,
+                    rustls::ProtocolVersion::TLSv1_3 => TlsVersion::Tls1_3,
+                    _ => return false,
+                };
+                min_version.is_none_or(|min| version >= min)
+                    && max_version.is_none_or(|max| version <= max)
+            })
+            .collect();
+        
+        let verifier = PinningVerifier {
+            inner: WebPkiVerifier::new(roots.clone(), None),
+            accept_invalid_certs,
+            pins: pins.clone(),
+        };
+        
+        let mut config = rustls::ClientConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(&versions)
+            .map_err(|e| HttpError::ConfigError(format!("Invalid TLS versions: {}", e)))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        
+        config.dangerous().set_certificate_verifier(Arc::new(verifier));
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_is_pem() {
+        assert!(is_pem(b"-----BEGIN CERTIFICATE-----\nabc\n-----END CERTIFICATE-----\n"));
+        assert!(!is_pem(&[0x30, 0x82, 0x01, 0x0a]));
+    }
+    
+    #[test]
+    fn test_invalid_certificate_rejected() {
+        assert!(matches!(
+            RootCertificate::parse(b"definitely not a certificate"),
+            Err(HttpError::ConfigError(_))
+        ));
+        assert!(RootCertificate::parse(b"-----BEGIN CERTIFICATE-----\n-----END CERTIFICATE-----\n").is_err());
+    }
+    
+    const TEST_CERT_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBfTCCASOgAwIBAgIUA9ySE0gVyADEcmfSSzQwmlBAN7YwCgYIKoZIzj0EAwIw
+EzERMA8GA1UEAwwIcGluLnRlc3QwIBcNMjYxMDE1MTc0MDM1WhgPMjEyNjA5MjEx
+NzQwMzVaMBMxETAPBgNVBAMMCHBpbi50ZXN0MFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAEdwnu38DGx2p+Omws+fbh5iXDZ3Yx5riQevHB0Dwrd+T/o/jPSFidquKM
+S0mWilrMyHp+MgaNdl7Ks75upTu/paNTMFEwHQYDVR0OBBYEFNtRXnzXIsTgaN4s
+a1oK49L/OEU9MB8GA1UdIwQYMBaAFNtRXnzXIsTgaN4sa1oK49L/OEU9MA8GA1Ud
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgLtrysCT7wvPdlsob8mR4qS7H
+dd4QtHQXRW1Q+5GBwwECIQDgH73s08YCUt312KuMjCVCA2vcBBM5bFc87/1OJ+NG
+Sg==
+-----END CERTIFICATE-----
+";
+    
+    // Computed with openssl from TEST_CERT_PEM's public key
+    const TEST_CERT_PIN: &str = "hnwIIEp59uKR5zxfwYo547L3L09BwvsoQWTZtXdkGpw=";
+    
+    #[test]
+    fn test_parse_pem_certificate() {
+        let certs = RootCertificate::parse(TEST_CERT_PEM.as_bytes()).unwrap();
+        assert_eq!(certs.len(), 1);
+        
+        let der = certs[0].der().to_vec();
+        assert_eq!(RootCertificate::parse(&der).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_spki_pin_from_certificate() {
+        let certs = RootCertificate::parse(TEST_CERT_PEM.as_bytes()).unwrap();
+        let pin = SpkiPin::from_certificate_der(certs[0].der()).unwrap();
+        
+        assert_eq!(pin.to_base64(), TEST_CERT_PIN);
+        assert_eq!(pin, SpkiPin::from_base64(&format!("sha256/{}", TEST_CERT_PIN)).unwrap());
+        assert_eq!(pin.to_string(), format!("sha256/{}", TEST_CERT_PIN));
+    }
+    
+    #[test]
+    fn test_invalid_spki_pin_rejected() {
+        assert!(SpkiPin::from_base64("not base64!").is_err());
+        assert!(SpkiPin::from_base64("c2hvcnQ=").is_err());
+        assert!(SpkiPin::from_certificate_der(&[0x30, 0x00]).is_err());
+    }
+    
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn test_pin_only_matches_end_entity() {
+        use crate::client::{ClientConfig, HttpClient};
+        use crate::test_support::RawServer;
+        use base64::Engine;
+        use std::io::{Read, Write};
+        
+        // a different self-signed pin.test certificate and its PKCS#8 key
+        const LEAF_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBfDCCASOgAwIBAgIUTwW0MEIAFl/nHvtTtQIDPthe7yAwCgYIKoZIzj0EAwIw
+EzERMA8GA1UEAwwIcGluLnRlc3QwIBcNMjYxMDE2MDY1NzA4WhgPMjEyNjA5MjIw
+NjU3MDhaMBMxETAPBgNVBAMMCHBpbi50ZXN0MFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAEwjREiDLZ3MjpxYi8rZWtFDJ8YLOGSYwU1PzfvgxcYXjBjqyC6XQkipQP
+2RDpMF6JOg5/yKtAFowumwUFiqXKt6NTMFEwHQYDVR0OBBYEFNDh6jVxYgDhz4oR
+MoVg/uL+1Ec/MB8GA1UdIwQYMBaAFNDh6jVxYgDhz4oRMoVg/uL+1Ec/MA8GA1Ud
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDRwAwRAIgKCsMU0dvwyhDB5VFlRHJKjf4
+XVtsKC3FNyNU6Bwn7J8CIER4RoKGi7xbTwXc2Yst071rFZs84E84kWG9TsZXUrP2
+-----END CERTIFICATE-----
+";
+        const LEAF_KEY: &str = "MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgDsQhubZ/pDXjKcdg\
+            6gQJ16/+whJ5/YYT/z7fDbyZTEahRANCAATCNESIMtncyOnFiLytla0UMnxgs4ZJ\
+            jBTU/N++DFxheMGOrILpdCSKlA/ZEOkwXok6Dn/Iq0AWjC6bBQWKpcq3";
+        const LEAF_PIN: &str = "z79JAuLtNp8WoKyX4D6J6QRlmXL5wqX2NVOfEQyO/FM=";
+        
+        // the pinned certificate only rides along as an extra intermediate
+        let chain: Vec<_> = [LEAF_PEM, TEST_CERT_PEM]
+            .iter()
+            .map(|pem| rustls::Certificate(RootCertificate::parse(pem.as_bytes()).unwrap()[0].der().to_vec()))
+            .collect();
+        let key = rustls::PrivateKey(base64::engine::general_purpose::STANDARD.decode(LEAF_KEY).unwrap());
+        let tls = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .unwrap();
+        let tls = std::sync::Arc::new(tls);
+        let server = RawServer::start(move |_, stream| {
+            let connection = rustls::ServerConnection::new(tls.clone()).unwrap();
+            let mut stream = rustls::StreamOwned::new(connection, stream);
+            if stream.read(&mut [0; 4096]).is_ok() {
+                let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+                stream.conn.send_close_notify();
+                let _ = stream.flush();
+            }
+        });
+        let url = format!("https://pin.test:{}/", server.port());
+        
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        for (pin, pass) in [(TEST_CERT_PIN, false), (LEAF_PIN, true)] {
+            let config = ClientConfig::new()
+                .with_resolve("pin.test", "127.0.0.1")
+                .unwrap()
+                .danger_accept_invalid_certs(true)
+                .with_spki_pin("pin.test", pin)
+                .unwrap();
+            let client = HttpClient::with_config(config).unwrap();
+            let result = runtime.block_on(client.get(&url));
+            assert_eq!(result.is_ok(), pass, "pin {} gave {:?}", pin, result);
+        }
+    }
+    
+    #[test]
+    fn test_tls_version_ordering() {
+        assert!(TlsVersion::Tls1_0 < TlsVersion::Tls1_2);
+        assert!(TlsVersion::Tls1_3 > TlsVersion::Tls1_2);
+        assert_eq!(TlsVersion::Tls1_2.to_reqwest(), reqwest::tls::Version::TLS_1_2);
+    }
+    
+    #[test]
+    fn test_compiled_default_backend_is_compiled() {
+        assert!(TlsBackend::compiled_default().is_compiled());
+    }
+    
+    #[cfg(feature = "native-tls")]
+    #[test]
+    fn test_invalid_identity_rejected() {
+        assert!(matches!(
+            ClientIdentity::from_pkcs12_der(b"not pkcs12", "password"),
+            Err(HttpError::ConfigError(_))
+        ));
+        assert!(matches!(
+            ClientIdentity::from_pkcs8_pem(b"not a cert", b"not a key"),
+            Err(HttpError::ConfigError(_))
+        ));
+    }
+}
@@ -0,0 +1,463 @@
+// src/transport.rs
+// Pluggable request transport and an in-memory mock for tests
+
+use crate::error::{HttpError, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Method, Request, Response, ResponseBuilderExt, StatusCode, Url};
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Sends requests on behalf of an `HttpClient`
+///
+/// The default transport is the client's reqwest client. Swap it with
+/// `HttpClient::with_transport`, e.g. for a `MockTransport` in tests.
+#[async_trait::async_trait]
+pub trait Transport: Send + Sync + fmt::Debug {
+    async fn send(&self, request: Request) -> Result<Response>;
+    
+    /// Whether requests may reach the real network, which offline mode forbids
+    ///
+    /// Assumed unless a transport says otherwise, so offline mode can't be
+    /// bypassed by a transport that forgets to override this.
+    fn uses_network(&self) -> bool {
+        true
+    }
+}
+
+/// A request as seen by a `MockTransport`
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+}
+
+impl RecordedRequest {
+    pub(crate) fn from_request(request: &Request) -> Self {
+        Self {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            body: request.body().and_then(|body| body.as_bytes()).map(<[u8]>::to_vec),
+        }
+    }
+    
+    /// Rebuild the request so it can be sent again
+    #[cfg(any(test, feature = "test-support"))]
+    pub(crate) fn to_request(&self) -> Request {
+        let mut request = Request::new(self.method.clone(), self.url.clone());
+        *request.headers_mut() = self.headers.clone();
+        *request.body_mut() = self.body.clone().map(Into::into);
+        request
+    }
+    
+    /// Parse the body as JSON
+    pub fn json(&self) -> Result<serde_json::Value> {
+        let body = self.body.as_deref().unwrap_or_default();
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+/// Canned response returned by a `Mock`
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+    templated: bool,
+}
+
+impl MockResponse {
+    /// Create an empty response with `status`
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            templated: false,
+        }
+    }
+    
+    /// Create a response with a JSON body
+    pub fn json<T: Serialize>(status: StatusCode, body: &T) -> Result<Self> {
+        let body = serde_json::to_vec(body)?;
+        Ok(Self::new(status)
+            .with_header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .with_body(body))
+    }
+    
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+    
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+    
+    /// Fill `{{name}}` placeholders in the body from path parameters
+    ///
+    /// Parameters come from `{name}` segments in the mock's path, e.g. a
+    /// mock for `/users/{id}` answers `/users/7` with `{{id}}` set to `7`.
+    pub fn templated(mut self) -> Self {
+        self.templated = true;
+        self
+    }
+    
+    fn to_response(&self, url: &Url, params: &[(String, String)]) -> Response {
+        let body = if self.templated {
+            let body = String::from_utf8_lossy(&self.body);
+            render_template(&body, |name| {
+                params.iter().find(|(param, _)| param == name).map(|(_, value)| value.as_str())
+            })
+            .into_bytes()
+        } else {
+            self.body.clone()
+        };
+        build_response(url, self.status, self.headers.clone(), body)
+    }
+    
+    #[cfg(any(test, feature = "test-support"))]
+    pub(crate) fn into_hyper(self) -> hyper::Response<hyper::Body> {
+        let mut response = hyper::Response::new(hyper::Body::from(self.body));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers;
+        response
+    }
+}
+
+/// Build a response for `url` that was never sent over the network
+pub(crate) fn build_response(url: &Url, status: StatusCode, headers: HeaderMap, body: Vec<u8>) -> Response {
+    let mut builder = hyper::Response::builder().status(status).url(url.clone());
+    if let Some(response_headers) = builder.headers_mut() {
+        *response_headers = headers;
+    }
+    Response::from(builder.body(body).expect("valid response parts"))
+}
+
+/// Replace `{{name}}` placeholders with values from `lookup`
+///
+/// Placeholders without a value are left as they are.
+pub(crate) fn render_template<'a>(template: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+        rendered.push_str(&rest[..start]);
+        match lookup(rest[start + 2..end].trim()) {
+            Some(value) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Match `path` against a pattern, returning the `{name}` segments it binds
+///
+/// A trailing `*` segment matches any remainder of the path.
+fn match_path(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+    let mut params = Vec::new();
+    let mut segments = path.split('/');
+    for expected in pattern.split('/') {
+        if expected == "*" {
+            return Some(params);
+        }
+        let segment = segments.next()?;
+        match expected.strip_prefix('{').and_then(|name| name.strip_suffix('}')) {
+            Some(name) if !segment.is_empty() => params.push((name.to_string(), segment.to_string())),
+            Some(_) => return None,
+            None if expected == segment => {}
+            None => return None,
+        }
+    }
+    segments.next().is_none().then_some(params)
+}
+
+/// An expected request and the response to send back
+///
+/// A mock matches on method and path, and optionally on query, headers and
+/// body. Paths may contain `{name}` segments matching any single segment
+/// and end in `*` to match any remainder. Without `respond_with` it answers
+/// `200 OK` with an empty body.
+#[derive(Debug, Clone)]
+pub struct Mock {
+    method: Option<Method>,
+    path: String,
+    query: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: Option<Vec<u8>>,
+    response: MockResponse,
+    expected_calls: Option<usize>,
+}
+
+impl Mock {
+    /// Expect a `method` request to `path`
+    pub fn new(method: Method, path: impl Into<String>) -> Self {
+        Self {
+            method: Some(method),
+            ..Self::any(path)
+        }
+    }
+    
+    /// Expect a request to `path` with any method
+    pub fn any(path: impl Into<String>) -> Self {
+        Self {
+            method: None,
+            path: path.into(),
+            query: None,
+            headers: Vec::new(),
+            body: None,
+            response: MockResponse::new(StatusCode::OK),
+            expected_calls: None,
+        }
+    }
+    
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new(Method::GET, path)
+    }
+    
+    pub fn post(path: impl Into<String>) -> Self {
+        Self::new(Method::POST, path)
+    }
+    
+    pub fn put(path: impl Into<String>) -> Self {
+        Self::new(Method::PUT, path)
+    }
+    
+    pub fn delete(path: impl Into<String>) -> Self {
+        Self::new(Method::DELETE, path)
+    }
+    
+    /// Only match this exact query string
+    pub fn with_query(mut self, query: impl Into<String>) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+    
+    /// Only match requests carrying this header value
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+    
+    /// Only match requests with exactly this body
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+    
+    /// Only match requests whose body is this value as JSON
+    pub fn with_json_body<T: Serialize>(self, body: &T) -> Result<Self> {
+        Ok(self.with_body(serde_json::to_vec(body)?))
+    }
+    
+    pub fn respond_with(mut self, response: MockResponse) -> Self {
+        self.response = response;
+        self
+    }
+    
+    /// Expect exactly `calls` matching requests
+    pub fn times(mut self, calls: usize) -> Self {
+        self.expected_calls = Some(calls);
+        self
+    }
+    
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        if self.method.as_ref().is_some_and(|method| method != request.method) {
+            return false;
+        }
+        if match_path(&self.path, request.url.path()).is_none() {
+            return false;
+        }
+        if self.query.as_ref().is_some_and(|query| request.url.query() != Some(query.as_str())) {
+            return false;
+        }
+        if !self.headers.iter().all(|(name, value)| request.headers.get_all(name).iter().any(|v| v == value)) {
+            return false;
+        }
+        
+        match &self.body {
+            Some(body) if body_is_json(body) => {
+                // compare JSON structurally so key order doesn't matter
+                let expected: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+                request.json().is_ok_and(|actual| actual == expected)
+            }
+            Some(body) => request.body.as_ref() == Some(body),
+            None => true,
+        }
+    }
+    
+    fn describe(&self) -> String {
+        let method = self.method.as_ref().map_or("*", Method::as_str);
+        format!("{} {}", method, self.path)
+    }
+}
+
+fn body_is_json(body: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(body).is_ok()
+}
+
+#[derive(Debug)]
+struct Registered {
+    mock: Mock,
+    calls: AtomicUsize,
+}
+
+/// Handle to a registered mock for checking its calls
+#[derive(Debug, Clone)]
+pub struct MockHandle {
+    registered: Arc<Registered>,
+}
+
+impl MockHandle {
+    /// Number of requests the mock has answered
+    pub fn calls(&self) -> usize {
+        self.registered.calls.load(Ordering::SeqCst)
+    }
+    
+    /// Panic unless the mock was called as often as expected (at least once by default)
+    pub fn assert(&self) {
+        let calls = self.calls();
+        let mock = &self.registered.mock;
+        match mock.expected_calls {
+            Some(expected) => assert_eq!(
+                calls, expected,
+                "mock {} expected {} calls, got {}",
+                mock.describe(), expected, calls
+            ),
+            None => assert!(calls > 0, "mock {} was never called", mock.describe()),
+        }
+    }
+}
+
+/// In-memory transport answering requests from programmed mocks
+///
+/// Mocks are tried in registration order and the first match answers.
+/// Unmatched requests fail with a `ConfigError`. Clones share mocks and
+/// recorded requests.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    mocks: Arc<Mutex<Vec<Arc<Registered>>>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Register a mock
+    pub fn expect(&self, mock: Mock) -> MockHandle {
+        let registered = Arc::new(Registered {
+            mock,
+            calls: AtomicUsize::new(0),
+        });
+        self.mocks.lock().unwrap().push(registered.clone());
+        MockHandle { registered }
+    }
+    
+    /// Every request received so far, matched or not
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+    
+    /// Assert every registered mock was called as expected
+    pub fn verify(&self) {
+        for registered in self.mocks.lock().unwrap().iter() {
+            MockHandle { registered: registered.clone() }.assert();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for MockTransport {
+    async fn send(&self, request: Request) -> Result<Response> {
+        let recorded = RecordedRequest::from_request(&request);
+        self.requests.lock().unwrap().push(recorded.clone());
+        
+        let mocks = self.mocks.lock().unwrap();
+        let registered = mocks
+            .iter()
+            .find(|registered| registered.mock.matches(&recorded))
+            .ok_or_else(|| {
+                HttpError::ConfigError(format!("No mock matches {} {}", recorded.method, recorded.url))
+            })?;
+        
+        registered.calls.fetch_add(1, Ordering::SeqCst);
+        let params = match_path(&registered.mock.path, recorded.url.path()).unwrap_or_default();
+        Ok(registered.mock.response.to_response(&recorded.url, &params))
+    }
+    
+    fn uses_network(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+impl MockTransport {
+    /// A client for `http://api.test` that sends through this transport
+    pub(crate) fn client(&self) -> crate::client::HttpClient {
+        crate::client::HttpClient::with_base_url("http://api.test").unwrap().with_transport(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[tokio::test]
+    async fn test_mock_transport_matches_and_counts() {
+        let transport = MockTransport::new();
+        let users = transport.expect(
+            Mock::get("/users")
+                .with_query("page=2")
+                .respond_with(MockResponse::json(StatusCode::OK, &json!([{"id": 1}])).unwrap())
+                .times(1),
+        );
+        let create = transport.expect(
+            Mock::post("/users")
+                .with_json_body(&json!({"name": "ada", "admin": false}))
+                .unwrap()
+                .respond_with(MockResponse::new(StatusCode::CREATED)),
+        );
+        
+        let client = transport.client();
+        
+        let listed: serde_json::Value = client.get_json("/users?page=2").await.unwrap();
+        assert_eq!(listed, json!([{"id": 1}]));
+        
+        let request = client
+            .request(Method::POST, "/users")
+            .unwrap()
+            .json(&json!({"admin": false, "name": "ada"}))
+            .build()
+            .unwrap();
+        let response = client.execute(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        
+        assert!(client.get("/missing").await.is_err());
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(transport.requests()[1].json().unwrap()["name"], "ada");
+        
+        users.assert();
+        create.assert();
+        transport.verify();
+    }
+    
+    #[test]
+    #[should_panic(expected = "expected 2 calls")]
+    fn test_mock_handle_assert_fails_on_wrong_count() {
+        let transport = MockTransport::new();
+        transport.expect(Mock::get("/").times(2)).assert();
+    }
+}
@@ -0,0 +1,139 @@
+// src/upload.rs
+// Streaming large files as request bodies
+
+use crate::content_type::guess_content_type;
+use crate::error::{HttpError, Result};
+use hyper::body::Bytes;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+/// A file sent as a request body, read one chunk at a time
+///
+/// At most one chunk is buffered at once, so memory stays flat no matter
+/// how large the file is. The body is sent with a `Content-Length` but
+/// can't be replayed, so such requests are never retried.
+#[derive(Debug, Clone)]
+pub struct ChunkedFile {
+    path: PathBuf,
+    len: u64,
+    content_type: String,
+    chunk_size: usize,
+}
+
+impl ChunkedFile {
+    pub const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
+    
+    /// Prepare `path` for upload, guessing its content type
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let io_error = |e: std::io::Error| HttpError::IoError(format!("Failed to open '{}': {}", path.display(), e));
+        
+        let mut file = std::fs::File::open(path).map_err(io_error)?;
+        let len = file.metadata().map_err(io_error)?.len();
+        let mut head = Vec::with_capacity(16);
+        file.by_ref().take(16).read_to_end(&mut head).map_err(io_error)?;
+        let file_name = path.file_name().and_then(|name| name.to_str());
+        
+        Ok(Self {
+            path: path.to_path_buf(),
+            len,
+            content_type: guess_content_type(file_name, &head),
+            chunk_size: Self::DEFAULT_CHUNK_SIZE,
+        })
+    }
+    
+    /// Read and send `chunk_size` bytes at a time
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+    
+    /// Override the guessed content type
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+    
+    /// Size of the file when it was opened
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+    
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+    
+    /// Body for the async client
+    ///
+    /// Chunks are read by a background task, which must run inside a tokio
+    /// runtime. Read errors abort the request.
+    pub fn into_body(self) -> reqwest::Body {
+        let (mut sender, body) = hyper::Body::channel();
+        tokio::spawn(async move {
+            let mut file = match tokio::fs::File::open(&self.path).await {
+                Ok(file) => file,
+                Err(e) => {
+                    log::warn!("Failed to open '{}' for upload: {}", self.path.display(), e);
+                    sender.abort();
+                    return;
+                }
+            };
+            loop {
+                let mut chunk = Vec::with_capacity(self.chunk_size);
+                match (&mut file).take(self.chunk_size as u64).read_to_end(&mut chunk).await {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        if sender.send_data(Bytes::from(chunk)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to read '{}' for upload: {}", self.path.display(), e);
+                        sender.abort();
+                        break;
+                    }
+                }
+            }
+        });
+        body.into()
+    }
+    
+    /// Body for the blocking client
+    #[cfg(feature = "blocking")]
+    pub fn into_blocking_body(self) -> Result<reqwest::blocking::Body> {
+        let file = std::fs::File::open(&self.path)
+            .map_err(|e| HttpError::IoError(format!("Failed to open '{}': {}", self.path.display(), e)))?;
+        let reader = std::io::BufReader::with_capacity(self.chunk_size, file);
+        Ok(reqwest::blocking::Body::sized(reader, self.len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_chunked_file_metadata() {
+        let dir = std::env::temp_dir().join(format!("rusty-upload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scan.bin");
+        std::fs::write(&path, b"%PDF-1.7 rest of the document").unwrap();
+        
+        let file = ChunkedFile::open(&path).unwrap().with_chunk_size(0);
+        assert_eq!(file.len(), 29);
+        assert_eq!(file.content_type(), "application/pdf");
+        assert_eq!(file.chunk_size(), 1);
+        assert!(ChunkedFile::open(dir.join("missing.bin")).is_err());
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,436 @@
+// src/url_template.rs
+// URI template expansion (RFC 6570)
+
+use crate::error::{HttpError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// A parsed URI template such as `/users/{id}/repos{?page,per_page}`
+///
+/// Supports RFC 6570 up to level 4: simple `{var}`, reserved `{+var}`,
+/// fragment `{#var}`, label `{.var}`, path `{/var}`, path-style `{;var}`
+/// and query `{?var}` / `{&var}` expressions, with the `:n` prefix and `*`
+/// explode modifiers. Values are percent-encoded for their position, so
+/// user input can't add path segments or query parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Expression(Operator, Vec<VarSpec>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VarSpec {
+    name: String,
+    prefix: Option<usize>,
+    explode: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Simple,
+    Reserved,
+    Fragment,
+    Label,
+    Path,
+    PathParam,
+    Query,
+    QueryContinuation,
+}
+
+impl Operator {
+    fn parse(c: char) -> Option<Self> {
+        Some(match c {
+            '+' => Self::Reserved,
+            '#' => Self::Fragment,
+            '.' => Self::Label,
+            '/' => Self::Path,
+            ';' => Self::PathParam,
+            '?' => Self::Query,
+            '&' => Self::QueryContinuation,
+            _ => return None,
+        })
+    }
+    
+    fn first(self) -> &'static str {
+        match self {
+            Self::Simple | Self::Reserved => "",
+            Self::Fragment => "#",
+            Self::Label => ".",
+            Self::Path => "/",
+            Self::PathParam => ";",
+            Self::Query => "?",
+            Self::QueryContinuation => "&",
+        }
+    }
+    
+    fn separator(self) -> &'static str {
+        match self {
+            Self::Simple | Self::Reserved | Self::Fragment => ",",
+            Self::Label => ".",
+            Self::Path => "/",
+            Self::PathParam => ";",
+            Self::Query | Self::QueryContinuation => "&",
+        }
+    }
+    
+    fn named(self) -> bool {
+        matches!(self, Self::PathParam | Self::Query | Self::QueryContinuation)
+    }
+    
+    fn if_empty(self) -> &'static str {
+        match self {
+            Self::Query | Self::QueryContinuation => "=",
+            _ => "",
+        }
+    }
+    
+    fn allow_reserved(self) -> bool {
+        matches!(self, Self::Reserved | Self::Fragment)
+    }
+}
+
+impl UrlTemplate {
+    /// Parse a template, failing with `UrlError` on malformed expressions
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |reason: String| HttpError::UrlError(format!("Invalid URL template '{}': {}", template, reason));
+        let mut parts = Vec::new();
+        let mut rest = template;
+        
+        while let Some(start) = rest.find('{') {
+            if rest[..start].contains('}') {
+                return Err(invalid("unexpected '}'".to_string()));
+            }
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| invalid("unclosed expression".to_string()))?;
+            let mut expression = &rest[start + 1..end];
+            
+            let operator = match expression.chars().next().and_then(Operator::parse) {
+                Some(operator) => {
+                    expression = &expression[1..];
+                    operator
+                }
+                None => Operator::Simple,
+            };
+            let vars = expression
+                .split(',')
+                .map(|spec| parse_var_spec(spec).ok_or_else(|| invalid(format!("bad variable '{}'", spec))))
+                .collect::<Result<Vec<_>>>()?;
+            parts.push(Part::Expression(operator, vars));
+            rest = &rest[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(invalid("unexpected '}'".to_string()));
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+        })
+    }
+    
+    /// Names of the variables used by the template, in order
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for part in &self.parts {
+            if let Part::Expression(_, vars) = part {
+                for var in vars {
+                    if !names.contains(&var.name.as_str()) {
+                        names.push(var.name.as_str());
+                    }
+                }
+            }
+        }
+        names
+    }
+    
+    /// Names of the variables outside `{?...}` and `{&...}` expressions
+    ///
+    /// Leaving one of these out changes the shape of the path rather than
+    /// just dropping an optional query parameter.
+    pub fn path_variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Expression(Operator::Query | Operator::QueryContinuation, _) | Part::Literal(_) => {}
+                Part::Expression(_, vars) => {
+                    for var in vars {
+                        if !names.contains(&var.name.as_str()) {
+                            names.push(var.name.as_str());
+                        }
+                    }
+                }
+            }
+        }
+        names
+    }
+    
+    /// Expand the template with the fields of `vars`
+    ///
+    /// `vars` must serialize to an object, such as a struct, a map or a
+    /// `json!` object. Strings, numbers and bools are single values,
+    /// arrays are lists and objects are key/value pairs. Missing and null
+    /// variables, and empty lists and objects, are left out along with
+    /// their operator prefix.
+    pub fn expand<T: Serialize>(&self, vars: &T) -> Result<String> {
+        let vars = match serde_json::to_value(vars).map_err(|e| HttpError::SerializationError(e.to_string()))? {
+            Value::Object(vars) => vars,
+            Value::Null => Default::default(),
+            other => {
+                return Err(HttpError::SerializationError(format!(
+                    "URL template variables must be an object, got {}",
+                    other
+                )))
+            }
+        };
+        
+        let mut url = String::with_capacity(self.template.len());
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => encode_into(&mut url, literal, true),
+                Part::Expression(operator, specs) => {
+                    let mut first = true;
+                    for spec in specs {
+                        let value = match vars.get(&spec.name) {
+                            Some(value) if is_defined(value) => value,
+                            _ => continue,
+                        };
+                        url.push_str(if first { operator.first() } else { operator.separator() });
+                        first = false;
+                        self.expand_var(&mut url, *operator, spec, value)?;
+                    }
+                }
+            }
+        }
+        Ok(url)
+    }
+    
+    fn expand_var(&self, url: &mut String, operator: Operator, spec: &VarSpec, value: &Value) -> Result<()> {
+        let reserved = operator.allow_reserved();
+        let named = |url: &mut String, name: &str, empty: bool| {
+            url.push_str(name);
+            url.push_str(if empty { operator.if_empty() } else { "=" });
+        };
+        
+        match value {
+            Value::Array(_) | Value::Object(_) if spec.prefix.is_some() => Err(HttpError::SerializationError(format!(
+                "Prefix modifier on '{}' in URL template '{}' needs a single value",
+                spec.name, self.template
+            ))),
+            Value::Array(items) if spec.explode => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        url.push_str(operator.separator());
+                    }
+                    let item = scalar(item);
+                    if operator.named() {
+                        named(url, &spec.name, item.is_empty());
+                    }
+                    encode_into(url, &item, reserved);
+                }
+                Ok(())
+            }
+            Value::Object(pairs) if spec.explode => {
+                for (i, (key, item)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        url.push_str(operator.separator());
+                    }
+                    let item = scalar(item);
+                    encode_into(url, key, reserved);
+                    if operator.named() && item.is_empty() {
+                        url.push_str(operator.if_empty());
+                    } else {
+                        url.push('=');
+                    }
+                    encode_into(url, &item, reserved);
+                }
+                Ok(())
+            }
+            Value::Array(items) => {
+                if operator.named() {
+                    named(url, &spec.name, false);
+                }
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        url.push(',');
+                    }
+                    encode_into(url, &scalar(item), reserved);
+                }
+                Ok(())
+            }
+            Value::Object(pairs) => {
+                if operator.named() {
+                    named(url, &spec.name, false);
+                }
+                for (i, (key, item)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        url.push(',');
+                    }
+                    encode_into(url, key, reserved);
+                    url.push(',');
+                    encode_into(url, &scalar(item), reserved);
+                }
+                Ok(())
+            }
+            _ => {
+                let mut value = scalar(value);
+                if let Some(prefix) = spec.prefix {
+                    value = value.chars().take(prefix).collect();
+                }
+                if operator.named() {
+                    named(url, &spec.name, value.is_empty());
+                }
+                encode_into(url, &value, reserved);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for UrlTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// Parse `name`, `name*` or `name:3`
+fn parse_var_spec(spec: &str) -> Option<VarSpec> {
+    let (name, prefix, explode) = if let Some(name) = spec.strip_suffix('*') {
+        (name, None, true)
+    } else if let Some((name, prefix)) = spec.split_once(':') {
+        let prefix: usize = prefix.parse().ok().filter(|prefix| (1..10_000).contains(prefix))?;
+        (name, Some(prefix), false)
+    } else {
+        (spec, None, false)
+    };
+    
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '%');
+    valid.then(|| VarSpec {
+        name: name.to_string(),
+        prefix,
+        explode,
+    })
+}
+
+fn is_defined(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(pairs) => !pairs.is_empty(),
+        _ => true,
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Percent-encode `value`, keeping reserved characters and existing
+/// escapes when `reserved` is set
+fn encode_into(url: &mut String, value: &str, reserved: bool) {
+    const RESERVED: &str = ":/?#[]@!$&'()*+,;=";
+    let bytes = value.as_bytes();
+    
+    for (i, c) in value.char_indices() {
+        let keep = c.is_ascii_alphanumeric()
+            || matches!(c, '-' | '.' | '_' | '~')
+            || (reserved && RESERVED.contains(c))
+            || (reserved
+                && c == '%'
+                && bytes.get(i + 1..i + 3).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)));
+        if keep {
+            url.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                url.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_expands_rfc_examples() {
+        let vars = json!({
+            "var": "value",
+            "hello": "Hello World!",
+            "path": "/foo/bar",
+            "list": ["red", "green", "blue"],
+            "keys": {"comma": ",", "dot": ".", "semi": ";"},
+            "empty": "",
+            "x": 1024,
+            "y": 768,
+            "undef": null,
+        });
+        let cases = [
+            ("{var}", "value"),
+            ("{hello}", "Hello%20World%21"),
+            ("{+hello}", "Hello%20World!"),
+            ("{+path}/here", "/foo/bar/here"),
+            ("{#path,x}/here", "#/foo/bar,1024/here"),
+            ("X{.var:3}", "X.val"),
+            ("{/list*,path:4}", "/red/green/blue/%2Ffoo"),
+            ("{;x,y,empty}", ";x=1024;y=768;empty"),
+            ("{?x,y,empty,undef}", "?x=1024&y=768&empty="),
+            ("?fixed=yes{&x}", "?fixed=yes&x=1024"),
+            ("{?list}", "?list=red,green,blue"),
+            ("{?list*}", "?list=red&list=green&list=blue"),
+            ("{keys}", "comma,%2C,dot,.,semi,%3B"),
+            ("{?keys*}", "?comma=%2C&dot=.&semi=%3B"),
+            ("{/undef}{?undef}", ""),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(UrlTemplate::parse(template).unwrap().expand(&vars).unwrap(), expected, "{}", template);
+        }
+    }
+    
+    #[test]
+    fn test_typed_expansion_and_errors() {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            owner: &'a str,
+            page: Option<u32>,
+            per_page: u32,
+        }
+        
+        let template = UrlTemplate::parse("/users/{owner}/repos{?page,per_page}").unwrap();
+        assert_eq!(template.variables(), ["owner", "page", "per_page"]);
+        assert_eq!(template.path_variables(), ["owner"]);
+        let url = template
+            .expand(&Params { owner: "../admin?x=1", page: None, per_page: 50 })
+            .unwrap();
+        assert_eq!(url, "/users/..%2Fadmin%3Fx%3D1/repos?per_page=50");
+        
+        assert!(matches!(UrlTemplate::parse("/users/{id"), Err(HttpError::UrlError(_))));
+        assert!(UrlTemplate::parse("/users/{id:0}").is_err());
+        assert!(UrlTemplate::parse("/users/{}").is_err());
+        assert!(UrlTemplate::parse("/users/id}").is_err());
+        assert!(template.expand(&[1, 2]).is_err());
+        assert!(UrlTemplate::parse("{list:2}").unwrap().expand(&json!({"list": ["a"]})).is_err());
+    }
+}
@@ -0,0 +1,452 @@
+// src/vcr.rs
+// Record-and-replay transport backed by cassette files
+
+use crate::error::{HttpError, Result};
+use crate::transport::{build_response, Transport};
+use crate::utils::UrlSanitizer;
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE};
+use reqwest::{Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+const REDACTED: &str = "[REDACTED]";
+
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Whether a `VcrTransport` talks to the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcrMode {
+    /// Send requests for real and append them to the cassette
+    Record,
+    /// Answer requests from the cassette only
+    Replay,
+}
+
+/// A body stored as text when it is UTF-8, otherwise as base64
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CassetteBody {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl CassetteBody {
+    fn new(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(text) => CassetteBody::Text(text),
+            Err(e) => CassetteBody::Binary {
+                base64: base64::engine::general_purpose::STANDARD.encode(e.into_bytes()),
+            },
+        }
+    }
+    
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            CassetteBody::Text(text) => Ok(text.clone().into_bytes()),
+            CassetteBody::Binary { base64 } => base64::engine::general_purpose::STANDARD
+                .decode(base64)
+                .map_err(|e| HttpError::SerializationError(format!("Invalid cassette body: {}", e))),
+        }
+    }
+}
+
+/// Recorded request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CassetteRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<CassetteBody>,
+}
+
+/// Recorded response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CassetteResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: CassetteBody,
+}
+
+/// One request/response pair in a cassette
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: CassetteRequest,
+    pub response: CassetteResponse,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    fn is_yaml(path: &Path) -> bool {
+        matches!(path.extension().and_then(|ext| ext.to_str()), Some("yaml" | "yml"))
+    }
+    
+    fn decode(path: &Path, bytes: &[u8]) -> Result<Self> {
+        if !Self::is_yaml(path) {
+            return Ok(serde_json::from_slice(bytes)?);
+        }
+        #[cfg(feature = "yaml")]
+        return serde_yaml::from_slice(bytes)
+            .map_err(|e| HttpError::SerializationError(format!("Invalid YAML cassette: {}", e)));
+        #[cfg(not(feature = "yaml"))]
+        Err(HttpError::ConfigError("YAML cassettes require the `yaml` feature".to_string()))
+    }
+    
+    fn encode(&self, path: &Path) -> Result<Vec<u8>> {
+        if !Self::is_yaml(path) {
+            return Ok(serde_json::to_vec_pretty(self)?);
+        }
+        #[cfg(feature = "yaml")]
+        return serde_yaml::to_string(self)
+            .map(String::into_bytes)
+            .map_err(|e| HttpError::SerializationError(format!("Failed to encode YAML cassette: {}", e)));
+        #[cfg(not(feature = "yaml"))]
+        Err(HttpError::ConfigError("YAML cassettes require the `yaml` feature".to_string()))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    played: Vec<bool>,
+}
+
+/// Transport that records real traffic to a cassette file or replays it
+///
+/// Cassettes are JSON, or YAML when the path ends in `.yaml`/`.yml` and the
+/// `yaml` feature is enabled. In record mode every exchange is appended to
+/// the cassette and the file is rewritten, with sensitive headers
+/// (`Authorization`, `Cookie`, `Set-Cookie` and `Proxy-Authorization` by
+/// default) replaced by `[REDACTED]`. In replay mode requests are matched on
+/// method and URL; each recorded interaction is played once in order, then
+/// the last match repeats. URLs are kept as sent unless a sanitizer is set
+/// with `with_url_sanitizer`.
+#[derive(Debug)]
+pub struct VcrTransport {
+    path: PathBuf,
+    mode: VcrMode,
+    client: Option<reqwest::Client>,
+    redacted: Vec<HeaderName>,
+    sanitizer: Option<UrlSanitizer>,
+    tape: Mutex<Tape>,
+    // serializes cassette writes so an older snapshot never lands last
+    saving: tokio::sync::Mutex<()>,
+}
+
+impl VcrTransport {
+    fn new(path: PathBuf, mode: VcrMode, client: Option<reqwest::Client>, tape: Tape) -> Self {
+        Self {
+            path,
+            mode,
+            client,
+            redacted: vec![AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE, SET_COOKIE],
+            sanitizer: None,
+            tape: Mutex::new(tape),
+            saving: tokio::sync::Mutex::new(()),
+        }
+    }
+    
+    /// Record through `client` into a new cassette at `path`
+    pub fn record<P: Into<PathBuf>>(path: P, client: reqwest::Client) -> Self {
+        Self::new(path.into(), VcrMode::Record, Some(client), Tape::default())
+    }
+    
+    /// Replay the cassette at `path`
+    pub fn replay<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let bytes = fs::read(&path)
+            .map_err(|e| HttpError::IoError(format!("Failed to read cassette {}: {}", path.display(), e)))?;
+        let cassette = Cassette::decode(&path, &bytes)?;
+        
+        let played = vec![false; cassette.interactions.len()];
+        let tape = Tape {
+            interactions: cassette.interactions,
+            played,
+        };
+        Ok(Self::new(path, VcrMode::Replay, None, tape))
+    }
+    
+    /// Replay `path` if it exists, otherwise record into it through `client`
+    pub fn auto<P: Into<PathBuf>>(path: P, client: reqwest::Client) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            Self::replay(path)
+        } else {
+            Ok(Self::record(path, client))
+        }
+    }
+    
+    /// Also redact `name` when recording
+    pub fn with_redacted_header(mut self, name: HeaderName) -> Self {
+        self.redacted.push(name);
+        self
+    }
+    
+    /// Record URLs through `sanitizer` and match replays on sanitized URLs
+    ///
+    /// Keeps tokens in query parameters out of the cassette. Requests that
+    /// only differ in the parameters the sanitizer drops then share the
+    /// same recordings.
+    pub fn with_url_sanitizer(mut self, sanitizer: UrlSanitizer) -> Self {
+        self.sanitizer = Some(sanitizer);
+        self
+    }
+    
+    pub fn mode(&self) -> VcrMode {
+        self.mode
+    }
+    
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// Interactions recorded or loaded so far
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.tape.lock().unwrap().interactions.clone()
+    }
+    
+    fn headers(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.redacted.contains(name) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.as_str().to_string(), value)
+            })
+            .collect()
+    }
+    
+    fn sanitize(&self, url: &str) -> String {
+        match &self.sanitizer {
+            Some(sanitizer) => sanitizer.sanitize(url).unwrap_or_else(|_| url.to_string()),
+            None => url.to_string(),
+        }
+    }
+    
+    /// Temporary file next to the cassette, unique per process and write
+    fn tmp_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        self.path.with_file_name(name)
+    }
+    
+    async fn save(&self) -> Result<()> {
+        let io_error = |e: std::io::Error| {
+            HttpError::IoError(format!("Failed to save cassette {}: {}", self.path.display(), e))
+        };
+        
+        let _saving = self.saving.lock().await;
+        let bytes = {
+            let tape = self.tape.lock().unwrap();
+            Cassette {
+                interactions: tape.interactions.clone(),
+            }
+            .encode(&self.path)?
+        };
+        
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        let tmp_path = self.tmp_path();
+        if let Err(e) = tokio::fs::write(&tmp_path, bytes).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(io_error(e));
+        }
+        tokio::fs::rename(&tmp_path, &self.path).await.map_err(io_error)
+    }
+    
+    async fn record_exchange(&self, client: &reqwest::Client, request: Request) -> Result<Response> {
+        let recorded = CassetteRequest {
+            method: request.method().to_string(),
+            url: self.sanitize(request.url().as_str()),
+            headers: self.headers(request.headers()),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| CassetteBody::new(bytes.to_vec())),
+        };
+        
+        let response = client.execute(request).await?;
+        let url = response.url().clone();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?.to_vec();
+        
+        let interaction = Interaction {
+            request: recorded,
+            response: CassetteResponse {
+                status: status.as_u16(),
+                headers: self.headers(&headers),
+                body: CassetteBody::new(body.clone()),
+            },
+        };
+        
+        {
+            let mut tape = self.tape.lock().unwrap();
+            tape.interactions.push(interaction);
+            tape.played.push(true);
+        }
+        self.save().await?;
+        
+        Ok(build_response(&url, status, headers, body))
+    }
+    
+    fn replay_exchange(&self, request: &Request) -> Result<Response> {
+        let method = request.method().as_str();
+        let url = self.sanitize(request.url().as_str());
+        
+        let mut tape = self.tape.lock().unwrap();
+        let matching: Vec<usize> = (0..tape.interactions.len())
+            .filter(|&i| {
+                let recorded = &tape.interactions[i].request;
+                recorded.method == method && self.sanitize(&recorded.url) == url
+            })
+            .collect();
+        
+        let index = matching
+            .iter()
+            .copied()
+            .find(|&i| !tape.played[i])
+            .or_else(|| matching.last().copied())
+            .ok_or_else(|| {
+                HttpError::ConfigError(format!(
+                    "No interaction in cassette {} matches {} {}",
+                    self.path.display(),
+                    method,
+                    url
+                ))
+            })?;
+        tape.played[index] = true;
+        
+        let recorded = &tape.interactions[index].response;
+        let status = StatusCode::from_u16(recorded.status)
+            .map_err(|e| HttpError::SerializationError(format!("Invalid cassette status: {}", e)))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &recorded.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| HttpError::HeaderError(format!("Invalid cassette header '{}': {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| HttpError::HeaderError(format!("Invalid cassette header value: {}", e)))?;
+            headers.append(name, value);
+        }
+        
+        Ok(build_response(request.url(), status, headers, recorded.body.to_bytes()?))
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for VcrTransport {
+    async fn send(&self, request: Request) -> Result<Response> {
+        match (&self.mode, &self.client) {
+            (VcrMode::Record, Some(client)) => self.record_exchange(client, request).await,
+            _ => self.replay_exchange(&request),
+        }
+    }
+    
+    fn uses_network(&self) -> bool {
+        self.mode == VcrMode::Record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ClientConfig, HttpClient};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    
+    #[tokio::test]
+    async fn test_record_then_replay_offline() {
+        let path = std::env::temp_dir()
+            .join(format!("rusty-http-vcr-{}", std::process::id()))
+            .join("users.json");
+        let _ = fs::remove_file(&path);
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nset-cookie: sid=secret\r\ncontent-length: 9\r\n\r\n[\"alice\"]")
+                .await
+                .unwrap();
+        });
+        
+        let base = format!("http://127.0.0.1:{}", port);
+        let config = ClientConfig::new()
+            .with_base_url(&base)
+            .unwrap()
+            .with_default_header("authorization", "Bearer secret")
+            .unwrap();
+        let client = HttpClient::with_config(config).unwrap();
+        let recorder = VcrTransport::auto(&path, client.inner())
+            .unwrap()
+            .with_url_sanitizer(UrlSanitizer::new());
+        assert_eq!(recorder.mode(), VcrMode::Record);
+        let client = client.with_transport(recorder);
+        let users: Vec<String> = client.get_json("/users?access_token=secret&page=1").await.unwrap();
+        assert_eq!(users, ["alice"]);
+        server.await.unwrap();
+        
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("secret"));
+        assert!(saved.contains(REDACTED));
+        assert!(saved.contains("/users?page=1"));
+        let leftovers = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension().is_some_and(|ext| ext == "tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+        
+        // the server is gone, so this only works from the cassette
+        let player = VcrTransport::auto(&path, reqwest::Client::new())
+            .unwrap()
+            .with_url_sanitizer(UrlSanitizer::new());
+        assert_eq!(player.mode(), VcrMode::Replay);
+        let client = HttpClient::with_base_url(&base).unwrap().with_transport(player);
+        let users: Vec<String> = client.get_json("/users?page=1&access_token=other").await.unwrap();
+        assert_eq!(users, ["alice"]);
+        assert!(client.get("/other").await.is_err());
+        
+        // without a sanitizer, URLs must match exactly
+        let strict = VcrTransport::replay(&path).unwrap();
+        let client = HttpClient::with_base_url(&base).unwrap().with_transport(strict);
+        assert!(client.get("/users?page=1").await.is_ok());
+        assert!(client.get("/users?page=1&access_token=other").await.is_err());
+        
+        let _ = fs::remove_file(&path);
+    }
+    
+    #[cfg(feature = "yaml")]
+    #[tokio::test]
+    async fn test_replay_yaml_cassette() {
+        let path = std::env::temp_dir().join(format!("rusty-http-vcr-{}.yaml", std::process::id()));
+        let yaml = "interactions:\n\
+            - request:\n    method: GET\n    url: http://example.test/ping\n    headers: []\n\
+            \x20 response:\n    status: 200\n    headers: []\n    body: pong\n";
+        fs::write(&path, yaml).unwrap();
+        
+        let player = VcrTransport::replay(&path).unwrap();
+        let client = HttpClient::with_base_url("http://example.test").unwrap().with_transport(player);
+        let response = client.get("/ping").await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "pong");
+        
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,90 @@
+// src/xml.rs
+// XML request and response helpers
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+
+const APPLICATION_XML: &str = "application/xml";
+
+/// Serialize `value` as an XML document named after its type
+pub fn to_xml<T: Serialize>(value: &T) -> Result<String> {
+    quick_xml::se::to_string(value)
+        .map_err(|e| HttpError::SerializationError(format!("Failed to serialize XML: {}", e)))
+}
+
+/// Deserialize an XML document
+pub fn from_xml<T: DeserializeOwned>(xml: &str) -> Result<T> {
+    quick_xml::de::from_str(xml)
+        .map_err(|e| HttpError::SerializationError(format!("Failed to deserialize XML: {}", e)))
+}
+
+impl HttpClient {
+    /// Send a GET request and deserialize the response as XML
+    pub async fn get_xml<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.send_text_format(Method::GET, url, APPLICATION_XML, None, from_xml).await
+    }
+    
+    /// Send a POST request with an XML body
+    pub async fn post_xml<T: Serialize, R: DeserializeOwned>(&self, url: &str, body: &T) -> Result<R> {
+        self.send_xml(Method::POST, url, body).await
+    }
+    
+    /// Send a PUT request with an XML body
+    pub async fn put_xml<T: Serialize, R: DeserializeOwned>(&self, url: &str, body: &T) -> Result<R> {
+        self.send_xml(Method::PUT, url, body).await
+    }
+    
+    async fn send_xml<T: Serialize, R: DeserializeOwned>(&self, method: Method, url: &str, body: &T) -> Result<R> {
+        self.send_text_format(method, url, APPLICATION_XML, Some(to_xml(body)?), from_xml).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::header::{HeaderValue, ACCEPT, CONTENT_TYPE};
+    use reqwest::StatusCode;
+    use serde::Deserialize;
+    
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        id: u32,
+        item: String,
+    }
+    
+    #[tokio::test]
+    async fn test_xml_round_trip() {
+        let transport = MockTransport::new();
+        let xml = |body: &str| {
+            MockResponse::new(StatusCode::OK)
+                .with_header(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_XML))
+                .with_body(body.to_string())
+        };
+        transport.expect(
+            Mock::get("/orders/1")
+                .with_header(ACCEPT, HeaderValue::from_static(APPLICATION_XML))
+                .respond_with(xml("<Order><id>1</id><item>tea</item></Order>")),
+        );
+        transport.expect(
+            Mock::post("/orders")
+                .with_header(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_XML))
+                .with_body("<Order><id>2</id><item>cake</item></Order>")
+                .respond_with(xml("<Order><id>2</id><item>cake</item></Order>")),
+        );
+        transport.expect(Mock::get("/orders/3").respond_with(MockResponse::new(StatusCode::NOT_FOUND)));
+        
+        let client = transport.client();
+        let order: Order = client.get_xml("/orders/1").await.unwrap();
+        assert_eq!(order, Order { id: 1, item: "tea".to_string() });
+        
+        let cake = Order { id: 2, item: "cake".to_string() };
+        let created: Order = client.post_xml("/orders", &cake).await.unwrap();
+        assert_eq!(created, cake);
+        
+        let missing = client.get_xml::<Order>("/orders/3").await;
+        assert!(matches!(missing, Err(HttpError::ResponseError { status: StatusCode::NOT_FOUND, .. })));
+    }
+}
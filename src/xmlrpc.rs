@@ -0,0 +1,524 @@
+// src/xmlrpc.rs
+// XML-RPC calls over HttpClient
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+pub use crate::error::XmlRpcFault;
+use base64::Engine;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::Method;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+
+/// A value passed to or returned from an XML-RPC method
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlRpcValue {
+    /// `<int>`/`<i4>`, or `<i8>` for values outside the 32-bit range
+    Int(i64),
+    Boolean(bool),
+    String(String),
+    Double(f64),
+    /// `<dateTime.iso8601>`, kept as sent
+    DateTime(String),
+    Base64(Vec<u8>),
+    Array(Vec<XmlRpcValue>),
+    Struct(BTreeMap<String, XmlRpcValue>),
+    Nil,
+}
+
+impl XmlRpcValue {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            XmlRpcValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+    
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            XmlRpcValue::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            XmlRpcValue::String(value) | XmlRpcValue::DateTime(value) => Some(value),
+            _ => None,
+        }
+    }
+    
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            XmlRpcValue::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+    
+    pub fn as_array(&self) -> Option<&[XmlRpcValue]> {
+        match self {
+            XmlRpcValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+    
+    /// Get a struct member
+    pub fn get(&self, name: &str) -> Option<&XmlRpcValue> {
+        match self {
+            XmlRpcValue::Struct(members) => members.get(name),
+            _ => None,
+        }
+    }
+    
+    fn write(&self, xml: &mut String) {
+        xml.push_str("<value>");
+        match self {
+            XmlRpcValue::Int(value) if i32::try_from(*value).is_ok() => {
+                xml.push_str(&format!("<int>{}</int>", value))
+            }
+            XmlRpcValue::Int(value) => xml.push_str(&format!("<i8>{}</i8>", value)),
+            XmlRpcValue::Boolean(value) => xml.push_str(&format!("<boolean>{}</boolean>", u8::from(*value))),
+            XmlRpcValue::String(value) => xml.push_str(&format!("<string>{}</string>", escape(value))),
+            XmlRpcValue::Double(value) => xml.push_str(&format!("<double>{}</double>", value)),
+            XmlRpcValue::DateTime(value) => {
+                xml.push_str(&format!("<dateTime.iso8601>{}</dateTime.iso8601>", escape(value)))
+            }
+            XmlRpcValue::Base64(value) => xml.push_str(&format!(
+                "<base64>{}</base64>",
+                base64::engine::general_purpose::STANDARD.encode(value)
+            )),
+            XmlRpcValue::Array(values) => {
+                xml.push_str("<array><data>");
+                for value in values {
+                    value.write(xml);
+                }
+                xml.push_str("</data></array>");
+            }
+            XmlRpcValue::Struct(members) => {
+                xml.push_str("<struct>");
+                for (name, value) in members {
+                    xml.push_str(&format!("<member><name>{}</name>", escape(name)));
+                    value.write(xml);
+                    xml.push_str("</member>");
+                }
+                xml.push_str("</struct>");
+            }
+            XmlRpcValue::Nil => xml.push_str("<nil/>"),
+        }
+        xml.push_str("</value>");
+    }
+}
+
+impl From<i32> for XmlRpcValue {
+    fn from(value: i32) -> Self {
+        XmlRpcValue::Int(value.into())
+    }
+}
+
+impl From<i64> for XmlRpcValue {
+    fn from(value: i64) -> Self {
+        XmlRpcValue::Int(value)
+    }
+}
+
+impl From<bool> for XmlRpcValue {
+    fn from(value: bool) -> Self {
+        XmlRpcValue::Boolean(value)
+    }
+}
+
+impl From<f64> for XmlRpcValue {
+    fn from(value: f64) -> Self {
+        XmlRpcValue::Double(value)
+    }
+}
+
+impl From<&str> for XmlRpcValue {
+    fn from(value: &str) -> Self {
+        XmlRpcValue::String(value.to_string())
+    }
+}
+
+impl From<String> for XmlRpcValue {
+    fn from(value: String) -> Self {
+        XmlRpcValue::String(value)
+    }
+}
+
+impl<T: Into<XmlRpcValue>> From<Vec<T>> for XmlRpcValue {
+    fn from(values: Vec<T>) -> Self {
+        XmlRpcValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Encode a `<methodCall>` document
+pub fn encode_call(method: &str, params: &[XmlRpcValue]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\"?><methodCall><methodName>{}</methodName><params>",
+        escape(method)
+    );
+    for param in params {
+        xml.push_str("<param>");
+        param.write(&mut xml);
+        xml.push_str("</param>");
+    }
+    xml.push_str("</params></methodCall>");
+    xml
+}
+
+/// Decode a `<methodResponse>` document
+///
+/// A fault response fails with `HttpError::XmlRpcFault`. A response without
+/// params decodes as `Nil`.
+pub fn decode_response(xml: &str) -> Result<XmlRpcValue> {
+    let mut parser = Parser::new(xml)?;
+    parser.start("methodResponse")?;
+    
+    let value = match parser.next_tag()? {
+        Token::Start(tag) if tag == "params" => match parser.next_tag()? {
+            Token::Start(tag) if tag == "param" => {
+                let value = parser.value()?;
+                parser.end("param")?;
+                parser.end("params")?;
+                value
+            }
+            Token::End(tag) if tag == "params" => XmlRpcValue::Nil,
+            other => return Err(parser.unexpected(other)),
+        },
+        Token::Empty(tag) if tag == "params" => XmlRpcValue::Nil,
+        Token::Start(tag) if tag == "fault" => {
+            let fault = parser.value()?;
+            return Err(HttpError::XmlRpcFault(XmlRpcFault {
+                code: fault.get("faultCode").and_then(XmlRpcValue::as_i64).unwrap_or_default(),
+                message: fault.get("faultString").and_then(XmlRpcValue::as_str).unwrap_or_default().to_string(),
+            }));
+        }
+        other => return Err(parser.unexpected(other)),
+    };
+    parser.end("methodResponse")?;
+    Ok(value)
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Start(String),
+    End(String),
+    Empty(String),
+    Text(String),
+    Eof,
+}
+
+/// Recursive descent over the document's elements and text
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn new(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        let mut tokens = Vec::new();
+        loop {
+            let token = match reader.read_event().map_err(xml_error)? {
+                Event::Start(e) => Token::Start(String::from_utf8_lossy(e.name().as_ref()).into_owned()),
+                Event::End(e) => Token::End(String::from_utf8_lossy(e.name().as_ref()).into_owned()),
+                Event::Empty(e) => Token::Empty(String::from_utf8_lossy(e.name().as_ref()).into_owned()),
+                Event::Text(e) => Token::Text(e.unescape().map_err(xml_error)?.into_owned()),
+                Event::CData(e) => Token::Text(String::from_utf8_lossy(&e).into_owned()),
+                Event::Eof => break,
+                _ => continue,
+            };
+            // entities and CDATA can split one text node into several events
+            match (tokens.last_mut(), token) {
+                (Some(Token::Text(text)), Token::Text(more)) => text.push_str(&more),
+                (_, token) => tokens.push(token),
+            }
+        }
+        Ok(Self { tokens: tokens.into_iter().peekable() })
+    }
+    
+    fn next(&mut self) -> Token {
+        self.tokens.next().unwrap_or(Token::Eof)
+    }
+    
+    /// The next element token, skipping whitespace between elements
+    fn next_tag(&mut self) -> Result<Token> {
+        loop {
+            match self.next() {
+                Token::Text(text) if text.trim().is_empty() => continue,
+                Token::Text(text) => return Err(self.unexpected(Token::Text(text))),
+                token => return Ok(token),
+            }
+        }
+    }
+    
+    fn start(&mut self, name: &str) -> Result<()> {
+        match self.next_tag()? {
+            Token::Start(tag) if tag == name => Ok(()),
+            other => Err(self.unexpected(other)),
+        }
+    }
+    
+    fn end(&mut self, name: &str) -> Result<()> {
+        match self.next_tag()? {
+            Token::End(tag) if tag == name => Ok(()),
+            other => Err(self.unexpected(other)),
+        }
+    }
+    
+    /// Text content up to the closing tag `name`
+    fn text(&mut self, name: &str) -> Result<String> {
+        match self.next() {
+            Token::Text(text) => {
+                self.end(name)?;
+                Ok(text)
+            }
+            Token::End(tag) if tag == name => Ok(String::new()),
+            other => Err(self.unexpected(other)),
+        }
+    }
+    
+    fn value(&mut self) -> Result<XmlRpcValue> {
+        match self.next_tag()? {
+            Token::Start(tag) if tag == "value" => {}
+            Token::Empty(tag) if tag == "value" => return Ok(XmlRpcValue::String(String::new())),
+            other => return Err(self.unexpected(other)),
+        }
+        
+        // a bare <value> holds a string
+        let tag = match self.next() {
+            Token::Text(text) => match self.next_tag()? {
+                Token::End(tag) if tag == "value" => return Ok(XmlRpcValue::String(text)),
+                Token::Start(tag) if text.trim().is_empty() => tag,
+                Token::Empty(tag) if text.trim().is_empty() => {
+                    let value = empty_value(&tag)?;
+                    self.end("value")?;
+                    return Ok(value);
+                }
+                other => return Err(self.unexpected(other)),
+            },
+            Token::End(tag) if tag == "value" => return Ok(XmlRpcValue::String(String::new())),
+            Token::Start(tag) => tag,
+            Token::Empty(tag) => {
+                let value = empty_value(&tag)?;
+                self.end("value")?;
+                return Ok(value);
+            }
+            other => return Err(self.unexpected(other)),
+        };
+        
+        let value = match tag.as_str() {
+            "int" | "i4" | "i8" => XmlRpcValue::Int(parse(&tag, self.text(&tag)?.trim())?),
+            "boolean" => match self.text(&tag)?.trim() {
+                "1" => XmlRpcValue::Boolean(true),
+                "0" => XmlRpcValue::Boolean(false),
+                other => return Err(invalid(&tag, other)),
+            },
+            "string" => XmlRpcValue::String(self.text(&tag)?),
+            "double" => XmlRpcValue::Double(parse(&tag, self.text(&tag)?.trim())?),
+            "dateTime.iso8601" => XmlRpcValue::DateTime(self.text(&tag)?.trim().to_string()),
+            "base64" => {
+                let text: String = self.text(&tag)?.split_whitespace().collect();
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(&text)
+                    .map_err(|_| invalid(&tag, &text))?;
+                XmlRpcValue::Base64(bytes)
+            }
+            "nil" => {
+                self.end("nil")?;
+                XmlRpcValue::Nil
+            }
+            "array" => {
+                let mut values = Vec::new();
+                match self.next_tag()? {
+                    Token::Start(data) if data == "data" => {
+                        while let Some(value) = self.value_until("data")? {
+                            values.push(value);
+                        }
+                    }
+                    Token::Empty(data) if data == "data" => {}
+                    other => return Err(self.unexpected(other)),
+                }
+                self.end("array")?;
+                XmlRpcValue::Array(values)
+            }
+            "struct" => {
+                let mut members = BTreeMap::new();
+                loop {
+                    match self.next_tag()? {
+                        Token::Start(member) if member == "member" => {
+                            self.start("name")?;
+                            let name = self.text("name")?;
+                            members.insert(name, self.value()?);
+                            self.end("member")?;
+                        }
+                        Token::End(end) if end == "struct" => break,
+                        other => return Err(self.unexpected(other)),
+                    }
+                }
+                XmlRpcValue::Struct(members)
+            }
+            other => return Err(HttpError::SerializationError(format!("Unknown XML-RPC type <{}>", other))),
+        };
+        self.end("value")?;
+        Ok(value)
+    }
+    
+    /// Parse a value, or return `None` at the closing tag `name`
+    fn value_until(&mut self, name: &str) -> Result<Option<XmlRpcValue>> {
+        while matches!(self.tokens.peek(), Some(Token::Text(text)) if text.trim().is_empty()) {
+            self.tokens.next();
+        }
+        if matches!(self.tokens.peek(), Some(Token::End(tag)) if tag == name) {
+            self.tokens.next();
+            return Ok(None);
+        }
+        self.value().map(Some)
+    }
+    
+    fn unexpected(&self, token: Token) -> HttpError {
+        HttpError::SerializationError(format!("Malformed XML-RPC response near {:?}", token))
+    }
+}
+
+fn empty_value(tag: &str) -> Result<XmlRpcValue> {
+    match tag {
+        "nil" => Ok(XmlRpcValue::Nil),
+        "string" => Ok(XmlRpcValue::String(String::new())),
+        "array" => Ok(XmlRpcValue::Array(Vec::new())),
+        "struct" => Ok(XmlRpcValue::Struct(BTreeMap::new())),
+        "base64" => Ok(XmlRpcValue::Base64(Vec::new())),
+        other => Err(invalid(other, "")),
+    }
+}
+
+fn parse<T: std::str::FromStr>(tag: &str, text: &str) -> Result<T> {
+    text.parse().map_err(|_| invalid(tag, text))
+}
+
+fn invalid(tag: &str, text: &str) -> HttpError {
+    HttpError::SerializationError(format!("Invalid XML-RPC <{}> value '{}'", tag, text))
+}
+
+fn xml_error(e: quick_xml::Error) -> HttpError {
+    HttpError::SerializationError(format!("Failed to parse XML-RPC response: {}", e))
+}
+
+/// An XML-RPC endpoint reached through an `HttpClient`
+///
+/// Calls go through `HttpClient::execute`, so middleware and retries apply.
+#[derive(Debug, Clone)]
+pub struct XmlRpcClient {
+    client: HttpClient,
+    url: String,
+}
+
+impl XmlRpcClient {
+    /// Send calls to the endpoint at `url`
+    pub fn new(client: HttpClient, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+        }
+    }
+    
+    /// Call `method` with positional `params`
+    pub async fn call(&self, method: &str, params: &[XmlRpcValue]) -> Result<XmlRpcValue> {
+        let request = self
+            .client
+            .request(Method::POST, &self.url)?
+            .header(CONTENT_TYPE, HeaderValue::from_static("text/xml"))
+            .body(encode_call(method, params))
+            .build()?;
+        let response = self.client.execute(request).await?;
+        let status = response.status();
+        let body = response.text().await?;
+        
+        if !status.is_success() {
+            return Err(HttpError::ResponseError { status, body });
+        }
+        decode_response(&body)
+    }
+}
+
+impl HttpClient {
+    /// Make XML-RPC calls to the endpoint at `url`
+    pub fn xmlrpc(&self, url: &str) -> XmlRpcClient {
+        XmlRpcClient::new(self.clone(), url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::StatusCode;
+    
+    #[test]
+    fn test_encode_and_decode_values() {
+        let mut members = BTreeMap::new();
+        members.insert("name".to_string(), XmlRpcValue::from("a & b"));
+        members.insert("tags".to_string(), XmlRpcValue::from(vec![1, 2]));
+        let params = [
+            XmlRpcValue::Struct(members.clone()),
+            XmlRpcValue::Int(1 << 40),
+            XmlRpcValue::Base64(b"hi".to_vec()),
+            true.into(),
+            XmlRpcValue::Nil,
+        ];
+        
+        let call = encode_call("demo.echo", &params);
+        assert!(call.contains("<methodName>demo.echo</methodName>"));
+        assert!(call.contains("<string>a &amp; b</string>"));
+        assert!(call.contains("<i8>1099511627776</i8>"));
+        
+        let mut value = String::new();
+        XmlRpcValue::Array(params.to_vec()).write(&mut value);
+        let response = format!(
+            "<methodResponse>\n<params>\n<param>{}</param>\n</params>\n</methodResponse>",
+            value.replace("<data>", "<data>\n  ")
+        );
+        let decoded = decode_response(&response).unwrap();
+        assert_eq!(decoded.as_array().unwrap(), params);
+        
+        let bare = "<methodResponse><params><param><value> plain </value></param></params></methodResponse>";
+        assert_eq!(decode_response(bare).unwrap().as_str(), Some(" plain "));
+        assert!(decode_response("<methodResponse><params><param><value><int>x</int></value>").is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_call_and_fault() {
+        let transport = MockTransport::new();
+        let xml = |body: &str| {
+            MockResponse::new(StatusCode::OK)
+                .with_header(CONTENT_TYPE, HeaderValue::from_static("text/xml"))
+                .with_body(body.to_string())
+        };
+        transport.expect(
+            Mock::post("/RPC2")
+                .with_body(encode_call("math.add", &[2.into(), 3.into()]))
+                .respond_with(xml(
+                    "<?xml version=\"1.0\"?>\n<methodResponse>\n  <params>\n    <param><value><i4>5</i4></value></param>\n  </params>\n</methodResponse>",
+                )),
+        );
+        transport.expect(Mock::post("/RPC2").respond_with(xml(
+            "<methodResponse><fault><value><struct>\
+             <member><name>faultCode</name><value><int>4</int></value></member>\
+             <member><name>faultString</name><value><string>Too many parameters</string></value></member>\
+             </struct></value></fault></methodResponse>",
+        )));
+        let client = transport.client();
+        let rpc = client.xmlrpc("/RPC2");
+        
+        assert_eq!(rpc.call("math.add", &[2.into(), 3.into()]).await.unwrap(), XmlRpcValue::Int(5));
+        match rpc.call("math.add", &[1.into(), 2.into(), 3.into()]).await {
+            Err(HttpError::XmlRpcFault(fault)) => {
+                assert_eq!(fault.code, 4);
+                assert_eq!(fault.message, "Too many parameters");
+            }
+            other => panic!("expected a fault, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// src/yaml.rs
+// YAML request and response helpers
+
+use crate::client::HttpClient;
+use crate::error::{HttpError, Result};
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+
+const APPLICATION_YAML: &str = "application/yaml";
+
+/// Serialize `value` as a YAML document
+pub fn to_yaml<T: Serialize>(value: &T) -> Result<String> {
+    serde_yaml::to_string(value)
+        .map_err(|e| HttpError::SerializationError(format!("Failed to serialize YAML: {}", e)))
+}
+
+/// Deserialize a YAML document
+pub fn from_yaml<T: DeserializeOwned>(yaml: &str) -> Result<T> {
+    serde_yaml::from_str(yaml)
+        .map_err(|e| HttpError::SerializationError(format!("Failed to deserialize YAML: {}", e)))
+}
+
+impl HttpClient {
+    /// Send a GET request and deserialize the response as YAML
+    pub async fn get_yaml<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.fetch_yaml(Method::GET, url).await
+    }
+    
+    /// Send a POST request with a YAML body
+    pub async fn post_yaml<T: Serialize, R: DeserializeOwned>(&self, url: &str, body: &T) -> Result<R> {
+        self.send_yaml(Method::POST, url, body).await
+    }
+    
+    /// Send a PUT request with a YAML body
+    pub async fn put_yaml<T: Serialize, R: DeserializeOwned>(&self, url: &str, body: &T) -> Result<R> {
+        self.send_yaml(Method::PUT, url, body).await
+    }
+    
+    /// Send a PATCH request with a YAML body
+    pub async fn patch_yaml<T: Serialize, R: DeserializeOwned>(&self, url: &str, body: &T) -> Result<R> {
+        self.send_yaml(Method::PATCH, url, body).await
+    }
+    
+    /// Send a DELETE request and deserialize the response as YAML
+    pub async fn delete_yaml<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.fetch_yaml(Method::DELETE, url).await
+    }
+    
+    async fn fetch_yaml<T: DeserializeOwned>(&self, method: Method, url: &str) -> Result<T> {
+        self.send_text_format(method, url, APPLICATION_YAML, None, from_yaml).await
+    }
+    
+    async fn send_yaml<T: Serialize, R: DeserializeOwned>(&self, method: Method, url: &str, body: &T) -> Result<R> {
+        self.send_text_format(method, url, APPLICATION_YAML, Some(to_yaml(body)?), from_yaml).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Mock, MockResponse, MockTransport};
+    use reqwest::header::{HeaderValue, ACCEPT, CONTENT_TYPE};
+    use reqwest::StatusCode;
+    use serde::Deserialize;
+    
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Deployment {
+        name: String,
+        replicas: u32,
+    }
+    
+    #[tokio::test]
+    async fn test_yaml_round_trip() {
+        let transport = MockTransport::new();
+        let yaml = |body: &str| {
+            MockResponse::new(StatusCode::OK)
+                .with_header(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_YAML))
+                .with_body(body.to_string())
+        };
+        transport.expect(
+            Mock::get("/deployments/web")
+                .with_header(ACCEPT, HeaderValue::from_static(APPLICATION_YAML))
+                .respond_with(yaml("name: web\nreplicas: 3\n")),
+        );
+        transport.expect(
+            Mock::put("/deployments/web")
+                .with_header(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_YAML))
+                .with_body("name: web\nreplicas: 5\n")
+                .respond_with(yaml("name: web\nreplicas: 5\n")),
+        );
+        transport.expect(Mock::delete("/deployments/gone").respond_with(MockResponse::new(StatusCode::NOT_FOUND)));
+        
+        let client = transport.client();
+        let web: Deployment = client.get_yaml("/deployments/web").await.unwrap();
+        assert_eq!(web, Deployment { name: "web".to_string(), replicas: 3 });
+        
+        let scaled = Deployment { replicas: 5, ..web };
+        let updated: Deployment = client.put_yaml("/deployments/web", &scaled).await.unwrap();
+        assert_eq!(updated, scaled);
+        
+        let missing = client.delete_yaml::<Deployment>("/deployments/gone").await;
+        assert!(matches!(missing, Err(HttpError::ResponseError { status: StatusCode::NOT_FOUND, .. })));
+    }
+}